@@ -6,9 +6,12 @@
 use crate::{
     config::{BaseComponentConfig, Config},
     core::InputData,
+    themes::OutputTarget,
 };
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Terminal color support level
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -53,6 +56,8 @@ pub struct TerminalCapabilities {
     pub supports_emoji: bool,
     /// Whether terminal supports Nerd Font icons
     pub supports_nerd_font: bool,
+    /// Escape syntax the rendered line is embedded in (tmux, shell prompts)
+    pub output_target: OutputTarget,
 }
 
 impl TerminalCapabilities {
@@ -69,6 +74,7 @@ impl Default for TerminalCapabilities {
             color_support: ColorSupport::TrueColor,
             supports_emoji: true,
             supports_nerd_font: false,
+            output_target: OutputTarget::Ansi,
         }
     }
 }
@@ -96,7 +102,7 @@ pub struct RenderContext {
 }
 
 /// Output from a component
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ComponentOutput {
     /// The rendered text
     pub text: String,
@@ -110,6 +116,19 @@ pub struct ComponentOutput {
     pub component_name: Option<String>,
     /// Whether to show this component (empty/disabled components return None)
     pub visible: bool,
+    /// Layout priority; lower-priority segments give way first on narrow terminals
+    #[serde(default = "default_output_priority")]
+    pub priority: u8,
+    /// Narrowest width the text may be abbreviated to (0 = drop instead)
+    #[serde(default)]
+    pub min_width: usize,
+    /// Widest the text may be (0 = unlimited)
+    #[serde(default)]
+    pub max_width: usize,
+}
+
+const fn default_output_priority() -> u8 {
+    50
 }
 
 impl ComponentOutput {
@@ -122,6 +141,9 @@ impl ComponentOutput {
             text_color: None,
             component_name: None,
             visible: true,
+            priority: default_output_priority(),
+            min_width: 0,
+            max_width: 0,
         }
     }
 
@@ -135,6 +157,9 @@ impl ComponentOutput {
             text_color: None,
             component_name: None,
             visible: false,
+            priority: default_output_priority(),
+            min_width: 0,
+            max_width: 0,
         }
     }
 
@@ -170,6 +195,13 @@ impl ComponentOutput {
     pub fn set_component_name(&mut self, name: impl Into<String>) {
         self.component_name = Some(name.into());
     }
+
+    /// Copy layout priority and width limits from the component configuration
+    pub const fn set_layout(&mut self, base: &BaseComponentConfig) {
+        self.priority = base.priority;
+        self.min_width = base.min_width as usize;
+        self.max_width = base.max_width as usize;
+    }
 }
 
 /// Trait that all statusline components must implement
@@ -187,6 +219,11 @@ pub trait Component: Send + Sync {
     /// Get the base configuration for this component
     fn base_config(&self, ctx: &RenderContext) -> Option<&BaseComponentConfig>;
 
+    /// Render deadline for this component; `None` uses `components.timeout`
+    fn render_timeout(&self) -> Option<Duration> {
+        None
+    }
+
     /// Select the appropriate icon based on terminal capabilities
     fn select_icon(&self, ctx: &RenderContext) -> Option<String> {
         let config = self.base_config(ctx)?;
@@ -2,31 +2,29 @@
 //!
 //! Displays Git branch information with optional status indicators.
 
-use std::collections::HashMap;
 use std::fmt::Write as _;
-use std::path::{Path, PathBuf};
-use std::sync::Mutex;
-use std::time::{Duration, Instant};
+use std::path::PathBuf;
+use std::time::Duration;
 
 use super::base::{Component, ComponentFactory, ComponentOutput, RenderContext};
 use crate::config::{BaseComponentConfig, BranchComponentConfig, Config};
-use crate::git::{GitCollectionOptions, GitInfo, GitService};
+use crate::git::{
+    GitCollectionOptions, GitDiffStat, GitInfo, GitInfoCache, GitOperationStatus, GitService,
+    GitVersionInfo,
+};
 use async_trait::async_trait;
+use chrono::Utc;
 use tokio::task;
 
 /// Branch component
 pub struct BranchComponent {
     config: BranchComponentConfig,
-    git_cache: Mutex<HashMap<PathBuf, CachedGitEntry>>,
 }
 
 impl BranchComponent {
     #[must_use]
-    pub fn new(config: BranchComponentConfig) -> Self {
-        Self {
-            config,
-            git_cache: Mutex::new(HashMap::new()),
-        }
+    pub const fn new(config: BranchComponentConfig) -> Self {
+        Self { config }
     }
 
     fn resolve_repo_path(ctx: &RenderContext) -> Option<PathBuf> {
@@ -38,77 +36,84 @@ impl BranchComponent {
     }
 
     async fn load_git_info(&self, ctx: &RenderContext) -> Option<GitInfo> {
-        let repo_path = Self::resolve_repo_path(ctx)?;
-        let performance = self.config.performance.clone();
-        let status_config = self.config.status.clone();
-        let include_status = self.status_required();
-        let include_stash = status_config.show_stash_count;
+        let options = GitCollectionOptions {
+            include_status: self.status_required(),
+            include_stash: self.config.status.show_stash_count,
+            include_operation: self.config.status.show_operation,
+            include_version: self.config.version.is_enabled(),
+            include_diff: self.config.status.show_diff_stat,
+        };
+        self.load_git_info_with_options(ctx, options).await
+    }
 
-        if performance.enable_cache {
-            if let Some(info) = self.cached_git_info(repo_path.as_path()) {
-                return Some(info);
-            }
-        }
+    /// Commit and tag information for the `session.git.*` widget namespace
+    pub(crate) async fn load_version_info(&self, ctx: &RenderContext) -> Option<GitVersionInfo> {
+        let options = GitCollectionOptions {
+            include_status: false,
+            include_stash: false,
+            include_operation: false,
+            include_version: true,
+            include_diff: false,
+        };
+        let info = self.load_git_info_with_options(ctx, options).await?;
+        info.is_repo.then_some(info.version)
+    }
 
-        let enable_cache = performance.enable_cache;
-        let cache_ttl = Duration::from_millis(performance.cache_ttl);
-        let path_for_store = repo_path.clone();
+    async fn load_git_info_with_options(
+        &self,
+        ctx: &RenderContext,
+        mut options: GitCollectionOptions,
+    ) -> Option<GitInfo> {
+        let repo_path = Self::resolve_repo_path(ctx)?;
+        let performance = self.config.performance.clone();
+        let include_operation = options.include_operation;
+        let diff_base = self.config.status.diff_base.clone();
+        // preview 模式只读缓存,不写盘
+        let persist = !ctx.preview_mode;
+        let debug = ctx.config.debug;
 
         let result = task::spawn_blocking(move || {
-            let service = GitService::discover(repo_path)?;
-
-            let mut options = GitCollectionOptions {
-                include_status,
-                include_stash,
-                include_operation: false,
-                include_version: false,
-            };
-
-            if performance.skip_on_large_repo {
-                let entry_count = service.estimate_workdir_entries() as u64;
-                if entry_count > performance.large_repo_threshold {
-                    options.include_status = false;
-                    options.include_stash = false;
+            let service = GitService::discover(repo_path)?.with_diff_base(diff_base);
+
+            let cache = GitInfoCache::new();
+            let cache_ttl = Duration::from_millis(performance.cache_ttl);
+            if performance.enable_cache {
+                if let Some(info) = cache.load(&service, options, cache_ttl) {
+                    return Ok(info);
                 }
             }
+            // 缓存以请求的选项为键,大仓库降级不影响命中
+            let requested = options;
+
+            let large_repo = performance.skip_on_large_repo
+                && service.estimate_workdir_entries() as u64 > performance.large_repo_threshold;
+            if large_repo {
+                options.include_status = false;
+                options.include_stash = false;
+                options.include_diff = false;
+            } else if include_operation && service.operation_status().is_active() {
+                // 进行中的操作需要工作区状态来统计冲突数
+                options.include_status = true;
+            }
 
-            Ok::<GitInfo, anyhow::Error>(service.collect_info_with_options(&options))
+            let info = service.collect_info_with_options(&options);
+            if performance.enable_cache && persist && !cache_ttl.is_zero() {
+                if let Err(err) = cache.store(&service, requested, &info) {
+                    if debug {
+                        eprintln!("[statusline] failed to cache git info: {err}");
+                    }
+                }
+            }
+            Ok::<GitInfo, anyhow::Error>(info)
         })
         .await;
 
         match result {
-            Ok(Ok(info)) => {
-                if enable_cache {
-                    self.store_git_info(path_for_store, info.clone(), cache_ttl);
-                }
-                Some(info)
-            }
+            Ok(Ok(info)) => Some(info),
             _ => None,
         }
     }
 
-    fn cached_git_info(&self, path: &Path) -> Option<GitInfo> {
-        let mut guard = self.git_cache.lock().ok()?;
-        let now = Instant::now();
-        if let Some(entry) = guard.get(path) {
-            if entry.expires_at > now {
-                return Some(entry.info.clone());
-            }
-        }
-        guard.remove(path);
-        None
-    }
-
-    fn store_git_info(&self, path: PathBuf, info: GitInfo, ttl: Duration) {
-        if ttl.is_zero() {
-            return;
-        }
-        let expires_at = Instant::now() + ttl;
-        if let Ok(mut guard) = self.git_cache.lock() {
-            guard.insert(path, CachedGitEntry { expires_at, info });
-        }
-    }
-
     fn prepare_branch_name(&self, raw: &str) -> String {
         let max_len = self.config.max_length.max(3) as usize;
         if raw.len() > max_len {
@@ -189,12 +194,122 @@ impl BranchComponent {
             let _ = write!(&mut result, "{}{}", icon, status.stash_count);
         }
 
+        if let Some(diff) = status.diff.as_ref().filter(|diff| diff.files_changed > 0) {
+            let icon = Self::select_status_icon(
+                ctx,
+                &self.config.status_icons.diff_emoji,
+                &self.config.status_icons.diff_nerd,
+                &self.config.status_icons.diff_text,
+            );
+            let _ = write!(
+                &mut result,
+                " {}{} +{} -{}",
+                icon, diff.files_changed, diff.insertions, diff.deletions
+            );
+        }
+
+        if let Some(operation) = &status.operation {
+            let icon = Self::select_status_icon(
+                ctx,
+                &self.config.status_icons.operation_emoji,
+                &self.config.status_icons.operation_nerd,
+                &self.config.status_icons.operation_text,
+            );
+            let _ = write!(&mut result, " {icon}{operation}");
+        }
+
+        if status.conflicts > 0 {
+            let icon = Self::select_status_icon(
+                ctx,
+                &self.config.status_icons.conflict_emoji,
+                &self.config.status_icons.conflict_nerd,
+                &self.config.status_icons.conflict_text,
+            );
+            let _ = write!(&mut result, " {}{}", icon, status.conflicts);
+        }
+
         result
     }
 
+    /// Append short SHA, nearest tag and last-commit age as configured
+    fn append_version(
+        &self,
+        result: &mut String,
+        info: &GitInfo,
+        show_commit: bool,
+        ctx: &RenderContext,
+    ) {
+        let version = &self.config.version;
+
+        if show_commit {
+            let icon = Self::select_status_icon(
+                ctx,
+                &version.commit_emoji,
+                &version.commit_nerd,
+                &version.commit_text,
+            );
+            let _ = write!(result, " {}{}", icon, info.version.short_commit_id);
+        }
+
+        if let Some(tag) = info.version.tag_label().filter(|_| version.show_tag) {
+            let icon = Self::select_status_icon(
+                ctx,
+                &version.tag_emoji,
+                &version.tag_nerd,
+                &version.tag_text,
+            );
+            let _ = write!(result, " {icon}{tag}");
+        }
+
+        if let Some(age) = version
+            .show_age
+            .then(|| info.version.relative_age(Utc::now().timestamp()))
+            .flatten()
+        {
+            let icon = Self::select_status_icon(
+                ctx,
+                &version.age_emoji,
+                &version.age_nerd,
+                &version.age_text,
+            );
+            let _ = write!(result, " {icon}{age}");
+        }
+    }
+
+    /// Label for the in-progress operation, e.g. `REBASE 3/7`
+    fn describe_operation(&self, operation: &GitOperationStatus) -> Option<String> {
+        let labels = &self.config.operation_labels;
+        let label = if operation.rebasing {
+            &labels.rebase
+        } else if operation.merging {
+            &labels.merge
+        } else if operation.cherry_pick {
+            &labels.cherry_pick
+        } else if operation.reverting {
+            &labels.revert
+        } else if operation.bisecting {
+            &labels.bisect
+        } else {
+            return None;
+        };
+
+        Some(match (operation.step, operation.total) {
+            (Some(step), Some(total)) if operation.rebasing => format!("{label} {step}/{total}"),
+            _ => label.clone(),
+        })
+    }
+
     /// Get the appropriate color based on branch status
     fn get_branch_color(&self, status: &BranchStatus) -> &str {
-        if status.is_dirty {
+        if status.operation.is_some() || status.conflicts > 0 {
+            &self.config.status_colors.operation
+        } else if status
+            .diff
+            .as_ref()
+            .is_some_and(|diff| diff.lines_changed() >= self.config.status.diff_large_threshold)
+        {
+            &self.config.status_colors.diff_large
+        } else if status.is_dirty {
             &self.config.status_colors.dirty
         } else {
             &self.config.status_colors.clean
@@ -243,6 +358,9 @@ struct BranchStatus {
     ahead: i32,
     behind: i32,
     stash_count: i32,
+    operation: Option<String>,
+    conflicts: i32,
+    diff: Option<GitDiffStat>,
 }
 
 #[async_trait]
@@ -261,7 +379,8 @@ impl Component for BranchComponent {
         }
 
         // 优先尝试从stdin输入获取分支信息(适用于有git字段的情况)
-        if self.config.performance.lazy_load_status {
+        // stdin 不含提交/标签信息,启用版本显示时直接走 libgit2
+        if self.config.performance.lazy_load_status && !self.config.version.is_enabled() {
             if let Some((name, status)) = self.get_branch_info(ctx) {
                 let formatted = self.format_branch(name, &status, ctx);
                 let color = self.get_branch_color(&status).to_string();
@@ -299,6 +418,12 @@ impl Component for BranchComponent {
     fn base_config(&self, _ctx: &RenderContext) -> Option<&BaseComponentConfig> {
         Some(&self.config.base)
     }
+
+    fn render_timeout(&self) -> Option<Duration> {
+        Some(Duration::from_millis(u64::from(
+            self.config.performance.git_timeout,
+        )))
+    }
 }
 
 impl BranchComponent {
@@ -328,13 +453,35 @@ impl BranchComponent {
     }
 
     fn render_from_git_info(&self, ctx: &RenderContext, info: &GitInfo) -> ComponentOutput {
+        let status_config = &self.config.status;
         let mut status = BranchStatus::default();
-        status.is_dirty = !info.status.clean;
-        status.ahead = Self::usize_to_i32(info.branch.ahead);
-        status.behind = Self::usize_to_i32(info.branch.behind);
+        // 进行中的操作会强制采集工作区状态,但那只用来统计冲突
+        if self.status_required() {
+            status.is_dirty = !info.status.clean;
+            status.ahead = Self::usize_to_i32(info.branch.ahead);
+            status.behind = Self::usize_to_i32(info.branch.behind);
+        }
         status.stash_count = Self::usize_to_i32(info.stash.count);
+        if status_config.show_operation {
+            status.operation = self.describe_operation(&info.operation);
+            status.conflicts = Self::usize_to_i32(info.status.conflicted);
+        }
+        if status_config.show_diff_stat {
+            status.diff.clone_from(&info.diff);
+        }
 
-        let branch_name = self.prepare_branch_name(&info.branch.current);
+        // rebase 期间 HEAD 是分离的,显示正在变基的分支名
+        let show_commit = self.config.version.show_commit
+            && info.branch.detached
+            && !info.version.short_commit_id.is_empty();
+        let current = match info.operation.head_name.as_deref() {
+            Some(head_name) if info.branch.detached => head_name,
+            // 短 SHA 单独追加,分支名只保留 HEAD
+            _ if show_commit => "HEAD",
+            _ => &info.branch.current,
+        };
+        let mut branch_name = self.prepare_branch_name(current);
+        self.append_version(&mut branch_name, info, show_commit, ctx);
         let text = self.format_branch(branch_name, &status, ctx);
         let icon_color = self.get_branch_color(&status).to_string();
 
@@ -365,17 +512,12 @@ impl ComponentFactory for BranchComponentFactory {
     }
 }
 
-#[derive(Clone)]
-struct CachedGitEntry {
-    expires_at: Instant,
-    info: GitInfo,
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::components::TerminalCapabilities;
     use crate::core::{GitInfo, InputData, WorkspaceInfo, WorktreeInfo};
+    use std::path::Path;
     use std::sync::Arc;
 
     #[allow(clippy::field_reassign_with_default)]
@@ -579,4 +721,141 @@ mod tests {
             Some(Path::new("/workspace/worktrees/feature-x"))
         );
     }
+
+    #[test]
+    fn test_branch_shows_rebase_progress_and_conflicts() {
+        let mut config = Config::default();
+        config.terminal.force_text = true;
+        let ctx = RenderContext {
+            input: Arc::new(InputData::default()),
+            config: Arc::new(config),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: false,
+        };
+
+        let mut info = crate::git::GitInfo::default();
+        info.branch.current = "HEAD".to_string();
+        info.branch.detached = true;
+        info.status.clean = false;
+        info.status.conflicted = 2;
+        info.operation.rebasing = true;
+        info.operation.step = Some(3);
+        info.operation.total = Some(7);
+        info.operation.head_name = Some("feature".to_string());
+        info.branch.ahead = 1;
+
+        let component = BranchComponent::new(BranchComponentConfig::default());
+        let output = component.render_from_git_info(&ctx, &info);
+        assert_eq!(output.text, "feature REBASE 3/7 [!]2");
+        assert_eq!(output.icon_color.as_deref(), Some("red"));
+
+        let component = BranchComponent::new(build_branch_config(|config| {
+            config.status.show_operation = false;
+        }));
+        let output = component.render_from_git_info(&ctx, &info);
+        assert_eq!(output.text, "feature");
+
+        let component = BranchComponent::new(build_branch_config(|config| {
+            config.status.show_dirty = true;
+        }));
+        let output = component.render_from_git_info(&ctx, &info);
+        assert_eq!(output.text, "feature[*][↑]1 REBASE 3/7 [!]2");
+    }
+
+    fn commit_file(repo: &git2::Repository, name: &str) -> anyhow::Result<git2::Oid> {
+        use anyhow::Context as _;
+        let workdir = repo.workdir().context("workdir")?;
+        std::fs::write(workdir.join(name), name)?;
+        let mut index = repo.index()?;
+        index.add_path(Path::new(name))?;
+        index.write()?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let signature = git2::Signature::now("tester", "tester@example.com")?;
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<_> = parent.iter().collect();
+        Ok(repo.commit(Some("HEAD"), &signature, &signature, name, &tree, &parents)?)
+    }
+
+    #[tokio::test]
+    async fn test_branch_shows_commit_tag_and_age() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let repo = git2::Repository::init(temp.path())?;
+        let tagged = commit_file(&repo, "a.txt")?;
+        repo.tag_lightweight("v1.2.0", &repo.find_object(tagged, None)?, false)?;
+        commit_file(&repo, "b.txt")?;
+        let head = commit_file(&repo, "c.txt")?;
+        repo.set_head_detached(head)?;
+
+        let mut config = Config::default();
+        config.terminal.force_text = true;
+        let input = build_input(|input| {
+            input.cwd = Some(temp.path().to_string_lossy().into_owned());
+        });
+        let ctx = RenderContext {
+            input: Arc::new(input),
+            config: Arc::new(config),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: true,
+        };
+
+        let component = BranchComponent::new(build_branch_config(|config| {
+            config.version.show_commit = true;
+            config.version.show_tag = true;
+            config.version.show_age = true;
+        }));
+        let output = component.render(&ctx).await;
+        let short = &head.to_string()[..7];
+        assert_eq!(output.text, format!("HEAD @{short} #v1.2.0+2 just now"));
+
+        let version = component
+            .load_version_info(&ctx)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("missing version info"))?;
+        assert_eq!(version.tag_label().as_deref(), Some("v1.2.0+2"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_branch_shows_diff_stat_against_base() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let repo = git2::Repository::init(temp.path())?;
+        let base = commit_file(&repo, "a.txt")?;
+        repo.branch("main", &repo.find_commit(base)?, true)?;
+        repo.set_head("refs/heads/main")?;
+        repo.branch("feature", &repo.find_commit(base)?, false)?;
+        repo.set_head("refs/heads/feature")?;
+        commit_file(&repo, "b.txt")?;
+        commit_file(&repo, "c.txt")?;
+
+        let mut config = Config::default();
+        config.terminal.force_text = true;
+        let input = build_input(|input| {
+            input.cwd = Some(temp.path().to_string_lossy().into_owned());
+        });
+        let ctx = RenderContext {
+            input: Arc::new(input),
+            config: Arc::new(config),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: true,
+        };
+
+        let component = BranchComponent::new(build_branch_config(|config| {
+            config.performance.lazy_load_status = false;
+            config.status.show_diff_stat = true;
+            config.status.diff_base = Some("main".to_string());
+        }));
+        let output = component.render(&ctx).await;
+        assert_eq!(output.text, "feature Δ2 +2 -0");
+        assert_eq!(output.icon_color.as_deref(), Some("green"));
+
+        let component = BranchComponent::new(build_branch_config(|config| {
+            config.performance.lazy_load_status = false;
+            config.status.show_diff_stat = true;
+            config.status.diff_base = Some("main".to_string());
+            config.status.diff_large_threshold = 2;
+        }));
+        let output = component.render(&ctx).await;
+        assert_eq!(output.icon_color.as_deref(), Some("magenta"));
+        Ok(())
+    }
 }
@@ -0,0 +1,240 @@
+//! Budget component implementation
+//!
+//! Sums the cost of all sessions in the current day / week / month and shows
+//! it against a configured limit with a progress bar and threshold colors.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use super::base::{Component, ComponentFactory, ComponentOutput, RenderContext};
+use crate::config::{BaseComponentConfig, BudgetComponentConfig, Config};
+use crate::storage;
+
+/// Budget component
+pub struct BudgetComponent {
+    config: BudgetComponentConfig,
+}
+
+impl BudgetComponent {
+    #[must_use]
+    pub const fn new(config: BudgetComponentConfig) -> Self {
+        Self { config }
+    }
+
+    async fn fetch_spent(&self, ctx: &RenderContext) -> f64 {
+        if let Some(spent) = ctx
+            .input
+            .extra
+            .get("__mock__")
+            .and_then(|mock| mock.get("budgetSpent"))
+            .and_then(serde_json::Value::as_f64)
+        {
+            return spent;
+        }
+
+        // preview 模式不读 storage,理由同 tokens / usage 组件
+        if ctx.preview_mode {
+            return 0.0;
+        }
+
+        let since =
+            storage::budget::period_start(self.config.period, &self.config.timezone, Utc::now());
+        match storage::get_period_spend(
+            since,
+            ctx.input.session_id.as_deref(),
+            Duration::from_millis(self.config.cache_ttl),
+        )
+        .await
+        {
+            Ok(spent) => spent,
+            Err(err) => {
+                eprintln!("[budget] failed to load period spend: {err}");
+                0.0
+            }
+        }
+    }
+
+    fn build_progress_bar(&self, percentage: f64) -> Option<String> {
+        if !self.config.show_progress_bar {
+            return None;
+        }
+
+        let chars = &self.config.progress_bar_chars;
+        let first = |value: &str, fallback: char| value.chars().next().unwrap_or(fallback);
+        let width = self.config.progress_width.max(1) as usize;
+        // 超出预算时整条改用 backup 字符,一眼可见
+        let filled_char = if percentage >= 100.0 {
+            first(&chars.backup, '▓')
+        } else {
+            first(&chars.filled, '█')
+        };
+
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss
+        )]
+        let filled = ((percentage.clamp(0.0, 100.0) / 100.0) * width as f64).round() as usize;
+
+        let mut bar = String::with_capacity(width * 3 + 2);
+        bar.push(first(&chars.left_bracket, '['));
+        for idx in 0..width {
+            bar.push(if idx < filled {
+                filled_char
+            } else {
+                first(&chars.empty, '░')
+            });
+        }
+        bar.push(first(&chars.right_bracket, ']'));
+        Some(bar)
+    }
+
+    fn select_color(&self, percentage: f64) -> String {
+        let thresholds = &self.config.thresholds;
+
+        if percentage >= thresholds.danger {
+            self.config.colors.danger.clone()
+        } else if percentage >= thresholds.warning {
+            self.config.colors.warning.clone()
+        } else {
+            self.config.colors.safe.clone()
+        }
+    }
+
+    fn format_cost(&self, cost: f64) -> String {
+        format!("${:.1$}", cost, self.config.precision as usize)
+    }
+}
+
+#[async_trait]
+impl Component for BudgetComponent {
+    fn name(&self) -> &'static str {
+        "budget"
+    }
+
+    fn is_enabled(&self, _ctx: &RenderContext) -> bool {
+        self.config.base.enabled
+    }
+
+    async fn render(&self, ctx: &RenderContext) -> ComponentOutput {
+        if !self.is_enabled(ctx) {
+            return ComponentOutput::hidden();
+        }
+
+        let spent = self.fetch_spent(ctx).await;
+        let icon = self.select_icon(ctx).unwrap_or_default();
+
+        if self.config.limit <= 0.0 {
+            return ComponentOutput::new(self.format_cost(spent))
+                .with_icon(icon)
+                .with_icon_color(self.config.base.icon_color.clone())
+                .with_text_color(self.config.base.text_color.clone());
+        }
+
+        let percentage = (spent / self.config.limit * 100.0).clamp(0.0, 999.9);
+        let mut parts = Vec::new();
+
+        if let Some(bar) = self.build_progress_bar(percentage) {
+            parts.push(bar);
+        }
+        parts.push(format!(
+            "{}/{}",
+            self.format_cost(spent),
+            self.format_cost(self.config.limit)
+        ));
+        if self.config.show_percentage {
+            parts.push(format!("{percentage:.0}%"));
+        }
+
+        let color = self.select_color(percentage);
+        ComponentOutput::new(parts.join(" "))
+            .with_icon(icon)
+            .with_icon_color(color.clone())
+            .with_text_color(color)
+    }
+
+    fn base_config(&self, _ctx: &RenderContext) -> Option<&BaseComponentConfig> {
+        Some(&self.config.base)
+    }
+}
+
+/// Factory for creating Budget components
+pub struct BudgetComponentFactory;
+
+impl ComponentFactory for BudgetComponentFactory {
+    fn create(&self, config: &Config) -> Box<dyn Component> {
+        Box::new(BudgetComponent::new(config.components.budget.clone()))
+    }
+
+    fn name(&self) -> &'static str {
+        "budget"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::TerminalCapabilities;
+    use crate::core::InputData;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn context_with_spent(spent: f64) -> RenderContext {
+        let input = InputData {
+            extra: json!({ "__mock__": { "budgetSpent": spent } }),
+            ..InputData::default()
+        };
+        RenderContext {
+            input: Arc::new(input),
+            config: Arc::new(Config::default()),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: false,
+        }
+    }
+
+    fn budget_config(configure: impl FnOnce(&mut BudgetComponentConfig)) -> BudgetComponentConfig {
+        let mut config = BudgetComponentConfig {
+            limit: 10.0,
+            progress_width: 4,
+            ..BudgetComponentConfig::default()
+        };
+        configure(&mut config);
+        config
+    }
+
+    #[tokio::test]
+    async fn test_budget_renders_spent_against_limit() {
+        let component = BudgetComponent::new(budget_config(|config| {
+            config.show_percentage = true;
+        }));
+
+        let output = component.render(&context_with_spent(2.5)).await;
+        assert!(output.visible);
+        assert_eq!(output.text, "[█░░░] $2.50/$10.00 25%");
+        assert_eq!(output.text_color.as_deref(), Some("green"));
+    }
+
+    #[tokio::test]
+    async fn test_budget_colors_follow_thresholds() {
+        let component = BudgetComponent::new(budget_config(|_| {}));
+
+        let warning = component.render(&context_with_spent(8.0)).await;
+        assert_eq!(warning.text_color.as_deref(), Some("yellow"));
+
+        let exceeded = component.render(&context_with_spent(12.0)).await;
+        assert_eq!(exceeded.text, "[▓▓▓▓] $12.00/$10.00");
+        assert_eq!(exceeded.text_color.as_deref(), Some("red"));
+    }
+
+    #[tokio::test]
+    async fn test_budget_without_limit_shows_spend_only() {
+        let component = BudgetComponent::new(budget_config(|config| {
+            config.limit = 0.0;
+        }));
+
+        let output = component.render(&context_with_spent(1.234)).await;
+        assert_eq!(output.text, "$1.23");
+    }
+}
@@ -0,0 +1,219 @@
+//! Cache component implementation
+//!
+//! Shows how much of the prompt was served from Anthropic's prompt cache,
+//! for the newest assistant turn and over the whole session, so tooling that
+//! keeps busting the cache (reordered system prompts, changing tool lists)
+//! stands out.
+
+use async_trait::async_trait;
+
+use super::base::{Component, ComponentFactory, ComponentOutput, RenderContext};
+use crate::config::{BaseComponentConfig, CacheComponentConfig, Config};
+use crate::storage::{self, transcript::TokenUsage, TokenHistory};
+
+/// Cache component
+pub struct CacheComponent {
+    config: CacheComponentConfig,
+}
+
+impl CacheComponent {
+    #[must_use]
+    pub const fn new(config: CacheComponentConfig) -> Self {
+        Self { config }
+    }
+
+    /// Usage of the newest assistant turn
+    async fn load_turn(ctx: &RenderContext) -> Option<TokenUsage> {
+        // mock 数据只走 __mock__,不碰真实 storage
+        if let Some(mock) = ctx.input.extra.get("__mock__") {
+            return mock
+                .get("tokensHistory")
+                .and_then(|history| serde_json::from_value::<TokenHistory>(history.clone()).ok())
+                .map(|history| history.usage());
+        }
+
+        // preview 模式不读 storage,理由同 tokens / usage 组件
+        if ctx.preview_mode {
+            return None;
+        }
+        let session_id = ctx.input.session_id.as_deref()?;
+        storage::get_session_tokens(session_id)
+            .await
+            .ok()
+            .flatten()
+            .map(|history| history.usage())
+    }
+
+    /// Usage summed over every assistant turn of the session
+    async fn load_session(ctx: &RenderContext) -> Option<TokenUsage> {
+        if let Some(mock) = ctx.input.extra.get("__mock__") {
+            return mock
+                .get("sessionUsage")
+                .and_then(|usage| serde_json::from_value(usage.clone()).ok());
+        }
+
+        if ctx.preview_mode {
+            return None;
+        }
+        let session_id = ctx.input.session_id.as_deref()?;
+        let path = ctx.input.transcript_path.as_deref()?;
+        storage::get_transcript_aggregates(session_id, path)
+            .await
+            .ok()
+            .flatten()
+            .map(|aggregates| aggregates.usage)
+    }
+
+    fn select_color(&self, ratio: f64) -> String {
+        let thresholds = &self.config.thresholds;
+
+        if ratio < thresholds.danger {
+            self.config.colors.danger.clone()
+        } else if ratio < thresholds.warning {
+            self.config.colors.warning.clone()
+        } else {
+            self.config.colors.safe.clone()
+        }
+    }
+}
+
+#[async_trait]
+impl Component for CacheComponent {
+    fn name(&self) -> &'static str {
+        "cache"
+    }
+
+    fn is_enabled(&self, _ctx: &RenderContext) -> bool {
+        self.config.base.enabled
+    }
+
+    async fn render(&self, ctx: &RenderContext) -> ComponentOutput {
+        if !self.is_enabled(ctx) {
+            return ComponentOutput::hidden();
+        }
+
+        let turn = if self.config.show_turn {
+            Self::load_turn(ctx)
+                .await
+                .as_ref()
+                .and_then(TokenUsage::cache_hit_ratio)
+        } else {
+            None
+        };
+        let session = if self.config.show_session {
+            Self::load_session(ctx)
+                .await
+                .as_ref()
+                .and_then(TokenUsage::cache_hit_ratio)
+        } else {
+            None
+        };
+
+        let mut parts = Vec::new();
+        if let Some(ratio) = turn {
+            parts.push(format!("{ratio:.0}%"));
+        }
+        if let Some(ratio) = session {
+            parts.push(format!("session {ratio:.0}%"));
+        }
+        // 颜色取两者中较差的一个,刚被打破的缓存和长期偏低都要显眼
+        let Some(worst) = turn.into_iter().chain(session).reduce(f64::min) else {
+            return ComponentOutput::hidden();
+        };
+
+        let color = self.select_color(worst);
+        ComponentOutput::new(parts.join(" "))
+            .with_icon(self.select_icon(ctx).unwrap_or_default())
+            .with_icon_color(color.clone())
+            .with_text_color(color)
+    }
+
+    fn base_config(&self, _ctx: &RenderContext) -> Option<&BaseComponentConfig> {
+        Some(&self.config.base)
+    }
+}
+
+/// Factory for creating Cache components
+pub struct CacheComponentFactory;
+
+impl ComponentFactory for CacheComponentFactory {
+    fn create(&self, config: &Config) -> Box<dyn Component> {
+        Box::new(CacheComponent::new(config.components.cache.clone()))
+    }
+
+    fn name(&self) -> &'static str {
+        "cache"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::TerminalCapabilities;
+    use crate::core::InputData;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+
+    fn context_with_mock(mock: &Value) -> RenderContext {
+        let input = InputData {
+            extra: json!({ "__mock__": mock }),
+            ..InputData::default()
+        };
+        RenderContext {
+            input: Arc::new(input),
+            config: Arc::new(Config::default()),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_renders_turn_and_session_ratios() {
+        let component = CacheComponent::new(CacheComponentConfig::default());
+        let ctx = context_with_mock(&json!({
+            "tokensHistory": {
+                "input": 100,
+                "cache_creation_input": 900,
+                "cache_read_input": 9000
+            },
+            "sessionUsage": {
+                "input": 1000,
+                "cache_creation_input": 4000,
+                "cache_read_input": 15000
+            }
+        }));
+
+        let output = component.render(&ctx).await;
+        assert!(output.visible);
+        assert_eq!(output.text, "90% session 75%");
+        assert_eq!(output.text_color.as_deref(), Some("green"));
+    }
+
+    #[tokio::test]
+    async fn test_cache_busted_turn_turns_danger() {
+        let component = CacheComponent::new(CacheComponentConfig::default());
+        let ctx = context_with_mock(&json!({
+            "tokensHistory": { "input": 100, "cache_creation_input": 9900 },
+            "sessionUsage": { "input": 100, "cache_creation_input": 3900, "cache_read_input": 6000 }
+        }));
+
+        let output = component.render(&ctx).await;
+        assert_eq!(output.text, "0% session 60%");
+        assert_eq!(output.text_color.as_deref(), Some("red"));
+
+        let component = CacheComponent::new(CacheComponentConfig {
+            show_turn: false,
+            ..CacheComponentConfig::default()
+        });
+        let output = component.render(&ctx).await;
+        assert_eq!(output.text, "session 60%");
+        assert_eq!(output.text_color.as_deref(), Some("yellow"));
+    }
+
+    #[tokio::test]
+    async fn test_cache_hidden_without_usage() {
+        let component = CacheComponent::new(CacheComponentConfig::default());
+        let output = component.render(&context_with_mock(&json!({}))).await;
+        assert!(!output.visible);
+    }
+}
@@ -0,0 +1,490 @@
+//! Custom component implementation
+//!
+//! Runs a user-configured external command. The command receives the current
+//! `InputData` as JSON on stdin and prints a JSON object on stdout:
+//!
+//! ```json
+//! {"text": "prod-cluster", "icon": "⎈", "icon_color": "cyan", "text_color": "white", "visible": true}
+//! ```
+//!
+//! Plain (non-JSON) stdout is accepted as well; its first line becomes the text.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::task;
+
+use super::base::{Component, ComponentFactory, ComponentOutput, RenderContext};
+use crate::config::{BaseComponentConfig, Config, CustomComponentConfig};
+use crate::storage;
+use crate::utils::process::run_with_timeout;
+
+/// Payload printed by a custom component command
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+struct CustomCommandOutput {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    icon: Option<String>,
+    #[serde(default)]
+    icon_color: Option<String>,
+    #[serde(default)]
+    text_color: Option<String>,
+    #[serde(default = "default_visible")]
+    visible: bool,
+}
+
+impl Default for CustomCommandOutput {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            icon: None,
+            icon_color: None,
+            text_color: None,
+            visible: true,
+        }
+    }
+}
+
+/// Extra time the generator grants on top of the command timeout
+const TIMEOUT_GRACE_MS: u64 = 200;
+
+const fn default_visible() -> bool {
+    true
+}
+
+/// On-disk cache entry so `cache_ttl` survives across statusline processes
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct CachedCommandOutput {
+    updated_at: i64,
+    output: CustomCommandOutput,
+}
+
+/// Custom component
+pub struct CustomComponent {
+    name: String,
+    config: Option<CustomComponentConfig>,
+}
+
+impl CustomComponent {
+    #[must_use]
+    pub const fn new(name: String, config: Option<CustomComponentConfig>) -> Self {
+        Self { name, config }
+    }
+
+    fn working_dir(ctx: &RenderContext) -> Option<PathBuf> {
+        ctx.input
+            .project_dir()
+            .or(ctx.input.cwd.as_deref())
+            .map(PathBuf::from)
+            .filter(|path| path.is_dir())
+    }
+
+    /// Cache file for one invocation; keyed by everything that can change the
+    /// command's output, including the session it reads from stdin
+    fn cache_path(
+        &self,
+        config: &CustomComponentConfig,
+        cwd: Option<&Path>,
+        session_id: Option<&str>,
+    ) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        config.command.hash(&mut hasher);
+        config.args.hash(&mut hasher);
+        config
+            .env
+            .iter()
+            .collect::<BTreeMap<_, _>>()
+            .hash(&mut hasher);
+        cwd.hash(&mut hasher);
+        session_id.hash(&mut hasher);
+        let file_name = format!(
+            "{}-{:016x}.json",
+            sanitize_name(&self.name),
+            hasher.finish()
+        );
+
+        storage::cache_dir().join("components").join(file_name)
+    }
+
+    fn read_cache(path: &Path) -> Option<CachedCommandOutput> {
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn write_cache(path: &Path, output: &CustomCommandOutput) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let entry = CachedCommandOutput {
+            updated_at: Utc::now().timestamp_millis(),
+            output: output.clone(),
+        };
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec(&entry)?)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    async fn execute(
+        config: &CustomComponentConfig,
+        ctx: &RenderContext,
+        cwd: Option<PathBuf>,
+    ) -> Result<CustomCommandOutput> {
+        let payload = serde_json::to_vec(ctx.input.as_ref())?;
+        let mut command = Command::new(&config.command);
+        ctx.input.env.apply_to(&mut command);
+        command.args(&config.args).envs(&config.env);
+        if let Some(dir) = cwd {
+            command.current_dir(dir);
+        }
+        let timeout = Duration::from_millis(config.timeout);
+
+        let output =
+            task::spawn_blocking(move || run_with_timeout(command, Some(&payload), timeout))
+                .await
+                .map_err(|err| anyhow!("custom command task failed: {err}"))??;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "'{}' exited with {}: {}",
+                config.command,
+                output.status,
+                output.stderr.trim()
+            ));
+        }
+
+        Ok(parse_command_output(&output.stdout))
+    }
+
+    fn build_output(
+        &self,
+        config: &CustomComponentConfig,
+        payload: CustomCommandOutput,
+        ctx: &RenderContext,
+    ) -> ComponentOutput {
+        if !payload.visible || payload.text.is_empty() && payload.icon.is_none() {
+            return ComponentOutput::hidden();
+        }
+
+        let icon = payload
+            .icon
+            .or_else(|| self.select_icon(ctx))
+            .filter(|icon| !icon.is_empty());
+
+        let mut output = ComponentOutput::new(payload.text)
+            .with_icon_color(
+                payload
+                    .icon_color
+                    .unwrap_or_else(|| config.base.icon_color.clone()),
+            )
+            .with_text_color(
+                payload
+                    .text_color
+                    .unwrap_or_else(|| config.base.text_color.clone()),
+            );
+        if let Some(icon) = icon {
+            output = output.with_icon(icon);
+        }
+        output
+    }
+}
+
+#[async_trait]
+impl Component for CustomComponent {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_enabled(&self, _ctx: &RenderContext) -> bool {
+        self.config
+            .as_ref()
+            .is_some_and(|config| config.base.enabled && !config.command.trim().is_empty())
+    }
+
+    async fn render(&self, ctx: &RenderContext) -> ComponentOutput {
+        if !self.is_enabled(ctx) {
+            return ComponentOutput::hidden();
+        }
+        let Some(config) = self.config.as_ref() else {
+            return ComponentOutput::hidden();
+        };
+
+        let cwd = Self::working_dir(ctx);
+        let cache_path = self.cache_path(config, cwd.as_deref(), ctx.input.session_id.as_deref());
+        let cached = if config.cache_ttl > 0 {
+            Self::read_cache(&cache_path)
+        } else {
+            None
+        };
+
+        if let Some(entry) = cached.as_ref() {
+            let age = Utc::now().timestamp_millis() - entry.updated_at;
+            if u64::try_from(age).is_ok_and(|age| age < config.cache_ttl) {
+                return self.build_output(config, entry.output.clone(), ctx);
+            }
+        }
+
+        match Self::execute(config, ctx, cwd).await {
+            Ok(payload) => {
+                if config.cache_ttl > 0 && !ctx.preview_mode {
+                    if let Err(err) = Self::write_cache(&cache_path, &payload) {
+                        if ctx.config.debug {
+                            eprintln!(
+                                "[statusline] custom component '{}' cache write failed: {err}",
+                                self.name
+                            );
+                        }
+                    }
+                }
+                self.build_output(config, payload, ctx)
+            }
+            Err(err) => {
+                if ctx.config.debug {
+                    eprintln!(
+                        "[statusline] custom component '{}' failed: {err}",
+                        self.name
+                    );
+                }
+                // 命令失败时沿用上一次成功的输出，避免状态栏闪烁
+                cached.map_or_else(ComponentOutput::hidden, |entry| {
+                    self.build_output(config, entry.output, ctx)
+                })
+            }
+        }
+    }
+
+    fn base_config(&self, _ctx: &RenderContext) -> Option<&BaseComponentConfig> {
+        self.config.as_ref().map(|config| &config.base)
+    }
+
+    /// 留出余量让命令自身的超时先触发,走"沿用上次缓存"的分支
+    fn render_timeout(&self) -> Option<Duration> {
+        self.config
+            .as_ref()
+            .map(|config| Duration::from_millis(config.timeout + TIMEOUT_GRACE_MS))
+    }
+}
+
+/// Factory for creating Custom components
+pub struct CustomComponentFactory {
+    name: String,
+}
+
+impl CustomComponentFactory {
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+impl ComponentFactory for CustomComponentFactory {
+    fn create(&self, config: &Config) -> Box<dyn Component> {
+        Box::new(CustomComponent::new(
+            self.name.clone(),
+            config.components.custom.get(&self.name).cloned(),
+        ))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn parse_command_output(stdout: &str) -> CustomCommandOutput {
+    let trimmed = stdout.trim();
+    if trimmed.starts_with('{') {
+        if let Ok(parsed) = serde_json::from_str::<CustomCommandOutput>(trimmed) {
+            return parsed;
+        }
+    }
+
+    CustomCommandOutput {
+        text: trimmed
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+        ..CustomCommandOutput::default()
+    }
+}
+
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::TerminalCapabilities;
+    use crate::core::InputData;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    #[allow(clippy::field_reassign_with_default)]
+    fn build_input(configure: impl FnOnce(&mut InputData)) -> InputData {
+        let mut input = InputData::default();
+        configure(&mut input);
+        input
+    }
+
+    fn build_custom_config(command: &str, args: &[&str]) -> CustomComponentConfig {
+        CustomComponentConfig {
+            base: BaseComponentConfig {
+                enabled: true,
+                icon_color: "white".to_string(),
+                text_color: "white".to_string(),
+                emoji_icon: "⎈".to_string(),
+                nerd_icon: String::new(),
+                text_icon: "[K]".to_string(),
+                priority: 50,
+                min_width: 0,
+                max_width: 0,
+            },
+            command: command.to_string(),
+            args: args.iter().map(|arg| (*arg).to_string()).collect(),
+            env: HashMap::new(),
+            timeout: 2000,
+            cache_ttl: 0,
+            preset_key: None,
+        }
+    }
+
+    fn create_test_context(input: InputData) -> RenderContext {
+        RenderContext {
+            input: Arc::new(input),
+            config: Arc::new(Config::default()),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_command_output_json_and_plain() {
+        let parsed = parse_command_output(r#"{"text":"prod","icon_color":"red"}"#);
+        assert_eq!(parsed.text, "prod");
+        assert_eq!(parsed.icon_color.as_deref(), Some("red"));
+        assert!(parsed.visible);
+
+        let plain = parse_command_output("  staging\nignored\n");
+        assert_eq!(plain.text, "staging");
+        assert!(plain.visible);
+    }
+
+    #[test]
+    fn test_cache_path_keys_on_env_and_session() {
+        let component = CustomComponent::new("probe".to_string(), None);
+        let config = build_custom_config("kubectl", &["config", "current-context"]);
+        let mut with_env = config.clone();
+        with_env
+            .env
+            .insert("KUBECONFIG".to_string(), "/tmp/other".to_string());
+
+        let base = component.cache_path(&config, None, Some("a"));
+        assert_eq!(base, component.cache_path(&config, None, Some("a")));
+        assert_ne!(base, component.cache_path(&config, None, Some("b")));
+        assert_ne!(base, component.cache_path(&with_env, None, Some("a")));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_custom_component_reads_stdin_json() {
+        let config = build_custom_config(
+            "sh",
+            &[
+                "-c",
+                r#"grep -q '"session_id":"abc"' && echo '{"text":"ok","text_color":"green"}'"#,
+            ],
+        );
+        let component = CustomComponent::new("probe".to_string(), Some(config));
+        let ctx = create_test_context(build_input(|input| {
+            input.session_id = Some("abc".to_string());
+        }));
+
+        let output = component.render(&ctx).await;
+        assert!(output.visible);
+        assert_eq!(output.text, "ok");
+        assert_eq!(output.text_color.as_deref(), Some("green"));
+        assert_eq!(output.icon.as_deref(), Some("⎈"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_custom_component_hidden_on_failure_or_timeout() {
+        let ctx = create_test_context(InputData::default());
+
+        let failing = CustomComponent::new(
+            "fail".to_string(),
+            Some(build_custom_config("sh", &["-c", "exit 3"])),
+        );
+        assert!(!failing.render(&ctx).await.visible);
+
+        let mut slow_config = build_custom_config("sleep", &["5"]);
+        slow_config.timeout = 50;
+        let slow = CustomComponent::new("slow".to_string(), Some(slow_config));
+        assert!(!slow.render(&ctx).await.visible);
+
+        let invisible = CustomComponent::new(
+            "quiet".to_string(),
+            Some(build_custom_config(
+                "sh",
+                &["-c", r#"echo '{"text":"x","visible":false}'"#],
+            )),
+        );
+        assert!(!invisible.render(&ctx).await.visible);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_custom_component_cache_ttl_reuses_output() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        std::env::set_var("STATUSLINE_STORAGE_PATH", temp.path());
+        let counter = temp.path().join("counter");
+
+        let mut config = build_custom_config(
+            "sh",
+            &[
+                "-c",
+                r#"echo run >> "$0"; wc -l < "$0""#,
+                &counter.to_string_lossy(),
+            ],
+        );
+        config.cache_ttl = 60_000;
+        let component = CustomComponent::new("counter".to_string(), Some(config));
+        let ctx = create_test_context(InputData::default());
+
+        let first = component.render(&ctx).await;
+        let second = component.render(&ctx).await;
+        std::env::remove_var("STATUSLINE_STORAGE_PATH");
+
+        assert_eq!(first.text.trim(), "1");
+        assert_eq!(second.text.trim(), "1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_config_is_disabled() {
+        let component = CustomComponent::new("ghost".to_string(), None);
+        let ctx = create_test_context(InputData::default());
+        assert!(!component.is_enabled(&ctx));
+    }
+}
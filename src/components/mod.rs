@@ -4,9 +4,13 @@
 
 pub mod base;
 pub mod branch;
+pub mod budget;
+pub mod cache;
+pub mod custom;
 pub mod model;
 pub mod project;
 pub mod status;
+pub mod time;
 pub mod tokens;
 pub mod usage;
 
@@ -15,8 +19,12 @@ pub use base::{
     ColorSupport, Component, ComponentFactory, ComponentOutput, RenderContext, TerminalCapabilities,
 };
 pub use branch::{BranchComponent, BranchComponentFactory};
+pub use budget::{BudgetComponent, BudgetComponentFactory};
+pub use cache::{CacheComponent, CacheComponentFactory};
+pub use custom::{CustomComponent, CustomComponentFactory};
 pub use model::{ModelComponent, ModelComponentFactory};
 pub use project::{ProjectComponent, ProjectComponentFactory};
 pub use status::{StatusComponent, StatusComponentFactory};
-pub use tokens::{TokensComponent, TokensComponentFactory};
+pub use time::{TimeComponent, TimeComponentFactory};
+pub use tokens::{ContextUsage, TokensComponent, TokensComponentFactory};
 pub use usage::{UsageComponent, UsageComponentFactory};
@@ -2,16 +2,20 @@
 //!
 //! Displays the current status of Claude (ready, thinking, tool, error, etc.),
 //! falling back to STDIN metadata when transcript data is unavailable.
+//!
+//! Transcript status is folded incrementally by the session snapshot pass
+//! (see [`crate::storage::transcript`]); without a current snapshot only the
+//! tail of the transcript is read.
 
-use std::fs;
-use std::sync::Mutex;
-use std::time::SystemTime;
+use std::path::PathBuf;
 
 use async_trait::async_trait;
 use serde_json::Value;
+use tokio::task;
 
 use super::base::{Component, ComponentFactory, ComponentOutput, RenderContext};
 use crate::config::{BaseComponentConfig, Config, StatusComponentConfig};
+use crate::storage::{self, transcript::TranscriptStatus};
 
 /// Status types rendered by the component
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -73,39 +77,49 @@ impl StatusInfo {
     }
 }
 
-#[derive(Clone)]
-struct TranscriptCache {
-    mtime: SystemTime,
-    info: StatusInfo,
-}
-
 /// Status component
 pub struct StatusComponent {
     config: StatusComponentConfig,
-    transcript_cache: Mutex<Option<TranscriptCache>>,
 }
 
 impl StatusComponent {
     /// Create a new status component
     #[must_use]
     pub const fn new(config: StatusComponentConfig) -> Self {
-        Self {
-            config,
-            transcript_cache: Mutex::new(None),
-        }
+        Self { config }
     }
 
     /// Resolve status using transcript when available, otherwise fall back to stdin metadata.
-    fn resolve_status(&self, ctx: &RenderContext) -> StatusInfo {
+    async fn resolve_status(ctx: &RenderContext) -> StatusInfo {
         if let Some(path) = ctx.input.transcript_path.as_deref() {
-            if let Some(info) = self.parse_transcript_status(path, ctx) {
-                return info;
+            if let Some(status) = Self::load_transcript_status(path, ctx).await {
+                return Self::status_from_transcript(&status);
             }
         }
 
         Self::parse_status_from_input(ctx)
     }
 
+    /// Status folded by the snapshot pass, or rebuilt from the transcript tail
+    async fn load_transcript_status(path: &str, ctx: &RenderContext) -> Option<TranscriptStatus> {
+        // preview 模式不读 storage,理由同 tokens / usage 组件
+        if !ctx.preview_mode {
+            if let Some(session_id) = ctx.input.session_id.as_deref() {
+                if let Ok(Some(aggregates)) =
+                    storage::get_transcript_aggregates(session_id, path).await
+                {
+                    return Some(aggregates.status);
+                }
+            }
+        }
+
+        let path = PathBuf::from(path);
+        task::spawn_blocking(move || storage::transcript::tail_status(&path))
+            .await
+            .ok()
+            .flatten()
+    }
+
     /// Parse status from stdin metadata (legacy path)
     fn parse_status_from_input(ctx: &RenderContext) -> StatusInfo {
         if let Some(status_str) = ctx.input.extra.get("status").and_then(Value::as_str) {
@@ -127,103 +141,16 @@ impl StatusComponent {
         StatusInfo::ready()
     }
 
-    /// Parse transcript lines and infer status (mirrors TypeScript implementation)
-    fn parse_transcript_status(&self, path: &str, _ctx: &RenderContext) -> Option<StatusInfo> {
-        let metadata = fs::metadata(path).ok()?;
-        let modified = metadata.modified().ok()?;
-
-        if let Some(cache) = self
-            .transcript_cache
-            .lock()
-            .ok()
-            .and_then(|guard| guard.clone())
-        {
-            if cache.mtime == modified {
-                return Some(cache.info);
-            }
-        }
-
-        let content = fs::read_to_string(path).ok()?;
-        if content.trim().is_empty() {
-            let info = StatusInfo::ready();
-            self.memoize_transcript(modified, info.clone());
-            return Some(info);
-        }
-
-        let lines: Vec<&str> = content.lines().collect();
-        if lines.is_empty() {
-            let info = StatusInfo::ready();
-            self.memoize_transcript(modified, info.clone());
-            return Some(info);
-        }
-
-        let mut last_entry_type: Option<String> = None;
-        let mut last_stop_reason: Option<String> = None;
-        let mut assistant_error = false;
-        let mut assistant_error_detail: Option<String> = None;
-
-        for line in lines.iter().rev() {
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-
-            let Ok(value) = serde_json::from_str::<Value>(trimmed) else {
-                continue;
-            };
-
-            if last_entry_type.is_none() {
-                last_entry_type = value
-                    .get("type")
-                    .and_then(|v| v.as_str())
-                    .map(std::string::ToString::to_string);
-            }
-
-            if value.get("type").and_then(|v| v.as_str()) != Some("assistant") {
-                continue;
-            }
-
-            let Some(message) = value.get("message") else {
-                continue;
-            };
-
-            if message.get("usage").is_none() {
-                continue;
-            }
-
-            last_stop_reason = message
-                .get("stop_reason")
-                .and_then(|v| v.as_str())
-                .map(std::string::ToString::to_string);
-
-            if Self::is_error_entry(&value) {
-                assistant_error = true;
-                assistant_error_detail = Self::get_error_details(&value);
-            }
-
-            break;
-        }
-
-        let tool_name = Self::collect_recent_tool_name(&lines).filter(|name| !name.is_empty());
-
-        let info = if assistant_error {
-            StatusInfo::error(assistant_error_detail)
-        } else if let Some(reason) = last_stop_reason.as_deref() {
-            Self::parse_stop_reason(reason, tool_name)
-        } else if matches!(last_entry_type.as_deref(), Some("user")) {
+    /// Infer status from transcript facts (mirrors TypeScript implementation)
+    fn status_from_transcript(status: &TranscriptStatus) -> StatusInfo {
+        if status.error {
+            StatusInfo::error(status.error_detail.clone())
+        } else if let Some(reason) = status.stop_reason.as_deref() {
+            Self::parse_stop_reason(reason, status.recent_tool().map(str::to_string))
+        } else if matches!(status.last_entry_type.as_deref(), Some("user")) {
             StatusInfo::thinking()
         } else {
             StatusInfo::ready()
-        };
-
-        self.memoize_transcript(modified, info.clone());
-
-        Some(info)
-    }
-
-    fn memoize_transcript(&self, mtime: SystemTime, info: StatusInfo) {
-        if let Ok(mut guard) = self.transcript_cache.lock() {
-            *guard = Some(TranscriptCache { mtime, info });
         }
     }
 
@@ -317,144 +244,6 @@ impl StatusComponent {
             StatusType::Warning => self.config.colors.warning.clone(),
         }
     }
-
-    fn is_error_entry(entry: &Value) -> bool {
-        if entry
-            .get("toolUseResult")
-            .is_some_and(Self::tool_use_indicates_error)
-        {
-            return true;
-        }
-
-        if let Some(message) = entry.get("message") {
-            if Self::is_stop_sequence_message(message)
-                && Self::message_contains_matching_text(message, Self::text_indicates_error)
-            {
-                return true;
-            }
-        }
-
-        false
-    }
-
-    fn get_error_details(entry: &Value) -> Option<String> {
-        if let Some(tool_use_result) = entry.get("toolUseResult") {
-            if let Some(error_msg) = tool_use_result.get("error").and_then(Value::as_str) {
-                return Self::error_detail_from_message(error_msg);
-            }
-        }
-
-        entry.get("message").and_then(|message| {
-            if Self::is_stop_sequence_message(message) {
-                Self::message_error_detail(message)
-            } else {
-                None
-            }
-        })
-    }
-
-    fn collect_recent_tool_name(lines: &[&str]) -> Option<String> {
-        const RECENT_WINDOW: usize = 5;
-
-        lines
-            .iter()
-            .rev()
-            .take(RECENT_WINDOW)
-            .map(|line| line.trim())
-            .filter(|trimmed| !trimmed.is_empty())
-            .filter_map(|trimmed| serde_json::from_str::<Value>(trimmed).ok())
-            .find_map(|value| Self::extract_tool_use_name(&value))
-    }
-
-    fn tool_use_indicates_error(tool_use_result: &Value) -> bool {
-        if let Some(error_msg) = tool_use_result.get("error").and_then(Value::as_str) {
-            return !Self::is_blocked_error_message(error_msg);
-        }
-
-        tool_use_result
-            .get("type")
-            .and_then(Value::as_str)
-            .is_some_and(|ty| ty.eq_ignore_ascii_case("error"))
-    }
-
-    fn is_stop_sequence_message(message: &Value) -> bool {
-        message
-            .get("stop_reason")
-            .and_then(Value::as_str)
-            .is_some_and(|reason| reason == "stop_sequence")
-    }
-
-    fn message_contains_matching_text(message: &Value, predicate: impl Fn(&str) -> bool) -> bool {
-        message
-            .get("content")
-            .and_then(Value::as_array)
-            .is_some_and(|items| {
-                items
-                    .iter()
-                    .filter_map(|item| {
-                        let item_type = item.get("type").and_then(Value::as_str)?;
-                        if item_type != "text" {
-                            return None;
-                        }
-                        item.get("text").and_then(Value::as_str)
-                    })
-                    .any(predicate)
-            })
-    }
-
-    fn text_indicates_error(text: &str) -> bool {
-        (text.starts_with("API Error: 403") && text.contains("user quota is not enough"))
-            || text.contains("filter")
-    }
-
-    fn error_detail_from_message(error_msg: &str) -> Option<String> {
-        if Self::is_blocked_error_message(error_msg) {
-            None
-        } else {
-            Some(error_msg.to_string())
-        }
-    }
-
-    fn message_error_detail(message: &Value) -> Option<String> {
-        let items = message.get("content").and_then(Value::as_array)?;
-
-        items.iter().find_map(|item| {
-            let item_type = item.get("type").and_then(Value::as_str)?;
-            if item_type != "text" {
-                return None;
-            }
-
-            let text = item.get("text").and_then(Value::as_str)?;
-            if text.starts_with("API Error: 403") && text.contains("user quota is not enough") {
-                Some("403 quota insufficient".to_string())
-            } else if text.contains("filter") {
-                Some("Filter error".to_string())
-            } else {
-                None
-            }
-        })
-    }
-
-    fn extract_tool_use_name(value: &Value) -> Option<String> {
-        let content = value
-            .get("message")
-            .and_then(|message| message.get("content"))
-            .and_then(Value::as_array)?;
-
-        content.iter().find_map(|item| {
-            let item_type = item.get("type").and_then(Value::as_str)?;
-            if item_type != "tool_use" {
-                return None;
-            }
-            item.get("name")
-                .and_then(Value::as_str)
-                .map(std::string::ToString::to_string)
-        })
-    }
-
-    fn is_blocked_error_message(message: &str) -> bool {
-        message.contains("was blocked") || message.contains("For security")
-    }
 }
 
 #[async_trait]
@@ -472,7 +261,7 @@ impl Component for StatusComponent {
             return ComponentOutput::hidden();
         }
 
-        let status_info = self.resolve_status(ctx);
+        let status_info = Self::resolve_status(ctx).await;
 
         if status_info.status_type == StatusType::Ready && !self.config.show_when_idle {
             return ComponentOutput::hidden();
@@ -0,0 +1,200 @@
+//! Time component implementation
+//!
+//! Shows how long the session has been running and how much of that was
+//! spent waiting on the API, from the `cost` duration fields Claude Code
+//! sends on every refresh, optionally followed by a clock.
+
+use std::fmt::Write;
+
+use async_trait::async_trait;
+use chrono::Local;
+
+use super::base::{Component, ComponentFactory, ComponentOutput, RenderContext};
+use crate::config::{BaseComponentConfig, Config, TimeComponentConfig, TimeFormat};
+
+/// Clock format used when the configured one is invalid
+const FALLBACK_CLOCK_FORMAT: &str = "%H:%M";
+
+/// Time component
+pub struct TimeComponent {
+    config: TimeComponentConfig,
+}
+
+impl TimeComponent {
+    #[must_use]
+    pub const fn new(config: TimeComponentConfig) -> Self {
+        Self { config }
+    }
+
+    fn format_duration(&self, ms: u64) -> String {
+        let seconds = ms / 1000;
+        if seconds < 60 {
+            return format!("{seconds}s");
+        }
+
+        let minutes = seconds / 60;
+        match self.config.format {
+            TimeFormat::Compact if minutes >= 60 => {
+                format!("{}h{:02}m", minutes / 60, minutes % 60)
+            }
+            TimeFormat::Compact | TimeFormat::Minutes => format!("{minutes}m"),
+        }
+    }
+
+    fn format_clock(&self) -> String {
+        let now = Local::now();
+        let mut clock = String::new();
+        // 非法的 strftime 格式在 Display 时报错,回退到默认格式
+        if write!(clock, "{}", now.format(&self.config.clock_format)).is_err() {
+            clock.clear();
+            let _ = write!(clock, "{}", now.format(FALLBACK_CLOCK_FORMAT));
+        }
+        clock
+    }
+
+    fn build_parts(&self, wall_ms: Option<u64>, api_ms: Option<u64>) -> Vec<String> {
+        let mut parts = Vec::new();
+
+        if let (true, Some(wall)) = (self.config.show_wall, wall_ms) {
+            parts.push(self.format_duration(wall));
+        }
+        if let (true, Some(api)) = (self.config.show_api, api_ms) {
+            parts.push(format!("API {}", self.format_duration(api)));
+        }
+        if let (true, Some(wall), Some(api)) = (self.config.show_ratio, wall_ms, api_ms) {
+            if wall > 0 {
+                #[allow(clippy::cast_precision_loss)]
+                let ratio = api as f64 / wall as f64 * 100.0;
+                parts.push(format!("{ratio:.0}%"));
+            }
+        }
+        if self.config.show_clock {
+            parts.push(self.format_clock());
+        }
+
+        parts
+    }
+}
+
+#[async_trait]
+impl Component for TimeComponent {
+    fn name(&self) -> &'static str {
+        "time"
+    }
+
+    fn is_enabled(&self, _ctx: &RenderContext) -> bool {
+        self.config.base.enabled
+    }
+
+    async fn render(&self, ctx: &RenderContext) -> ComponentOutput {
+        if !self.is_enabled(ctx) {
+            return ComponentOutput::hidden();
+        }
+
+        let cost = ctx.input.cost.as_ref();
+        let duration = |ms: Option<i64>| ms.and_then(|ms| u64::try_from(ms).ok());
+        let parts = self.build_parts(
+            duration(cost.and_then(|cost| cost.total_duration_ms)),
+            duration(cost.and_then(|cost| cost.total_api_duration_ms)),
+        );
+        if parts.is_empty() {
+            return ComponentOutput::hidden();
+        }
+
+        ComponentOutput::new(parts.join(" "))
+            .with_icon(self.select_icon(ctx).unwrap_or_default())
+            .with_icon_color(self.config.base.icon_color.clone())
+            .with_text_color(self.config.base.text_color.clone())
+    }
+
+    fn base_config(&self, _ctx: &RenderContext) -> Option<&BaseComponentConfig> {
+        Some(&self.config.base)
+    }
+}
+
+/// Factory for creating Time components
+pub struct TimeComponentFactory;
+
+impl ComponentFactory for TimeComponentFactory {
+    fn create(&self, config: &Config) -> Box<dyn Component> {
+        Box::new(TimeComponent::new(config.components.time.clone()))
+    }
+
+    fn name(&self) -> &'static str {
+        "time"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::TerminalCapabilities;
+    use crate::core::{CostInfo, InputData};
+    use std::sync::Arc;
+
+    fn context_with_durations(wall_ms: Option<i64>, api_ms: Option<i64>) -> RenderContext {
+        let input = InputData {
+            cost: Some(CostInfo {
+                total_duration_ms: wall_ms,
+                total_api_duration_ms: api_ms,
+                ..CostInfo::default()
+            }),
+            ..InputData::default()
+        };
+        RenderContext {
+            input: Arc::new(input),
+            config: Arc::new(Config::default()),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: false,
+        }
+    }
+
+    fn time_config(configure: impl FnOnce(&mut TimeComponentConfig)) -> TimeComponentConfig {
+        let mut config = TimeComponentConfig::default();
+        configure(&mut config);
+        config
+    }
+
+    #[test]
+    fn test_time_formats_durations() {
+        let compact = TimeComponent::new(time_config(|_| {}));
+        assert_eq!(compact.format_duration(45_000), "45s");
+        assert_eq!(compact.format_duration(5 * 60_000 + 59_000), "5m");
+        assert_eq!(compact.format_duration(62 * 60_000), "1h02m");
+
+        let minutes = TimeComponent::new(time_config(|config| {
+            config.format = TimeFormat::Minutes;
+        }));
+        assert_eq!(minutes.format_duration(62 * 60_000), "62m");
+    }
+
+    #[tokio::test]
+    async fn test_time_renders_wall_api_and_ratio() {
+        let component = TimeComponent::new(time_config(|config| {
+            config.show_ratio = true;
+        }));
+
+        let output = component
+            .render(&context_with_durations(Some(3_720_000), Some(1_080_000)))
+            .await;
+        assert!(output.visible);
+        assert_eq!(output.text, "1h02m API 18m 29%");
+    }
+
+    #[tokio::test]
+    async fn test_time_clock_and_missing_durations() {
+        let component = TimeComponent::new(time_config(|_| {}));
+        let output = component.render(&context_with_durations(None, None)).await;
+        assert!(!output.visible);
+
+        let component = TimeComponent::new(time_config(|config| {
+            config.show_clock = true;
+            config.clock_format = "%Q".to_string();
+        }));
+        let output = component.render(&context_with_durations(None, None)).await;
+        assert!(output.visible);
+        // 非法格式回退到 HH:MM
+        assert_eq!(output.text.len(), 5);
+        assert_eq!(output.text.chars().nth(2), Some(':'));
+    }
+}
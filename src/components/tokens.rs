@@ -1,14 +1,16 @@
 //! Tokens component implementation
 //!
 //! Displays token usage information with cached transcript statistics and adaptive progress bars.
+//! The recent context growth recorded in the session token history drives a
+//! forecast of how soon Claude Code will auto-compact.
 
 use std::fmt::Write;
 
 use async_trait::async_trait;
 
 use super::base::{Component, ComponentFactory, ComponentOutput, RenderContext};
-use crate::config::{BaseComponentConfig, Config, TokensComponentConfig};
-use crate::storage;
+use crate::config::{BaseComponentConfig, Config, TokensComponentConfig, TokensForecastMode};
+use crate::storage::{self, TokenHistory};
 use crate::utils::model_parser::parse_model_id;
 
 #[derive(Clone, Debug)]
@@ -18,6 +20,28 @@ struct TokenUsageInfo {
     percentage: Option<f64>,
 }
 
+impl TokenUsageInfo {
+    fn resolved_percentage(&self) -> f64 {
+        self.percentage
+            .unwrap_or_else(|| (to_f64(self.used) / to_f64(self.total.max(1))) * 100.0)
+    }
+}
+
+/// Context window usage as the tokens component resolves it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContextUsage {
+    pub used: u64,
+    pub total: u64,
+    pub percentage: f64,
+}
+
+/// Estimated distance to auto-compact
+#[derive(Clone, Copy, Debug)]
+struct ContextForecast {
+    turns: u64,
+    minutes: Option<f64>,
+}
+
 /// Tokens component
 pub struct TokensComponent {
     config: TokensComponentConfig,
@@ -29,6 +53,18 @@ impl TokensComponent {
         Self { config }
     }
 
+    /// Context usage from the same sources and fallbacks `render` uses, so
+    /// widgets and other consumers agree with the tokens component
+    pub async fn context_usage(&self, ctx: &RenderContext) -> Option<ContextUsage> {
+        let history = Self::load_history(ctx).await;
+        self.resolve_usage(ctx, history.as_ref())
+            .map(|usage| ContextUsage {
+                used: usage.used,
+                total: usage.total,
+                percentage: usage.resolved_percentage(),
+            })
+    }
+
     fn usage_from_official_input(&self, ctx: &RenderContext) -> Option<TokenUsageInfo> {
         let context_window = ctx
             .input
@@ -88,7 +124,32 @@ impl TokensComponent {
         })
     }
 
-    async fn fetch_usage_from_cache(&self, ctx: &RenderContext) -> Option<TokenUsageInfo> {
+    /// Persisted token history of the session
+    async fn load_history(ctx: &RenderContext) -> Option<TokenHistory> {
+        // mock 数据只走 __mock__.tokensHistory,不碰真实 storage
+        if let Some(mock) = ctx.input.extra.get("__mock__") {
+            return mock
+                .get("tokensHistory")
+                .and_then(|history| serde_json::from_value(history.clone()).ok());
+        }
+
+        // preview 模式跳过 storage:`storage::get_session_tokens` 底层
+        // `StorageManager::new()` 会 `ensure_directories()`,在用户真实
+        // `~/.claude/statusline-pro/...` 下建目录,违反"preview 无副作用"
+        // 契约。preview 场景下落到 show_zero / None 分支即可,
+        // 预览里 token 用量的位置和图标仍然可见,具体数字不需要真实。
+        if ctx.preview_mode {
+            return None;
+        }
+        let session_id = ctx.input.session_id.as_deref()?;
+        storage::get_session_tokens(session_id).await.ok().flatten()
+    }
+
+    fn resolve_usage(
+        &self,
+        ctx: &RenderContext,
+        history: Option<&TokenHistory>,
+    ) -> Option<TokenUsageInfo> {
         if let Some(mock_tokens) = ctx
             .input
             .extra
@@ -117,26 +178,17 @@ impl TokensComponent {
             return Some(usage);
         }
 
-        // preview 模式跳过 storage:`storage::get_session_tokens` 底层
-        // `StorageManager::new()` 会 `ensure_directories()`,在用户真实
-        // `~/.claude/statusline-pro/...` 下建目录,违反"preview 无副作用"
-        // 契约。preview 场景下直接落到下面的 show_zero / None 分支即可,
-        // 预览里 token 用量的位置和图标仍然可见,具体数字不需要真实。
-        if !ctx.preview_mode {
-            if let Some(session_id) = ctx.input.session_id.as_deref() {
-                if let Ok(Some(tokens)) = storage::get_session_tokens(session_id).await {
-                    let used = tokens.input + tokens.cache_creation_input + tokens.cache_read_input;
-                    if used == 0 && !self.config.show_zero {
-                        return None;
-                    }
-                    let window = self.context_window_for_model(ctx);
-                    return Some(TokenUsageInfo {
-                        used,
-                        total: window,
-                        percentage: None,
-                    });
-                }
+        if let Some(tokens) = history {
+            let used = tokens.input + tokens.cache_creation_input + tokens.cache_read_input;
+            if used == 0 && !self.config.show_zero {
+                return None;
             }
+            let window = self.context_window_for_model(ctx);
+            return Some(TokenUsageInfo {
+                used,
+                total: window,
+                percentage: None,
+            });
         }
         if self.config.show_zero {
             let window = self.context_window_for_model(ctx);
@@ -314,6 +366,58 @@ impl TokensComponent {
         }
     }
 
+    /// Turns (and time) left before usage reaches `forecast.compact_at`,
+    /// at the pace of the recent turns
+    fn forecast(
+        &self,
+        percentage: f64,
+        total: u64,
+        history: &TokenHistory,
+    ) -> Option<ContextForecast> {
+        let forecast = &self.config.forecast;
+        // 已经越过压缩阈值时没有可预测的剩余量
+        if !forecast.enabled || percentage >= forecast.compact_at {
+            return None;
+        }
+
+        let growth = history.context_growth(forecast.window as usize)?;
+        let remaining = ((forecast.compact_at - percentage) / 100.0 * to_f64(total)).max(0.0);
+        let turns = clamp_round_to_usize((remaining / growth.per_turn).ceil(), usize::MAX);
+        Some(ContextForecast {
+            turns: u64::try_from(turns).unwrap_or(u64::MAX),
+            minutes: growth.per_minute.map(|per_minute| remaining / per_minute),
+        })
+    }
+
+    fn format_forecast(&self, forecast: ContextForecast) -> String {
+        match (self.config.forecast.mode, forecast.minutes) {
+            (TokensForecastMode::Time, Some(minutes)) => {
+                let minutes = clamp_round_to_usize(minutes.ceil(), usize::MAX);
+                if minutes >= 60 {
+                    format!("~{}h{:02}m", minutes / 60, minutes % 60)
+                } else {
+                    format!("~{minutes}m")
+                }
+            }
+            _ if forecast.turns == 1 => "~1 turn".to_string(),
+            _ => format!("~{} turns", forecast.turns),
+        }
+    }
+
+    /// Escalate the percentage color when auto-compact is only a few turns away
+    fn forecast_color(&self, color: String, forecast: ContextForecast) -> String {
+        let config = &self.config.forecast;
+        let colors = &self.config.colors;
+
+        if forecast.turns <= u64::from(config.danger_turns) {
+            colors.danger.clone()
+        } else if forecast.turns <= u64::from(config.warning_turns) && color == colors.safe {
+            colors.warning.clone()
+        } else {
+            color
+        }
+    }
+
     fn format_usage(&self, info: &TokenUsageInfo) -> String {
         if self.config.show_raw_numbers {
             format!("({}/{})", info.used, info.total)
@@ -340,14 +444,13 @@ impl Component for TokensComponent {
             return ComponentOutput::hidden();
         }
 
-        let Some(usage) = self.fetch_usage_from_cache(ctx).await else {
+        let history = Self::load_history(ctx).await;
+        let Some(usage) = self.resolve_usage(ctx, history.as_ref()) else {
             return ComponentOutput::hidden();
         };
 
         let total = usage.total.max(1);
-        let percentage = usage
-            .percentage
-            .unwrap_or_else(|| (to_f64(usage.used) / to_f64(total)) * 100.0);
+        let percentage = usage.resolved_percentage();
         let clamped_percentage = percentage.clamp(0.0, 999.9);
 
         let mut parts = Vec::new();
@@ -370,6 +473,13 @@ impl Component for TokensComponent {
             parts.push(format!("{left}{bar}{right}"));
         }
 
+        let forecast = history
+            .as_ref()
+            .and_then(|history| self.forecast(clamped_percentage, total, history));
+        if let Some(forecast) = forecast {
+            parts.push(self.format_forecast(forecast));
+        }
+
         if self.config.show_percentage {
             parts.push(format!("{clamped_percentage:.1}%"));
         }
@@ -381,7 +491,10 @@ impl Component for TokensComponent {
         }
 
         let text = parts.join(" ");
-        let color = self.select_color(clamped_percentage);
+        let mut color = self.select_color(clamped_percentage);
+        if let Some(forecast) = forecast {
+            color = self.forecast_color(color, forecast);
+        }
         let icon = self.select_icon(ctx);
 
         ComponentOutput::new(text)
@@ -789,6 +902,91 @@ mod tests {
 
     // ==================== 上下文窗口智能推断测试 ====================
 
+    fn create_test_context_with_history(used: u64, samples: &[(u64, u32)]) -> RenderContext {
+        let recent_context: Vec<_> = samples
+            .iter()
+            .map(|(context, minute)| {
+                json!({
+                    "context_used": context,
+                    "timestamp": format!("2025-01-01T00:{minute:02}:00Z")
+                })
+            })
+            .collect();
+        let input = build_input(|input| {
+            input.extra = json!({
+                "__mock__": {
+                    "tokensUsage": { "context_used": used, "context_window": 100_000u64 },
+                    "tokensHistory": { "recent_context": recent_context }
+                }
+            });
+        });
+
+        RenderContext {
+            input: Arc::new(input),
+            config: Arc::new(Config::default()),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tokens_forecast_turns_until_compact() {
+        let config = build_tokens_config(|config| {
+            config.show_progress_bar = false;
+            config.show_percentage = false;
+            config.forecast.enabled = true;
+        });
+        let component = TokensComponent::new(config);
+
+        // 每轮增长 5k,距 85% 还剩 45k → 9 轮,进入 warning
+        let ctx =
+            create_test_context_with_history(40_000, &[(30_000, 0), (35_000, 1), (40_000, 2)]);
+        let output = component.render(&ctx).await;
+        assert_eq!(output.text, "~9 turns (40.0k/100k)");
+        assert_eq!(output.text_color.as_deref(), Some("yellow"));
+
+        let ctx = create_test_context_with_history(75_000, &[(65_000, 0), (75_000, 1)]);
+        let output = component.render(&ctx).await;
+        assert_eq!(output.text, "~1 turn (75.0k/100k)");
+        assert_eq!(output.text_color.as_deref(), Some("red"));
+
+        // 只有一轮或上下文没有增长时不预测
+        let ctx = create_test_context_with_history(40_000, &[(40_000, 0)]);
+        let output = component.render(&ctx).await;
+        assert_eq!(output.text, "(40.0k/100k)");
+
+        // 越过 compact_at 后不再显示 "~0 turns"
+        let ctx = create_test_context_with_history(90_000, &[(80_000, 0), (90_000, 1)]);
+        let output = component.render(&ctx).await;
+        assert!(output.text.starts_with("(90.0k/100k)"));
+        assert!(!output.text.contains('~'));
+
+        // 默认关闭
+        let component = TokensComponent::new(build_tokens_config(|config| {
+            config.show_progress_bar = false;
+            config.show_percentage = false;
+        }));
+        let ctx =
+            create_test_context_with_history(40_000, &[(30_000, 0), (35_000, 1), (40_000, 2)]);
+        assert_eq!(component.render(&ctx).await.text, "(40.0k/100k)");
+    }
+
+    #[tokio::test]
+    async fn test_tokens_forecast_time_mode() {
+        let config = build_tokens_config(|config| {
+            config.show_progress_bar = false;
+            config.show_percentage = false;
+            config.forecast.enabled = true;
+            config.forecast.mode = TokensForecastMode::Time;
+        });
+        let component = TokensComponent::new(config);
+
+        // 2 分钟增长 10k → 5k/分钟,剩余 45k → 9 分钟
+        let ctx = create_test_context_with_history(40_000, &[(30_000, 0), (40_000, 2)]);
+        let output = component.render(&ctx).await;
+        assert_eq!(output.text, "~9m (40.0k/100k)");
+    }
+
     #[tokio::test]
     async fn test_context_window_infer_1m_model() {
         use crate::core::ModelInfo;
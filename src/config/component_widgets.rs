@@ -1,5 +1,7 @@
 use serde::Deserialize;
+use serde_json::Value;
 use std::collections::HashMap;
+use std::path::Path;
 
 /// Component-level multiline configuration loaded from component template files.
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -27,6 +29,8 @@ pub enum WidgetType {
     #[default]
     Static,
     Api,
+    Command,
+    File,
 }
 
 /// Widget configuration
@@ -50,12 +54,23 @@ pub struct WidgetConfig {
     pub emoji_icon: String,
     /// Text fallback icon string
     pub text_icon: String,
+    /// Icon color (color name or hex; uncolored when omitted)
+    pub icon_color: Option<String>,
+    /// Text color (color name or hex; uncolored when omitted)
+    pub text_color: Option<String>,
+    /// Numeric thresholds that switch the text color to warning/danger
+    pub thresholds: Option<WidgetThresholdsConfig>,
     /// Static content (for static widgets)
     pub content: Option<String>,
-    /// Template string (for api widgets)
+    /// Template string (for api, command and file widgets; static widgets
+    /// render it in place of the literal `content`)
     pub template: Option<String>,
     /// API configuration (for api widgets)
     pub api: Option<WidgetApiConfig>,
+    /// Command configuration (for command widgets)
+    pub command: Option<WidgetCommandConfig>,
+    /// File configuration (for file widgets)
+    pub file: Option<WidgetFileConfig>,
     /// Detection configuration used to gate widget rendering
     pub detection: Option<WidgetDetectionConfig>,
     /// Optional filter applied to API results before rendering
@@ -135,8 +150,132 @@ pub struct WidgetApiConfig {
     /// Optional headers (supports environment variable substitutions)
     #[serde(default)]
     pub headers: HashMap<String, String>,
+    /// Optional query parameters appended to the URL (supports environment variable substitutions)
+    #[serde(default)]
+    pub query: HashMap<String, String>,
+    /// Optional request body: a string is sent verbatim, a table is encoded as JSON
+    /// (or as form fields when `content_type` is `application/x-www-form-urlencoded`).
+    /// String values support environment variable substitutions.
+    pub body: Option<Value>,
+    /// Content-Type of the request body (defaults to `application/json`)
+    pub content_type: Option<String>,
     /// `JSONPath` expression for extracting data from response
     pub data_path: Option<String>,
+    /// Serve responses from the on-disk cache for this many milliseconds (0 disables caching).
+    /// Expired entries are still served while a background refresh runs.
+    #[serde(default)]
+    pub cache_ttl: u64,
+    /// Age in milliseconds after which cached data counts as stale (defaults to `cache_ttl`)
+    pub stale_after: Option<u64>,
+    /// Marker appended to the widget when its data is stale
+    pub stale_marker: Option<String>,
+}
+
+/// Threshold-based text coloring for widgets
+///
+/// `value` is a template expression evaluated against the widget data
+/// (e.g. `remaining` or `used / limit * 100`). With `reverse = true` values at
+/// or below the thresholds trigger, which suits balances and remaining quota.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WidgetThresholdsConfig {
+    /// Expression producing the numeric value to compare
+    pub value: String,
+    /// Warning threshold
+    pub warning: f64,
+    /// Danger threshold
+    pub danger: f64,
+    /// Trigger when the value drops to or below the thresholds instead of rising above them
+    #[serde(default)]
+    pub reverse: bool,
+    /// Text color in the warning range
+    #[serde(default = "default_warning_color")]
+    pub warning_color: String,
+    /// Text color in the danger range
+    #[serde(default = "default_danger_color")]
+    pub danger_color: String,
+}
+
+impl WidgetThresholdsConfig {
+    /// Color for `value`, or `None` while it stays in the safe range
+    #[must_use]
+    pub fn color_for(&self, value: f64) -> Option<&str> {
+        let reached = |threshold: f64| {
+            if self.reverse {
+                value <= threshold
+            } else {
+                value >= threshold
+            }
+        };
+
+        if reached(self.danger) {
+            Some(&self.danger_color)
+        } else if reached(self.warning) {
+            Some(&self.warning_color)
+        } else {
+            None
+        }
+    }
+}
+
+/// Local program configuration for command widgets
+///
+/// The program runs in the session project directory. Stdout is parsed as JSON
+/// when possible; otherwise the trimmed text is exposed to templates as
+/// `{output}` (and `{lines[0]}`, `{lines[1]}`, ...).
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct WidgetCommandConfig {
+    /// Program to execute (looked up in `PATH`)
+    pub program: String,
+    /// Arguments (support environment variable substitutions)
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extra environment variables (values support environment variable substitutions)
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Timeout in milliseconds
+    #[serde(default = "default_command_timeout_ms")]
+    pub timeout: u64,
+    /// `JSONPath` expression for extracting data from JSON output
+    pub data_path: Option<String>,
+}
+
+/// Local file configuration for file widgets
+///
+/// The file is re-read only when its modification time changes.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct WidgetFileConfig {
+    /// File path (supports `~/`, environment variables; relative to the project directory)
+    pub path: String,
+    /// File format (detected from the extension when omitted, falling back to text)
+    pub format: Option<WidgetFileFormat>,
+    /// `JSONPath` expression for extracting data from JSON/TOML files
+    pub data_path: Option<String>,
+}
+
+/// Supported file widget formats
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WidgetFileFormat {
+    Json,
+    Toml,
+    Text,
+}
+
+impl WidgetFileFormat {
+    /// Detect the format from a file extension
+    #[must_use]
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("json") => Self::Json,
+            Some("toml") => Self::Toml,
+            _ => Self::Text,
+        }
+    }
 }
 
 const fn default_true() -> bool {
@@ -147,6 +286,18 @@ const fn default_timeout_ms() -> u64 {
     5000
 }
 
+fn default_warning_color() -> String {
+    "yellow".to_string()
+}
+
+fn default_danger_color() -> String {
+    "red".to_string()
+}
+
+const fn default_command_timeout_ms() -> u64 {
+    1000
+}
+
 fn default_filter_object() -> String {
     "$".to_string()
 }
@@ -124,8 +124,34 @@ impl ConfigLoader {
         }
 
         let custom_path_owned = custom_path.map(str::to_owned);
+        let (config, source, report) = task::spawn_blocking(move || {
+            Self::load_config_layers(
+                custom_path_owned.as_deref(),
+                Self::get_project_config_path().ok(),
+            )
+        })
+        .await
+        .map_err(|err| anyhow!("Blocking configuration load failed: {err}"))??;
+
+        self.cached_config = Some(config.clone());
+        self.config_source = Some(source);
+        self.merge_report = Some(report);
+
+        Ok(config)
+    }
+
+    /// Load configuration for `project_dir` instead of the current directory
+    ///
+    /// Same layering as [`Self::load`] without a custom path; never served
+    /// from the loader cache, so long-running callers can reload.
+    /// # Errors
+    ///
+    /// Returns an error if configuration files cannot be read or parsed.
+    pub async fn load_for_project(&mut self, project_dir: &Path) -> Result<Config> {
+        let project_config_path =
+            Self::project_config_path_for_path(&project_dir.to_string_lossy());
         let (config, source, report) =
-            task::spawn_blocking(move || Self::load_config_layers(custom_path_owned.as_deref()))
+            task::spawn_blocking(move || Self::load_config_layers(None, Some(project_config_path)))
                 .await
                 .map_err(|err| anyhow!("Blocking configuration load failed: {err}"))??;
 
@@ -155,6 +181,7 @@ impl ConfigLoader {
 
     fn load_config_layers(
         custom_path: Option<&str>,
+        project_config_path: Option<PathBuf>,
     ) -> Result<(Config, ConfigSource, MergeReport)> {
         let mut merged_value = serde_json::to_value(Config::default())
             .context("Failed to serialize default config")?;
@@ -183,7 +210,7 @@ impl ConfigLoader {
             }
         }
 
-        if let Ok(project_config_path) = Self::get_project_config_path() {
+        if let Some(project_config_path) = project_config_path {
             if project_config_path.exists() {
                 let project_value = Self::load_toml_value(&project_config_path)?;
                 let before = merged_value.clone();
@@ -226,6 +253,10 @@ impl ConfigLoader {
 
         let config: Config = serde_json::from_value(merged_value)
             .context("Failed to build configuration from merged values")?;
+        // 只在加载时提示一次,渲染路径(daemon 里每次刷新都会走)保持安静
+        for conflict in config.components.custom_conflicts() {
+            eprintln!("[statusline] {conflict}");
+        }
 
         Ok((config, source, MergeReport { layers }))
     }
@@ -789,4 +820,32 @@ mod tests {
         assert!(loader.config_source.is_none());
         Ok(())
     }
+
+    #[test]
+    fn test_custom_components_conflicting_with_builtins() -> Result<()> {
+        let config: Config = toml_edit::de::from_str(
+            r#"
+[components.custom.time]
+command = "date"
+
+[components.custom.weather]
+command = "weather"
+preset_key = "c"
+
+[components.custom.deploy]
+command = "deploy-status"
+preset_key = "X"
+"#,
+        )?;
+
+        assert_eq!(
+            config.components.custom_conflicts(),
+            vec![
+                "custom component 'time' shadows the built-in component, ignored".to_string(),
+                "custom component 'weather' preset_key 'c' is reserved for 'cache' (C), ignored"
+                    .to_string(),
+            ]
+        );
+        Ok(())
+    }
 }
@@ -5,21 +5,27 @@
 
 pub mod component_widgets;
 pub mod loader;
+pub mod overrides;
 pub mod schema;
 
 // Re-export commonly used types
 pub use component_widgets::{
     ComponentMultilineConfig, ComponentMultilineMeta, WidgetApiConfig, WidgetApiMethod,
-    WidgetConfig, WidgetDetectionConfig, WidgetFilterConfig, WidgetFilterMode, WidgetType,
+    WidgetCommandConfig, WidgetConfig, WidgetDetectionConfig, WidgetFileConfig, WidgetFileFormat,
+    WidgetFilterConfig, WidgetFilterMode, WidgetThresholdsConfig, WidgetType,
 };
 pub use loader::{
     ComponentCopyStats, ConfigLoader, ConfigSource, ConfigSourceType, CreateConfigOptions,
     CreateConfigResult, MergeLayer, MergeReport, TerminalCapabilityHint,
 };
+pub use overrides::RuntimeOverrides;
 pub use schema::{
-    AutoDetect, BaseComponentConfig, BranchComponentConfig, ComponentsConfig, Config,
+    AutoDetect, BaseComponentConfig, BranchComponentConfig, BranchOperationLabels,
+    BranchVersionConfig, BudgetComponentConfig, BudgetPeriod, BudgetThresholdsConfig,
+    CacheComponentConfig, CacheThresholdsConfig, ComponentsConfig, Config, CustomComponentConfig,
     ModelComponentConfig, MultilineConfig, MultilineRowConfig, ProjectComponentConfig,
-    StatusComponentConfig, StorageConfig, StyleConfig, TerminalConfig, TokenIconSetConfig,
-    TokensColorConfig, TokensComponentConfig, TokensProgressBarCharsConfig,
-    TokensStatusIconsConfig, TokensThresholdsConfig, UsageComponentConfig,
+    StatusComponentConfig, StorageConfig, StyleConfig, TerminalConfig, TimeComponentConfig,
+    TimeFormat, TokenIconSetConfig, TokensColorConfig, TokensComponentConfig, TokensForecastConfig,
+    TokensForecastMode, TokensProgressBarCharsConfig, TokensStatusIconsConfig,
+    TokensThresholdsConfig, UsageComponentConfig, BUILTIN_COMPONENTS, BUILTIN_PRESET_KEYS,
 };
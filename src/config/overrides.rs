@@ -0,0 +1,51 @@
+//! Capability overrides passed on the command line
+//!
+//! `--no-colors`, `--force-text` and friends adjust the loaded config for a
+//! single render. They live in the library so the daemon applies exactly
+//! what an in-process render would.
+
+use serde::{Deserialize, Serialize};
+
+use super::{AutoDetect, Config};
+
+/// Capability flags of one invocation
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuntimeOverrides {
+    pub no_colors: bool,
+    pub no_emoji: bool,
+    pub no_icons: bool,
+    pub force_emoji: bool,
+    pub force_nerd_font: bool,
+    pub force_text: bool,
+}
+
+impl RuntimeOverrides {
+    /// Apply the flags on top of `config`
+    pub fn apply(&self, config: &mut Config) {
+        if self.no_colors {
+            config.style.enable_colors = AutoDetect::Bool(false);
+        }
+        if self.no_emoji {
+            config.style.enable_emoji = AutoDetect::Bool(false);
+            config.terminal.force_emoji = false;
+        }
+        if self.no_icons {
+            config.style.enable_nerd_font = AutoDetect::Bool(false);
+            config.terminal.force_nerd_font = false;
+        }
+
+        if self.force_emoji {
+            config.terminal.force_emoji = true;
+        }
+        if self.force_nerd_font {
+            config.terminal.force_nerd_font = true;
+        }
+        if self.force_text {
+            config.terminal.force_text = true;
+            config.terminal.force_emoji = false;
+            config.terminal.force_nerd_font = false;
+        }
+    }
+}
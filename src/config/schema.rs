@@ -81,6 +81,10 @@ pub struct TerminalConfig {
     /// Force enable text-only mode
     #[serde(default)]
     pub force_text: bool,
+
+    /// Statusline width in columns; 0 detects it from `COLUMNS` or the tty
+    #[serde(default)]
+    pub width: u16,
 }
 
 /// Storage system configuration
@@ -190,12 +194,25 @@ impl AutoDetect {
 }
 
 /// All component configurations
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ComponentsConfig {
     /// Component display order (e.g., `["project", "model", "branch", "tokens"]`)
     #[serde(default)]
     pub order: Vec<String>,
 
+    /// Per-component render deadline in ms; branch uses `performance.git_timeout`
+    /// and custom components their own `timeout`
+    #[serde(default = "default_component_timeout")]
+    pub timeout: u64,
+
+    /// Overall render budget in ms shared by all components (rendered concurrently)
+    #[serde(default = "default_render_budget")]
+    pub render_budget: u64,
+
+    /// Text shown for a timed-out component that has no previous output; empty hides it
+    #[serde(default = "default_timeout_placeholder")]
+    pub timeout_placeholder: String,
+
     #[serde(default)]
     pub project: ProjectComponentConfig,
 
@@ -213,6 +230,89 @@ pub struct ComponentsConfig {
 
     #[serde(default)]
     pub status: StatusComponentConfig,
+
+    #[serde(default)]
+    pub budget: BudgetComponentConfig,
+
+    #[serde(default)]
+    pub time: TimeComponentConfig,
+
+    #[serde(default)]
+    pub cache: CacheComponentConfig,
+
+    /// User-defined components backed by external commands, keyed by component name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub custom: HashMap<String, CustomComponentConfig>,
+}
+
+/// Preset letters of the built-in components
+///
+/// Custom components cannot claim these through `preset_key`.
+pub const BUILTIN_PRESET_KEYS: &[(char, &str)] = &[
+    ('P', "project"),
+    ('M', "model"),
+    ('B', "branch"),
+    ('T', "tokens"),
+    ('U', "usage"),
+    ('S', "status"),
+    ('D', "time"),
+    ('C', "cache"),
+];
+
+/// Names of the built-in components; custom components cannot reuse them
+pub const BUILTIN_COMPONENTS: &[&str] = &[
+    "project", "model", "branch", "tokens", "usage", "status", "budget", "time", "cache",
+];
+
+impl ComponentsConfig {
+    /// Custom components whose name or `preset_key` is taken by a built-in,
+    /// one message each; the built-in wins at render time
+    #[must_use]
+    pub fn custom_conflicts(&self) -> Vec<String> {
+        let mut conflicts: Vec<String> = self
+            .custom
+            .iter()
+            .filter_map(|(name, config)| {
+                if BUILTIN_COMPONENTS.contains(&name.as_str()) {
+                    return Some(format!(
+                        "custom component '{name}' shadows the built-in component, ignored"
+                    ));
+                }
+                let key = config.preset_key.as_deref()?;
+                BUILTIN_PRESET_KEYS
+                    .iter()
+                    .find(|(letter, _)| key.eq_ignore_ascii_case(&letter.to_string()))
+                    .map(|(letter, builtin)| {
+                        format!(
+                            "custom component '{name}' preset_key '{key}' is reserved for '{builtin}' ({letter}), ignored"
+                        )
+                    })
+            })
+            .collect();
+        conflicts.sort();
+        conflicts
+    }
+}
+
+impl Default for ComponentsConfig {
+    fn default() -> Self {
+        Self {
+            order: Vec::new(),
+            timeout: default_component_timeout(),
+            render_budget: default_render_budget(),
+            timeout_placeholder: default_timeout_placeholder(),
+            project: ProjectComponentConfig::default(),
+            model: ModelComponentConfig::default(),
+            branch: BranchComponentConfig::default(),
+            tokens: TokensComponentConfig::default(),
+            usage: UsageComponentConfig::default(),
+            status: StatusComponentConfig::default(),
+            budget: BudgetComponentConfig::default(),
+            time: TimeComponentConfig::default(),
+            cache: CacheComponentConfig::default(),
+            custom: HashMap::new(),
+        }
+    }
 }
 
 /// Base component configuration
@@ -231,13 +331,30 @@ pub struct BaseComponentConfig {
     pub text_color: String,
 
     /// Emoji icon
+    #[serde(default)]
     pub emoji_icon: String,
 
     /// Nerd Font icon
+    #[serde(default)]
     pub nerd_icon: String,
 
     /// Text icon
+    #[serde(default)]
     pub text_icon: String,
+
+    /// Layout priority; lower-priority segments are abbreviated or dropped first
+    /// when the statusline is wider than the terminal
+    #[serde(default = "default_component_priority")]
+    pub priority: u8,
+
+    /// Narrowest width (columns) the text may be abbreviated to before the
+    /// segment is dropped; 0 drops it without abbreviating
+    #[serde(default)]
+    pub min_width: u32,
+
+    /// Widest the text may ever be (columns); 0 means unlimited
+    #[serde(default)]
+    pub max_width: u32,
 }
 
 /// Project component configuration
@@ -261,6 +378,9 @@ impl Default for ProjectComponentConfig {
                 emoji_icon: "📁".to_string(),
                 nerd_icon: "\u{f07c}".to_string(),
                 text_icon: "[P]".to_string(),
+                priority: 70,
+                min_width: 8,
+                max_width: 0,
             },
             show_when_empty: false,
         }
@@ -296,6 +416,9 @@ impl Default for ModelComponentConfig {
                 emoji_icon: "🤖".to_string(),
                 nerd_icon: "\u{f09d1}".to_string(),
                 text_icon: "[M]".to_string(),
+                priority: 90,
+                min_width: 0,
+                max_width: 0,
             },
             show_full_name: false,
             mapping: HashMap::new(),
@@ -334,6 +457,14 @@ pub struct BranchComponentConfig {
     #[serde(default)]
     pub status_colors: BranchStatusColors,
 
+    /// Labels for in-progress git operations
+    #[serde(default)]
+    pub operation_labels: BranchOperationLabels,
+
+    /// Commit, tag and last-commit age display
+    #[serde(default)]
+    pub version: BranchVersionConfig,
+
     /// Performance tuning options
     #[serde(default)]
     pub performance: BranchPerformanceConfig,
@@ -349,6 +480,9 @@ impl Default for BranchComponentConfig {
                 emoji_icon: "🌿".to_string(),
                 nerd_icon: "\u{e0a0}".to_string(),
                 text_icon: "[B]".to_string(),
+                priority: 60,
+                min_width: 10,
+                max_width: 0,
             },
             show_when_empty: false,
             show_when_no_git: false,
@@ -356,6 +490,8 @@ impl Default for BranchComponentConfig {
             status: BranchStatusConfig::default(),
             status_icons: BranchStatusIcons::default(),
             status_colors: BranchStatusColors::default(),
+            operation_labels: BranchOperationLabels::default(),
+            version: BranchVersionConfig::default(),
             performance: BranchPerformanceConfig::default(),
         }
     }
@@ -401,7 +537,8 @@ impl Default for BranchPerformanceConfig {
 }
 
 /// Branch status configuration
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct BranchStatusConfig {
     /// Show dirty workspace status
     #[serde(default)]
@@ -414,6 +551,36 @@ pub struct BranchStatusConfig {
     /// Show stash count
     #[serde(default)]
     pub show_stash_count: bool,
+
+    /// Show an in-progress rebase / merge / cherry-pick / revert / bisect and its conflicts
+    #[serde(default = "default_true")]
+    pub show_operation: bool,
+
+    /// Show files changed / insertions / deletions against the diff base
+    #[serde(default)]
+    pub show_diff_stat: bool,
+
+    /// Branch to diff against (e.g. `main`); the upstream branch when unset
+    #[serde(default)]
+    pub diff_base: Option<String>,
+
+    /// Changed lines from which the branch counts as large (`status_colors.diff_large`)
+    #[serde(default = "default_branch_diff_large_threshold")]
+    pub diff_large_threshold: usize,
+}
+
+impl Default for BranchStatusConfig {
+    fn default() -> Self {
+        Self {
+            show_dirty: false,
+            show_ahead_behind: false,
+            show_stash_count: false,
+            show_operation: true,
+            show_diff_stat: false,
+            diff_base: None,
+            diff_large_threshold: default_branch_diff_large_threshold(),
+        }
+    }
 }
 
 /// Branch status icons
@@ -431,6 +598,24 @@ pub struct BranchStatusIcons {
     pub ahead_text: String,
     pub behind_text: String,
     pub stash_text: String,
+    #[serde(default = "default_branch_operation_emoji")]
+    pub operation_emoji: String,
+    #[serde(default = "default_branch_operation_nerd")]
+    pub operation_nerd: String,
+    #[serde(default)]
+    pub operation_text: String,
+    #[serde(default = "default_branch_conflict_emoji")]
+    pub conflict_emoji: String,
+    #[serde(default = "default_branch_conflict_nerd")]
+    pub conflict_nerd: String,
+    #[serde(default = "default_branch_conflict_text")]
+    pub conflict_text: String,
+    #[serde(default = "default_branch_diff_emoji")]
+    pub diff_emoji: String,
+    #[serde(default = "default_branch_diff_nerd")]
+    pub diff_nerd: String,
+    #[serde(default = "default_branch_diff_text")]
+    pub diff_text: String,
 }
 
 impl Default for BranchStatusIcons {
@@ -448,6 +633,106 @@ impl Default for BranchStatusIcons {
             ahead_text: "[↑]".to_string(),
             behind_text: "[↓]".to_string(),
             stash_text: "[S]".to_string(),
+            operation_emoji: default_branch_operation_emoji(),
+            operation_nerd: default_branch_operation_nerd(),
+            operation_text: String::new(),
+            conflict_emoji: default_branch_conflict_emoji(),
+            conflict_nerd: default_branch_conflict_nerd(),
+            conflict_text: default_branch_conflict_text(),
+            diff_emoji: default_branch_diff_emoji(),
+            diff_nerd: default_branch_diff_nerd(),
+            diff_text: default_branch_diff_text(),
+        }
+    }
+}
+
+/// Labels for in-progress git operations
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BranchOperationLabels {
+    #[serde(default = "default_branch_rebase_label")]
+    pub rebase: String,
+    #[serde(default = "default_branch_merge_label")]
+    pub merge: String,
+    #[serde(default = "default_branch_cherry_pick_label")]
+    pub cherry_pick: String,
+    #[serde(default = "default_branch_revert_label")]
+    pub revert: String,
+    #[serde(default = "default_branch_bisect_label")]
+    pub bisect: String,
+}
+
+impl Default for BranchOperationLabels {
+    fn default() -> Self {
+        Self {
+            rebase: default_branch_rebase_label(),
+            merge: default_branch_merge_label(),
+            cherry_pick: default_branch_cherry_pick_label(),
+            revert: default_branch_revert_label(),
+            bisect: default_branch_bisect_label(),
+        }
+    }
+}
+
+/// Commit, tag and last-commit age display
+///
+/// Each item is appended to the branch segment when enabled. The same values
+/// are exposed to branch widgets as `session.git.*`, so they can be placed on
+/// a multiline row instead.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BranchVersionConfig {
+    /// Short commit SHA while HEAD is detached
+    #[serde(default)]
+    pub show_commit: bool,
+    /// Nearest tag, with the number of commits since it (`v1.2.0+3`)
+    #[serde(default)]
+    pub show_tag: bool,
+    /// Relative age of the last commit (`3h ago`)
+    #[serde(default)]
+    pub show_age: bool,
+
+    #[serde(default = "default_branch_commit_emoji")]
+    pub commit_emoji: String,
+    #[serde(default = "default_branch_commit_nerd")]
+    pub commit_nerd: String,
+    #[serde(default = "default_branch_commit_text")]
+    pub commit_text: String,
+    #[serde(default = "default_branch_tag_emoji")]
+    pub tag_emoji: String,
+    #[serde(default = "default_branch_tag_nerd")]
+    pub tag_nerd: String,
+    #[serde(default = "default_branch_tag_text")]
+    pub tag_text: String,
+    #[serde(default = "default_branch_age_emoji")]
+    pub age_emoji: String,
+    #[serde(default = "default_branch_age_nerd")]
+    pub age_nerd: String,
+    #[serde(default)]
+    pub age_text: String,
+}
+
+impl BranchVersionConfig {
+    /// Whether any item needs commit / tag information
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.show_commit || self.show_tag || self.show_age
+    }
+}
+
+impl Default for BranchVersionConfig {
+    fn default() -> Self {
+        Self {
+            show_commit: false,
+            show_tag: false,
+            show_age: false,
+            commit_emoji: default_branch_commit_emoji(),
+            commit_nerd: default_branch_commit_nerd(),
+            commit_text: default_branch_commit_text(),
+            tag_emoji: default_branch_tag_emoji(),
+            tag_nerd: default_branch_tag_nerd(),
+            tag_text: default_branch_tag_text(),
+            age_emoji: default_branch_age_emoji(),
+            age_nerd: default_branch_age_nerd(),
+            age_text: String::new(),
         }
     }
 }
@@ -463,6 +748,8 @@ pub struct BranchStatusColors {
     pub behind: String,
     #[serde(default = "default_branch_operation_color")]
     pub operation: String,
+    #[serde(default = "default_branch_diff_large_color")]
+    pub diff_large: String,
 }
 
 impl Default for BranchStatusColors {
@@ -473,6 +760,7 @@ impl Default for BranchStatusColors {
             ahead: default_branch_ahead_color(),
             behind: default_branch_behind_color(),
             operation: default_branch_operation_color(),
+            diff_large: default_branch_diff_large_color(),
         }
     }
 }
@@ -521,6 +809,9 @@ pub struct TokensComponentConfig {
 
     #[serde(default)]
     pub context_windows: HashMap<String, u64>,
+
+    #[serde(default)]
+    pub forecast: TokensForecastConfig,
 }
 
 impl Default for TokensComponentConfig {
@@ -533,6 +824,9 @@ impl Default for TokensComponentConfig {
                 emoji_icon: "📊".to_string(),
                 nerd_icon: "\u{f201}".to_string(),
                 text_icon: "[T]".to_string(),
+                priority: 80,
+                min_width: 0,
+                max_width: 0,
             },
             show_zero: false,
             format: default_compact(),
@@ -546,6 +840,58 @@ impl Default for TokensComponentConfig {
             thresholds: TokensThresholdsConfig::default(),
             status_icons: TokensStatusIconsConfig::default(),
             context_windows: default_context_windows(),
+            forecast: TokensForecastConfig::default(),
+        }
+    }
+}
+
+/// What the context forecast counts down
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokensForecastMode {
+    /// Assistant turns until auto-compact, e.g. `~12 turns`
+    #[default]
+    Turns,
+    /// Time until auto-compact at the recent pace, e.g. `~25m`
+    Time,
+}
+
+/// Context burn-rate forecast shown next to the progress bar
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TokensForecastConfig {
+    /// Off by default: it adds text and may recolor the percentage
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default)]
+    pub mode: TokensForecastMode,
+
+    /// Context usage (% of the window) at which Claude Code auto-compacts
+    #[serde(default = "default_forecast_compact_at")]
+    pub compact_at: f64,
+
+    /// Recent assistant turns the growth rate is averaged over
+    #[serde(default = "default_forecast_window")]
+    pub window: u32,
+
+    /// Remaining turns at or below which the component turns warning colored
+    #[serde(default = "default_forecast_warning_turns")]
+    pub warning_turns: u32,
+
+    /// Remaining turns at or below which the component turns danger colored
+    #[serde(default = "default_forecast_danger_turns")]
+    pub danger_turns: u32,
+}
+
+impl Default for TokensForecastConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: TokensForecastMode::default(),
+            compact_at: default_forecast_compact_at(),
+            window: default_forecast_window(),
+            warning_turns: default_forecast_warning_turns(),
+            danger_turns: default_forecast_danger_turns(),
         }
     }
 }
@@ -680,6 +1026,9 @@ impl Default for UsageComponentConfig {
                 emoji_icon: "💰".to_string(),
                 nerd_icon: "\u{f155}".to_string(),
                 text_icon: "[U]".to_string(),
+                priority: 40,
+                min_width: 0,
+                max_width: 0,
             },
             display_mode: default_smart(),
             precision: default_precision(),
@@ -689,6 +1038,236 @@ impl Default for UsageComponentConfig {
     }
 }
 
+/// Budget reset period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetPeriod {
+    #[default]
+    Day,
+    /// ISO week, starting on Monday
+    Week,
+    Month,
+}
+
+/// Budget component configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BudgetComponentConfig {
+    #[serde(flatten)]
+    pub base: BaseComponentConfig,
+
+    /// Reset period for the spend total
+    #[serde(default)]
+    pub period: BudgetPeriod,
+
+    /// Spending limit in USD for one period; `0` shows the spend only
+    #[serde(default)]
+    pub limit: f64,
+
+    /// Timezone deciding when a period resets: `local`, `utc` or a fixed offset like `+08:00`
+    #[serde(default = "default_budget_timezone")]
+    pub timezone: String,
+
+    /// Precision for cost display
+    #[serde(default = "default_precision")]
+    pub precision: u32,
+
+    #[serde(default = "default_true")]
+    pub show_progress_bar: bool,
+
+    #[serde(default)]
+    pub show_percentage: bool,
+
+    #[serde(default = "default_budget_progress_width")]
+    pub progress_width: u32,
+
+    #[serde(default)]
+    pub progress_bar_chars: TokensProgressBarCharsConfig,
+
+    #[serde(default)]
+    pub colors: TokensColorConfig,
+
+    #[serde(default)]
+    pub thresholds: BudgetThresholdsConfig,
+
+    /// How long (ms) the cross-session spend total is reused before rescanning snapshots
+    #[serde(default = "default_budget_cache_ttl")]
+    pub cache_ttl: u64,
+}
+
+impl Default for BudgetComponentConfig {
+    fn default() -> Self {
+        Self {
+            base: BaseComponentConfig {
+                enabled: true,
+                icon_color: "green".to_string(),
+                text_color: "white".to_string(),
+                emoji_icon: "💸".to_string(),
+                nerd_icon: "\u{f0d6}".to_string(),
+                text_icon: "[$]".to_string(),
+                priority: 30,
+                min_width: 0,
+                max_width: 0,
+            },
+            period: BudgetPeriod::default(),
+            limit: 0.0,
+            timezone: default_budget_timezone(),
+            precision: default_precision(),
+            show_progress_bar: true,
+            show_percentage: false,
+            progress_width: default_budget_progress_width(),
+            progress_bar_chars: TokensProgressBarCharsConfig::default(),
+            colors: TokensColorConfig::default(),
+            thresholds: BudgetThresholdsConfig::default(),
+            cache_ttl: default_budget_cache_ttl(),
+        }
+    }
+}
+
+/// Percentages of the limit at which the budget turns warning / danger colored
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BudgetThresholdsConfig {
+    #[serde(default = "default_budget_warning_threshold")]
+    pub warning: f64,
+    #[serde(default = "default_budget_danger_threshold")]
+    pub danger: f64,
+}
+
+impl Default for BudgetThresholdsConfig {
+    fn default() -> Self {
+        Self {
+            warning: default_budget_warning_threshold(),
+            danger: default_budget_danger_threshold(),
+        }
+    }
+}
+
+/// How session durations are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeFormat {
+    /// Hours and minutes, e.g. `1h02m`
+    #[default]
+    Compact,
+    /// Total minutes, e.g. `62m`
+    Minutes,
+}
+
+/// Time component configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct TimeComponentConfig {
+    #[serde(flatten)]
+    pub base: BaseComponentConfig,
+
+    #[serde(default)]
+    pub format: TimeFormat,
+
+    /// Show the session wall time (`cost.total_duration_ms`)
+    #[serde(default = "default_true")]
+    pub show_wall: bool,
+
+    /// Show the time spent waiting on the API (`cost.total_api_duration_ms`)
+    #[serde(default = "default_true")]
+    pub show_api: bool,
+
+    /// Show API time as a percentage of wall time
+    #[serde(default)]
+    pub show_ratio: bool,
+
+    /// Append the current local time
+    #[serde(default)]
+    pub show_clock: bool,
+
+    /// strftime format of the clock
+    #[serde(default = "default_time_clock_format")]
+    pub clock_format: String,
+}
+
+impl Default for TimeComponentConfig {
+    fn default() -> Self {
+        Self {
+            base: BaseComponentConfig {
+                enabled: true,
+                icon_color: "pink".to_string(),
+                text_color: "white".to_string(),
+                emoji_icon: "⌛".to_string(),
+                nerd_icon: "\u{f252}".to_string(),
+                text_icon: "[D]".to_string(),
+                priority: 20,
+                min_width: 0,
+                max_width: 0,
+            },
+            format: TimeFormat::default(),
+            show_wall: true,
+            show_api: true,
+            show_ratio: false,
+            show_clock: false,
+            clock_format: default_time_clock_format(),
+        }
+    }
+}
+
+/// Cache component configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CacheComponentConfig {
+    #[serde(flatten)]
+    pub base: BaseComponentConfig,
+
+    /// Show the hit ratio of the newest assistant turn
+    #[serde(default = "default_true")]
+    pub show_turn: bool,
+
+    /// Show the hit ratio over the whole session
+    #[serde(default = "default_true")]
+    pub show_session: bool,
+
+    #[serde(default)]
+    pub colors: TokensColorConfig,
+
+    #[serde(default)]
+    pub thresholds: CacheThresholdsConfig,
+}
+
+impl Default for CacheComponentConfig {
+    fn default() -> Self {
+        Self {
+            base: BaseComponentConfig {
+                enabled: true,
+                icon_color: "bright_cyan".to_string(),
+                text_color: "white".to_string(),
+                emoji_icon: "♻️".to_string(),
+                nerd_icon: "\u{f1b8}".to_string(),
+                text_icon: "[C]".to_string(),
+                priority: 40,
+                min_width: 0,
+                max_width: 0,
+            },
+            show_turn: true,
+            show_session: true,
+            colors: TokensColorConfig::default(),
+            thresholds: CacheThresholdsConfig::default(),
+        }
+    }
+}
+
+/// Hit ratios (percent) below which the cache turns warning / danger colored
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CacheThresholdsConfig {
+    #[serde(default = "default_cache_warning_threshold")]
+    pub warning: f64,
+    #[serde(default = "default_cache_danger_threshold")]
+    pub danger: f64,
+}
+
+impl Default for CacheThresholdsConfig {
+    fn default() -> Self {
+        Self {
+            warning: default_cache_warning_threshold(),
+            danger: default_cache_danger_threshold(),
+        }
+    }
+}
+
 /// Status component configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct StatusComponentConfig {
@@ -722,6 +1301,9 @@ impl Default for StatusComponentConfig {
                 emoji_icon: "✨".to_string(),
                 nerd_icon: "\u{f00c}".to_string(),
                 text_icon: "[S]".to_string(),
+                priority: 50,
+                min_width: 0,
+                max_width: 0,
             },
             show_when_idle: false,
             show_recent_errors: default_true(),
@@ -847,6 +1429,43 @@ impl Default for StatusColorConfig {
     }
 }
 
+/// Custom component configuration
+///
+/// The command receives the `InputData` JSON on stdin and prints a JSON object
+/// (`text`, `icon`, `icon_color`, `text_color`, `visible`) on stdout.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CustomComponentConfig {
+    #[serde(flatten)]
+    pub base: BaseComponentConfig,
+
+    /// Program to execute
+    pub command: String,
+
+    /// Program arguments
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Extra environment variables for the program
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+
+    /// Execution timeout in milliseconds
+    #[serde(default = "default_custom_timeout")]
+    pub timeout: u64,
+
+    /// Reuse the last output for this many milliseconds (0 disables caching)
+    #[serde(default)]
+    pub cache_ttl: u64,
+
+    /// Optional single-letter key usable in preset strings
+    ///
+    /// `P M B T U S D C` belong to the built-in components (see
+    /// [`BUILTIN_PRESET_KEYS`]); claiming one of them is reported when the
+    /// config is loaded and the built-in keeps the letter.
+    #[serde(default)]
+    pub preset_key: Option<String>,
+}
+
 /// Multi-line configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct MultilineConfig {
@@ -1051,6 +1670,10 @@ fn default_auto() -> AutoDetect {
     AutoDetect::Auto("auto".to_string())
 }
 
+const fn default_component_priority() -> u8 {
+    50
+}
+
 fn default_white() -> String {
     "white".to_string()
 }
@@ -1091,6 +1714,98 @@ fn default_branch_operation_color() -> String {
     "red".to_string()
 }
 
+fn default_branch_operation_emoji() -> String {
+    "🔀".to_string()
+}
+
+fn default_branch_operation_nerd() -> String {
+    "\u{e727}".to_string()
+}
+
+fn default_branch_conflict_emoji() -> String {
+    "💥".to_string()
+}
+
+fn default_branch_conflict_nerd() -> String {
+    "\u{f071}".to_string()
+}
+
+fn default_branch_conflict_text() -> String {
+    "[!]".to_string()
+}
+
+fn default_branch_diff_emoji() -> String {
+    "📝".to_string()
+}
+
+fn default_branch_diff_nerd() -> String {
+    "\u{f440}".to_string()
+}
+
+fn default_branch_diff_text() -> String {
+    "Δ".to_string()
+}
+
+fn default_branch_diff_large_color() -> String {
+    "magenta".to_string()
+}
+
+const fn default_branch_diff_large_threshold() -> usize {
+    500
+}
+
+fn default_branch_rebase_label() -> String {
+    "REBASE".to_string()
+}
+
+fn default_branch_merge_label() -> String {
+    "MERGING".to_string()
+}
+
+fn default_branch_cherry_pick_label() -> String {
+    "CHERRY-PICKING".to_string()
+}
+
+fn default_branch_revert_label() -> String {
+    "REVERTING".to_string()
+}
+
+fn default_branch_bisect_label() -> String {
+    "BISECTING".to_string()
+}
+
+fn default_branch_commit_emoji() -> String {
+    "📍".to_string()
+}
+
+fn default_branch_commit_nerd() -> String {
+    "\u{f417}".to_string()
+}
+
+fn default_branch_commit_text() -> String {
+    "@".to_string()
+}
+
+fn default_branch_tag_emoji() -> String {
+    "🔖".to_string()
+}
+
+fn default_branch_tag_nerd() -> String {
+    "\u{f02b}".to_string()
+}
+
+fn default_branch_tag_text() -> String {
+    "#".to_string()
+}
+
+fn default_branch_age_emoji() -> String {
+    "🕒".to_string()
+}
+
+fn default_branch_age_nerd() -> String {
+    "\u{f017}".to_string()
+}
+
 const fn default_branch_max_length() -> u32 {
     20
 }
@@ -1099,6 +1814,22 @@ const fn default_branch_cache_ttl() -> u64 {
     5_000
 }
 
+const fn default_custom_timeout() -> u64 {
+    1000
+}
+
+const fn default_component_timeout() -> u64 {
+    1_000
+}
+
+const fn default_render_budget() -> u64 {
+    1_500
+}
+
+fn default_timeout_placeholder() -> String {
+    "…".to_string()
+}
+
 const fn default_branch_git_timeout() -> u32 {
     1_000
 }
@@ -1131,6 +1862,38 @@ fn default_right_bracket() -> String {
     "]".to_string()
 }
 
+fn default_budget_timezone() -> String {
+    "local".to_string()
+}
+
+const fn default_budget_progress_width() -> u32 {
+    10
+}
+
+const fn default_budget_cache_ttl() -> u64 {
+    30_000
+}
+
+const fn default_budget_warning_threshold() -> f64 {
+    75.0
+}
+
+const fn default_budget_danger_threshold() -> f64 {
+    90.0
+}
+
+const fn default_cache_warning_threshold() -> f64 {
+    70.0
+}
+
+const fn default_cache_danger_threshold() -> f64 {
+    40.0
+}
+
+fn default_time_clock_format() -> String {
+    "%H:%M".to_string()
+}
+
 fn default_safe_color() -> String {
     "green".to_string()
 }
@@ -1159,6 +1922,22 @@ const fn default_critical_threshold() -> f64 {
     95.0
 }
 
+const fn default_forecast_compact_at() -> f64 {
+    85.0
+}
+
+const fn default_forecast_window() -> u32 {
+    5
+}
+
+const fn default_forecast_warning_turns() -> u32 {
+    10
+}
+
+const fn default_forecast_danger_turns() -> u32 {
+    3
+}
+
 fn default_context_windows() -> HashMap<String, u64> {
     let mut map = HashMap::new();
     map.insert("default".to_string(), 200_000);
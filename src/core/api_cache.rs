@@ -0,0 +1,362 @@
+//! On-disk response cache for API widgets
+//!
+//! 缓存键由最终请求（URL、方法、headers）计算得出，条目保存在
+//! `<storage>/statusline-pro/cache/api/` 下。过期条目会立即返回给渲染流程，
+//! 同时拉起一个脱离当前进程的子进程在后台刷新（stale-while-revalidate），
+//! 状态栏进程本身不再为慢速中转站 API 阻塞。
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::storage;
+
+/// Hidden CLI subcommand used by the detached refresh process
+pub const REFRESH_SUBCOMMAND: &str = "__refresh-widget-cache";
+
+/// Encoded request body
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiBody {
+    /// Raw payload sent as-is (JSON, plain text, ...)
+    Raw(String),
+    /// `application/x-www-form-urlencoded` fields
+    Form(Vec<(String, String)>),
+}
+
+/// Fully resolved HTTP request issued by an API widget
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiRequest {
+    pub url: String,
+    pub method: String,
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: Option<ApiBody>,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    pub timeout_ms: u64,
+}
+
+impl ApiRequest {
+    /// Stable cache key derived from URL, method, headers, query and body
+    #[must_use]
+    pub fn cache_key(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.url.hash(&mut hasher);
+        self.method.hash(&mut hasher);
+        self.headers.hash(&mut hasher);
+        self.query.hash(&mut hasher);
+        self.body.hash(&mut hasher);
+        self.content_type.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
+
+    /// Perform the request synchronously and parse the JSON body
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the request fails or the body is not valid JSON.
+    pub fn send(&self) -> Result<Value> {
+        let mut request =
+            ureq::request(&self.method, &self.url).timeout(Duration::from_millis(self.timeout_ms));
+
+        for (key, value) in &self.query {
+            request = request.query(key, value);
+        }
+
+        for (key, value) in &self.headers {
+            request = request.set(key, value);
+        }
+
+        request = request.set("User-Agent", "claude-code-statusline/3.0");
+
+        let response = match &self.body {
+            Some(ApiBody::Raw(payload)) => request
+                .set(
+                    "Content-Type",
+                    self.content_type.as_deref().unwrap_or("application/json"),
+                )
+                .send_string(payload),
+            Some(ApiBody::Form(fields)) => {
+                let fields = fields
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value.as_str()))
+                    .collect::<Vec<_>>();
+                request.send_form(&fields)
+            }
+            None => request.call(),
+        }
+        .context("ureq request failed")?;
+        response
+            .into_json()
+            .context("Failed to parse JSON response")
+    }
+}
+
+/// Persisted response body with its fetch time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    /// Fetch time in milliseconds since the Unix epoch
+    pub fetched_at: i64,
+    pub response: Value,
+}
+
+impl CachedResponse {
+    /// Age of the entry in milliseconds
+    #[must_use]
+    pub fn age_ms(&self) -> u64 {
+        u64::try_from(Utc::now().timestamp_millis() - self.fetched_at).unwrap_or(0)
+    }
+}
+
+/// Payload handed to the detached refresh process over stdin
+#[derive(Debug, Serialize, Deserialize)]
+struct RefreshJob {
+    cache_dir: PathBuf,
+    request: ApiRequest,
+}
+
+/// Directory-backed cache of API widget responses
+#[derive(Debug, Clone)]
+pub struct ApiResponseCache {
+    dir: PathBuf,
+}
+
+impl Default for ApiResponseCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ApiResponseCache {
+    /// Cache rooted in the shared statusline-pro cache directory
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_dir(storage::cache_dir().join("api"))
+    }
+
+    #[must_use]
+    pub const fn with_dir(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn entry_path(&self, request: &ApiRequest) -> PathBuf {
+        self.dir.join(format!("{}.json", request.cache_key()))
+    }
+
+    fn lock_path(&self, request: &ApiRequest) -> PathBuf {
+        self.dir.join(format!("{}.lock", request.cache_key()))
+    }
+
+    /// Load the cached response for `request`, if any
+    #[must_use]
+    pub fn load(&self, request: &ApiRequest) -> Option<CachedResponse> {
+        let content = fs::read_to_string(self.entry_path(request)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Persist `response` for `request` (atomic tmp + rename)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory or entry cannot be written.
+    pub fn store(&self, request: &ApiRequest, response: &Value) -> Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create cache dir {}", self.dir.display()))?;
+        let entry = CachedResponse {
+            fetched_at: Utc::now().timestamp_millis(),
+            response: response.clone(),
+        };
+        let path = self.entry_path(request);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec(&entry)?)?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    /// Start a detached process that refreshes the entry for `request`.
+    ///
+    /// A lock file prevents concurrent refreshes of the same entry; stale locks
+    /// (older than twice the request timeout) are reclaimed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the refresh process cannot be started.
+    pub fn spawn_refresh(&self, request: &ApiRequest) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let lock_path = self.lock_path(request);
+        let lock_ttl = Duration::from_millis(request.timeout_ms.saturating_mul(2).max(10_000));
+        if !acquire_lock(&lock_path, lock_ttl)? {
+            return Ok(());
+        }
+
+        let job = RefreshJob {
+            cache_dir: self.dir.clone(),
+            request: request.clone(),
+        };
+        let spawned = spawn_refresh_process(&job);
+        if spawned.is_err() {
+            let _ = fs::remove_file(&lock_path);
+        }
+        spawned
+    }
+
+    /// Fetch `request` and update the cache entry, releasing the refresh lock.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the request fails or the entry cannot be stored.
+    pub fn refresh(&self, request: &ApiRequest) -> Result<()> {
+        let result = request
+            .send()
+            .and_then(|response| self.store(request, &response));
+        let _ = fs::remove_file(self.lock_path(request));
+        result
+    }
+}
+
+/// Entry point of the detached refresh process: reads a job from `reader`.
+///
+/// # Errors
+///
+/// Returns an error when the job payload is malformed or the refresh fails.
+pub fn run_refresh<R: Read>(mut reader: R) -> Result<()> {
+    let mut payload = String::new();
+    reader.read_to_string(&mut payload)?;
+    let job: RefreshJob =
+        serde_json::from_str(&payload).context("Invalid widget cache refresh job")?;
+    ApiResponseCache::with_dir(job.cache_dir).refresh(&job.request)
+}
+
+fn acquire_lock(path: &Path, ttl: Duration) -> Result<bool> {
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(_) => Ok(true),
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+            let expired = fs::metadata(path)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .is_none_or(|age| age >= ttl);
+            if expired {
+                let _ = fs::remove_file(path);
+                Ok(OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(path)
+                    .is_ok())
+            } else {
+                Ok(false)
+            }
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn spawn_refresh_process(job: &RefreshJob) -> Result<()> {
+    let exe = std::env::current_exe().context("Failed to locate current executable")?;
+    let mut child = Command::new(exe)
+        .arg(REFRESH_SUBCOMMAND)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to spawn widget cache refresh")?;
+
+    let payload = serde_json::to_vec(job)?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("refresh process stdin unavailable"))?;
+    stdin.write_all(&payload)?;
+    drop(stdin);
+
+    // 不阻塞等待子进程：状态栏进程退出后刷新进程继续运行；
+    // 长驻进程中由该线程回收子进程，避免僵尸进程
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_request() -> ApiRequest {
+        ApiRequest {
+            url: "http://127.0.0.1:9/balance".to_string(),
+            method: "GET".to_string(),
+            headers: BTreeMap::from([("X-API-Key".to_string(), "secret".to_string())]),
+            query: BTreeMap::new(),
+            body: None,
+            content_type: None,
+            timeout_ms: 200,
+        }
+    }
+
+    #[test]
+    fn cache_key_depends_on_headers() {
+        let request = sample_request();
+        let mut other = request.clone();
+        other
+            .headers
+            .insert("X-API-Key".to_string(), "rotated".to_string());
+
+        assert_eq!(request.cache_key(), sample_request().cache_key());
+        assert_ne!(request.cache_key(), other.cache_key());
+    }
+
+    #[test]
+    fn cache_key_depends_on_query_and_body() {
+        let request = sample_request();
+        let mut with_query = request.clone();
+        with_query.query.insert("page".to_string(), "1".to_string());
+        let mut with_body = request.clone();
+        with_body.body = Some(ApiBody::Raw("{}".to_string()));
+
+        assert_ne!(request.cache_key(), with_query.cache_key());
+        assert_ne!(request.cache_key(), with_body.cache_key());
+        assert_ne!(with_query.cache_key(), with_body.cache_key());
+    }
+
+    #[test]
+    fn store_and_load_round_trip() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let cache = ApiResponseCache::with_dir(temp.path().to_path_buf());
+        let request = sample_request();
+
+        assert!(cache.load(&request).is_none());
+        cache.store(&request, &json!({"balance": 12.5}))?;
+
+        let entry = cache
+            .load(&request)
+            .ok_or_else(|| anyhow!("entry missing"))?;
+        assert_eq!(entry.response, json!({"balance": 12.5}));
+        assert!(entry.age_ms() < 60_000);
+        Ok(())
+    }
+
+    #[test]
+    fn refresh_lock_is_exclusive_until_expired() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let lock = temp.path().join("entry.lock");
+
+        assert!(acquire_lock(&lock, Duration::from_secs(60))?);
+        assert!(!acquire_lock(&lock, Duration::from_secs(60))?);
+        assert!(acquire_lock(&lock, Duration::ZERO)?);
+        Ok(())
+    }
+}
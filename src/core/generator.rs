@@ -9,12 +9,16 @@ use std::time::{Duration, Instant};
 
 use anyhow::Result;
 
-use crate::components::{ComponentFactory, ComponentOutput, RenderContext, TerminalCapabilities};
-use crate::config::Config;
-use crate::core::{InputData, MultiLineRenderer};
+use crate::components::{
+    Component, ComponentFactory, ComponentOutput, RenderContext, TerminalCapabilities,
+};
+use crate::config::{Config, CustomComponentConfig, BUILTIN_PRESET_KEYS};
+use crate::core::output::{ComponentEntry, ComponentTiming, RenderTiming};
+use crate::core::{InputData, MultiLineRenderer, OutputFormat, StatuslineOutput};
 use crate::storage::{self, ProjectResolver};
 use crate::terminal::detector::TerminalDetector;
-use crate::themes::{create_theme_renderer, ThemeRenderer};
+use crate::themes::{create_theme_renderer, OutputTarget, ThemeRenderer};
+use crate::utils::client_env::ClientEnv;
 
 const POWERLINE_PALETTE: &[(&str, &str)] = &[
     ("project", "blue"),
@@ -23,6 +27,9 @@ const POWERLINE_PALETTE: &[(&str, &str)] = &[
     ("tokens", "yellow"),
     ("usage", "orange"),
     ("status", "magenta"),
+    ("budget", "green"),
+    ("time", "pink"),
+    ("cache", "bright_cyan"),
 ];
 
 const CAPSULE_PALETTE: &[(&str, &str)] = &[
@@ -32,8 +39,14 @@ const CAPSULE_PALETTE: &[(&str, &str)] = &[
     ("tokens", "yellow"),
     ("usage", "bright_orange"),
     ("status", "bright_magenta"),
+    ("budget", "bright_green"),
+    ("time", "bright_pink"),
+    ("cache", "bright_cyan"),
 ];
 
+/// Last-output files of sessions idle for longer than this are removed
+const LAST_OUTPUT_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
 /// Generator options
 #[derive(Debug, Clone)]
 pub struct GeneratorOptions {
@@ -80,13 +93,20 @@ impl GeneratorOptions {
     }
 }
 
+fn duration_ms(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+fn elapsed_ms(since: Instant) -> u64 {
+    duration_ms(since.elapsed())
+}
+
 /// Core statusline generator
 ///
 /// Integrates all components to generate the final statusline
 pub struct StatuslineGenerator {
     config: Arc<Config>,
     component_registry: HashMap<String, Box<dyn ComponentFactory>>,
-    terminal_detector: TerminalDetector,
     theme_renderer: Box<dyn ThemeRenderer>,
     multi_line_renderer: MultiLineRenderer,
     last_update: Option<Instant>,
@@ -99,13 +119,18 @@ pub struct StatuslineGenerator {
     /// See `GeneratorOptions::preview_mode`: when true, `generate` is
     /// side-effect free (no storage init, no snapshot persistence).
     preview_mode: bool,
+    /// Most recent output per component, used when a component times out
+    last_outputs: HashMap<String, ComponentOutput>,
+    /// Session the `last_outputs` were loaded for
+    last_outputs_session: Option<String>,
+    /// Width reported by the caller (daemon clients), used when the config sets none
+    terminal_width: Option<usize>,
 }
 
 impl StatuslineGenerator {
     /// Create a new generator with the given configuration and options
     pub fn new(config: Config, options: GeneratorOptions) -> Self {
         let config_arc = Arc::new(config);
-        let terminal_detector = TerminalDetector::new();
 
         // Create theme renderer based on configuration
         let theme_renderer = create_theme_renderer(&config_arc.theme);
@@ -124,7 +149,6 @@ impl StatuslineGenerator {
         let mut generator = Self {
             config: Arc::clone(&config_arc),
             component_registry: HashMap::new(),
-            terminal_detector,
             theme_renderer,
             multi_line_renderer,
             last_update: None,
@@ -135,6 +159,9 @@ impl StatuslineGenerator {
             active_project_id: None,
             config_base_dir,
             preview_mode: options.preview_mode,
+            last_outputs: HashMap::new(),
+            last_outputs_session: None,
+            terminal_width: None,
         };
         drop(config_arc);
 
@@ -156,10 +183,14 @@ impl StatuslineGenerator {
     /// Initialize component registry
     fn initialize_components(&mut self) {
         use crate::components::{
-            BranchComponentFactory, ModelComponentFactory, ProjectComponentFactory,
-            StatusComponentFactory, TokensComponentFactory, UsageComponentFactory,
+            BranchComponentFactory, BudgetComponentFactory, CacheComponentFactory,
+            CustomComponentFactory, ModelComponentFactory, ProjectComponentFactory,
+            StatusComponentFactory, TimeComponentFactory, TokensComponentFactory,
+            UsageComponentFactory,
         };
 
+        self.component_registry.clear();
+
         // Register all component factories
         self.component_registry
             .insert("project".to_string(), Box::new(ProjectComponentFactory));
@@ -173,6 +204,22 @@ impl StatuslineGenerator {
             .insert("status".to_string(), Box::new(StatusComponentFactory));
         self.component_registry
             .insert("usage".to_string(), Box::new(UsageComponentFactory));
+        self.component_registry
+            .insert("budget".to_string(), Box::new(BudgetComponentFactory));
+        self.component_registry
+            .insert("time".to_string(), Box::new(TimeComponentFactory));
+        self.component_registry
+            .insert("cache".to_string(), Box::new(CacheComponentFactory));
+
+        // Register user-defined command components; built-in names always win
+        // (conflicts are reported once by the config loader)
+        for name in self.config.components.custom.keys() {
+            if self.component_registry.contains_key(name) {
+                continue;
+            }
+            self.component_registry
+                .insert(name.clone(), Box::new(CustomComponentFactory::new(name)));
+        }
     }
 
     fn refresh_multiline_renderer(&mut self) {
@@ -184,7 +231,7 @@ impl StatuslineGenerator {
     /// Apply a preset configuration
     fn apply_preset(&mut self, preset: &str) {
         // Parse preset string (e.g., "PMBTUS" -> ["P", "M", "B", "T", "U", "S"])
-        let component_map = Self::parse_preset(preset);
+        let component_map = Self::parse_preset(preset, &self.config.components.custom);
 
         // Update config.components.order based on preset
         if let Some(ref mut config) = Arc::get_mut(&mut self.config) {
@@ -206,22 +253,32 @@ impl StatuslineGenerator {
     }
 
     /// Parse preset string into component order
-    fn parse_preset(preset: &str) -> Vec<String> {
+    ///
+    /// Custom components can claim a letter through `preset_key`; built-in
+    /// letters take precedence.
+    fn parse_preset(preset: &str, custom: &HashMap<String, CustomComponentConfig>) -> Vec<String> {
         let mut seen = HashSet::new();
 
         preset
             .chars()
-            .filter_map(|c| match c.to_ascii_uppercase() {
-                'P' => Some("project"),
-                'M' => Some("model"),
-                'B' => Some("branch"),
-                'T' => Some("tokens"),
-                'U' => Some("usage"),
-                'S' => Some("status"),
-                _ => None,
+            .filter_map(|c| {
+                let key = c.to_ascii_uppercase();
+                let builtin = BUILTIN_PRESET_KEYS
+                    .iter()
+                    .find(|(letter, _)| *letter == key)
+                    .map(|(_, name)| *name);
+                builtin.map(str::to_string).or_else(|| {
+                    custom
+                        .iter()
+                        .find(|(_, config)| {
+                            config.preset_key.as_deref().is_some_and(|preset_key| {
+                                preset_key.eq_ignore_ascii_case(&key.to_string())
+                            })
+                        })
+                        .map(|(name, _)| name.clone())
+                })
             })
-            .filter(|name| seen.insert(*name))
-            .map(std::string::ToString::to_string)
+            .filter(|name| seen.insert(name.clone()))
             .collect()
     }
 
@@ -254,6 +311,63 @@ impl StatuslineGenerator {
     /// Returns an error if component rendering fails or if required
     /// configuration initialization steps cannot complete successfully.
     pub async fn generate(&mut self, input_data: InputData) -> Result<String> {
+        self.persist_input(&input_data).await?;
+
+        if !self.should_update() {
+            if let Some(ref last_result) = self.last_result {
+                return Ok(last_result.clone());
+            }
+        }
+
+        let result = self
+            .render_output(input_data, OutputTarget::Ansi)
+            .await?
+            .text();
+
+        // Cache result
+        if !self.disable_cache {
+            self.last_result = Some(result.clone());
+        }
+
+        Ok(result)
+    }
+
+    /// Generate the statusline as structured data
+    ///
+    /// Unlike [`Self::generate`] this is never throttled: every call renders.
+    /// # Errors
+    ///
+    /// Returns an error if component rendering fails or if required
+    /// configuration initialization steps cannot complete successfully.
+    pub async fn generate_output(&mut self, input_data: InputData) -> Result<StatuslineOutput> {
+        self.persist_input(&input_data).await?;
+        self.render_output(input_data, OutputTarget::Ansi).await
+    }
+
+    /// Generate the statusline in the given output format
+    ///
+    /// ANSI goes through the throttled [`Self::generate`]; tmux and shell
+    /// prompts are rendered fresh in their own escape syntax, and JSON
+    /// serializes [`Self::generate_output`].
+    /// # Errors
+    ///
+    /// Returns an error if rendering or JSON serialization fails.
+    pub async fn generate_formatted(
+        &mut self,
+        input_data: InputData,
+        format: OutputFormat,
+    ) -> Result<String> {
+        match format.target() {
+            Some(OutputTarget::Ansi) => self.generate(input_data).await,
+            Some(target) => {
+                self.persist_input(&input_data).await?;
+                Ok(self.render_output(input_data, target).await?.text())
+            }
+            None => self.generate_output(input_data).await?.to_json(),
+        }
+    }
+
+    async fn persist_input(&mut self, input_data: &InputData) -> Result<()> {
         // Preview mode(TUI 编辑器)完全跳过任何持久化副作用:
         // 1. `ensure_storage_ready` 会把 mock 的 project_id 注册成全局状态,
         //    再初始化 storage 子系统,会在 `~/.claude/.../sessions/` 下建目录;
@@ -261,27 +375,33 @@ impl StatuslineGenerator {
         //    session snapshot,污染用户真实的 conversation 使用量/成本数据。
         // 两者都不是渲染本身必须的,preview 只需要纯粹的 "这份 config 渲染出来
         // 长什么样",所以直接短路。
-        if !self.preview_mode {
-            self.ensure_storage_ready(&input_data).await?;
-
-            if let Ok(snapshot_value) = serde_json::to_value(&input_data) {
-                if let Err(err) = storage::update_session_snapshot(&snapshot_value).await {
-                    // Only log unexpected errors; missing session ID is expected in some scenarios
-                    if !err.to_string().contains("No session ID found") {
-                        eprintln!("[statusline] failed to update session snapshot: {err}");
-                    }
-                }
-            }
+        if self.preview_mode {
+            return Ok(());
         }
 
-        if !self.should_update() {
-            if let Some(ref last_result) = self.last_result {
-                return Ok(last_result.clone());
+        self.ensure_storage_ready(input_data).await?;
+
+        if let Ok(snapshot_value) = serde_json::to_value(input_data) {
+            if let Err(err) = storage::update_session_snapshot(&snapshot_value).await {
+                // Only log unexpected errors; missing session ID is expected in some scenarios
+                if !err.to_string().contains("No session ID found") {
+                    eprintln!("[statusline] failed to update session snapshot: {err}");
+                }
             }
         }
+        Ok(())
+    }
+
+    async fn render_output(
+        &mut self,
+        input_data: InputData,
+        target: OutputTarget,
+    ) -> Result<StatuslineOutput> {
+        let started = Instant::now();
 
         // Detect terminal capabilities
-        let capabilities = self.detect_terminal_capabilities();
+        let mut capabilities = self.detect_terminal_capabilities(&input_data.env);
+        capabilities.output_target = target;
 
         // Create render context. preview_mode 从 generator 透传到组件,
         // 让 Usage/Tokens 这种依赖 storage 的组件能跳过 storage 调用 ——
@@ -296,69 +416,97 @@ impl StatuslineGenerator {
         };
 
         // Render components
-        let component_results = self.render_components(&context).await?;
+        let (rendered, component_timings) = self.render_components(&context).await?;
+        let components_ms = elapsed_ms(started);
+
+        let entries = rendered
+            .iter()
+            .map(|output| {
+                let name = output.component_name.as_deref().unwrap_or_default();
+                ComponentEntry::new(output, self.theme_color(name))
+            })
+            .collect();
 
         // Apply theme rendering
-        let colors = self.extract_component_colors(&component_results);
-        let main_line = self
-            .theme_renderer
-            .render(&component_results, &colors, &context)?;
+        let visible: Vec<ComponentOutput> = rendered
+            .into_iter()
+            .filter(|output| output.visible)
+            .collect();
+        let colors = self.extract_component_colors(&visible);
+        let main_line = self.theme_renderer.render_within(
+            &visible,
+            &colors,
+            &context,
+            self.detect_terminal_width(&context.input.env),
+        )?;
 
         // Render multiline extensions
+        let multiline_started = Instant::now();
         let extension_result = self
             .multi_line_renderer
             .render_extension_lines(&context)
             .await;
+        let multiline_ms = elapsed_ms(multiline_started);
 
         let mut lines = Vec::new();
         if !main_line.is_empty() {
             lines.push(main_line);
         }
 
+        let mut rows = Vec::new();
         if extension_result.success {
-            lines.extend(extension_result.lines);
+            rows = StatuslineOutput::plain_rows(&extension_result.lines);
+            // 多行内容混有 widget 自带的 ANSI,排版截断后逐行转换
+            lines.extend(extension_result.lines.iter().map(|line| target.apply(line)));
         } else if let Some(err) = extension_result.error {
             eprintln!("[statusline] multiline render failed: {err}");
         }
 
-        let result = lines.join("\n");
-
-        // Cache result
-        if !self.disable_cache {
-            self.last_result = Some(result.clone());
-        }
-
-        Ok(result)
+        Ok(StatuslineOutput {
+            theme: self.config.theme.clone(),
+            components: entries,
+            rows,
+            timing: RenderTiming {
+                total_ms: elapsed_ms(started),
+                components_ms,
+                multiline_ms,
+                components: component_timings,
+            },
+            lines,
+        })
     }
 
     fn extract_component_colors(&self, components: &[ComponentOutput]) -> Vec<String> {
         let mut colors = Vec::with_capacity(components.len());
-        let theme_palette = match self.config.theme.as_str() {
-            "powerline" => Some(POWERLINE_PALETTE),
-            "capsule" => Some(CAPSULE_PALETTE),
-            _ => None,
-        };
 
         for component in components {
             let Some(name) = component.component_name.as_deref() else {
                 continue;
             };
-
-            let color = theme_palette
-                .and_then(|palette| {
-                    palette
-                        .iter()
-                        .find(|(component_name, _)| *component_name == name)
-                        .map(|(_, color)| (*color).to_string())
-                })
-                .unwrap_or_else(|| self.component_config_color(name));
-
-            colors.push(color);
+            colors.push(self.theme_color(name));
         }
 
         colors
     }
 
+    /// Segment color of `name` under the current theme
+    fn theme_color(&self, name: &str) -> String {
+        let theme_palette = match self.config.theme.as_str() {
+            "powerline" => Some(POWERLINE_PALETTE),
+            "capsule" => Some(CAPSULE_PALETTE),
+            _ => None,
+        };
+
+        theme_palette
+            .and_then(|palette| {
+                palette
+                    .iter()
+                    .find(|(component_name, _)| *component_name == name)
+                    .map(|(_, color)| (*color).to_string())
+            })
+            .unwrap_or_else(|| self.component_config_color(name))
+    }
+
     fn component_config_color(&self, name: &str) -> String {
         match name {
             "project" => self.config.components.project.base.icon_color.clone(),
@@ -367,6 +515,15 @@ impl StatuslineGenerator {
             "tokens" => self.config.components.tokens.base.icon_color.clone(),
             "usage" => self.config.components.usage.base.icon_color.clone(),
             "status" => self.config.components.status.base.icon_color.clone(),
+            "budget" => self.config.components.budget.base.icon_color.clone(),
+            "time" => self.config.components.time.base.icon_color.clone(),
+            "cache" => self.config.components.cache.base.icon_color.clone(),
+            other if self.config.components.custom.contains_key(other) => {
+                self.config.components.custom.get(other).map_or_else(
+                    || "blue".to_string(),
+                    |custom| custom.base.icon_color.clone(),
+                )
+            }
             other => {
                 eprintln!(
                     "[statusline] unknown component '{other}' when resolving theme colors, fallback to blue"
@@ -376,9 +533,9 @@ impl StatuslineGenerator {
         }
     }
 
-    /// Detect terminal capabilities
-    fn detect_terminal_capabilities(&self) -> TerminalCapabilities {
-        let caps = self.terminal_detector.detect(
+    /// Detect terminal capabilities from the environment the render is for
+    fn detect_terminal_capabilities(&self, env: &ClientEnv) -> TerminalCapabilities {
+        let caps = TerminalDetector::with_env(env.clone()).detect(
             &self.config.style.enable_colors,
             &self.config.style.enable_emoji,
             &self.config.style.enable_nerd_font,
@@ -392,16 +549,34 @@ impl StatuslineGenerator {
             eprintln!("  - color_support: {:?}", caps.color_support);
             eprintln!("  - supports_emoji: {}", caps.supports_emoji);
             eprintln!("  - supports_nerd_font: {}", caps.supports_nerd_font);
-            eprintln!("  - TERM_PROGRAM: {:?}", std::env::var("TERM_PROGRAM"));
+            eprintln!("  - TERM_PROGRAM: {:?}", env.var("TERM_PROGRAM"));
         }
 
         caps
     }
 
-    /// Render all enabled components
-    async fn render_components(&self, context: &RenderContext) -> Result<Vec<ComponentOutput>> {
-        let mut results = Vec::new();
+    /// Columns available for the main line; preview mode renders at full width
+    fn detect_terminal_width(&self, env: &ClientEnv) -> Option<usize> {
+        if self.preview_mode {
+            return None;
+        }
+        if self.config.terminal.width == 0 && self.terminal_width.is_some() {
+            return self.terminal_width;
+        }
+        TerminalDetector::with_env(env.clone()).detect_width(self.config.terminal.width)
+    }
 
+    /// Render all enabled components concurrently
+    ///
+    /// Each component gets its own deadline (capped by the global render
+    /// budget). A component that misses it is replaced by its last known
+    /// output, or by `components.timeout_placeholder` when there is none.
+    /// Hidden outputs are kept (named, in order) so structured output can
+    /// report them; the theme only sees the visible ones.
+    async fn render_components(
+        &mut self,
+        context: &RenderContext,
+    ) -> Result<(Vec<ComponentOutput>, Vec<ComponentTiming>)> {
         // Get component order from configuration or use default
         let default_order = vec![
             "project".to_string(),
@@ -418,8 +593,13 @@ impl StatuslineGenerator {
             self.config.components.order.clone()
         };
 
-        // Render each component in order
+        let started = Instant::now();
+        let budget = Duration::from_millis(self.config.components.render_budget);
+        let default_timeout = Duration::from_millis(self.config.components.timeout);
+
+        // Spawn every component first so slow ones do not delay the rest
         let mut seen = HashSet::new();
+        let mut pending = Vec::new();
         for component_name in &component_order {
             if !seen.insert(component_name.clone()) {
                 continue;
@@ -429,24 +609,166 @@ impl StatuslineGenerator {
                 continue;
             };
 
-            let component = factory.create(&self.config);
+            let component: Arc<dyn Component> = Arc::from(factory.create(&self.config));
             if !component.is_enabled(context) {
                 continue;
             }
 
-            let mut output = component.render(context).await;
-            if !output.visible {
-                continue;
-            }
+            let deadline = component
+                .render_timeout()
+                .unwrap_or(default_timeout)
+                .min(budget);
+            let task_component = Arc::clone(&component);
+            let task_context = context.clone();
+            let handle = tokio::spawn(async move {
+                let started = Instant::now();
+                let output = task_component.render(&task_context).await;
+                (output, started.elapsed())
+            });
+            pending.push((component_name.clone(), component, deadline, handle));
+        }
+
+        self.load_last_outputs(context.input.session_id.as_deref());
+
+        let mut results = Vec::new();
+        let mut timings = Vec::new();
+        let mut changed = false;
+        for (component_name, component, deadline, mut handle) in pending {
+            let remaining = deadline.saturating_sub(started.elapsed());
+            let mut elapsed = None;
+            let mut output = match tokio::time::timeout(remaining, &mut handle).await {
+                Ok(Ok((output, render_time))) => {
+                    elapsed = Some(render_time);
+                    if self.last_outputs.get(&component_name) != Some(&output) {
+                        self.last_outputs
+                            .insert(component_name.clone(), output.clone());
+                        changed = true;
+                    }
+                    output
+                }
+                Ok(Err(err)) => {
+                    eprintln!("[statusline] component '{component_name}' failed: {err}");
+                    self.timeout_fallback(&component_name, component.as_ref(), context)
+                }
+                Err(_) => {
+                    handle.abort();
+                    if self.config.debug {
+                        eprintln!(
+                            "[statusline] component '{component_name}' timed out after {}ms",
+                            deadline.as_millis()
+                        );
+                    }
+                    self.timeout_fallback(&component_name, component.as_ref(), context)
+                }
+            };
 
-            output.set_component_name(component_name.clone());
+            timings.push(ComponentTiming {
+                name: component_name.clone(),
+                elapsed_ms: elapsed.map_or_else(|| elapsed_ms(started), duration_ms),
+                timed_out: elapsed.is_none(),
+            });
+
+            output.set_component_name(component_name);
+            if let Some(base) = component.base_config(context) {
+                output.set_layout(base);
+            }
             results.push(output);
         }
 
-        Ok(results)
+        if changed {
+            self.store_last_outputs();
+        }
+
+        Ok((results, timings))
+    }
+
+    fn timeout_fallback(
+        &self,
+        component_name: &str,
+        component: &dyn Component,
+        context: &RenderContext,
+    ) -> ComponentOutput {
+        if let Some(last) = self.last_outputs.get(component_name) {
+            return last.clone();
+        }
+
+        let placeholder = &self.config.components.timeout_placeholder;
+        let Some(base) = component.base_config(context) else {
+            return ComponentOutput::hidden();
+        };
+        if placeholder.is_empty() {
+            return ComponentOutput::hidden();
+        }
+
+        ComponentOutput::new(placeholder.clone())
+            .with_icon(component.select_icon(context).unwrap_or_default())
+            .with_icon_color(base.icon_color.clone())
+            .with_text_color(base.text_color.clone())
+    }
+
+    /// Last known outputs are kept per session so a timed-out component can
+    /// fall back to what the previous statusline process rendered, without
+    /// showing another concurrent session's numbers.
+    fn last_outputs_path(&self, session_id: Option<&str>) -> Option<PathBuf> {
+        if self.preview_mode {
+            return None;
+        }
+        let file_name: String = session_id?
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Some(
+            storage::cache_dir()
+                .join("last-output")
+                .join(format!("{file_name}.json")),
+        )
+    }
+
+    fn load_last_outputs(&mut self, session_id: Option<&str>) {
+        if self.last_outputs_session.as_deref() == session_id {
+            return;
+        }
+        self.last_outputs_session = session_id.map(str::to_string);
+        self.last_outputs = self
+            .last_outputs_path(session_id)
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default();
+    }
+
+    fn store_last_outputs(&self) {
+        let Some(path) = self.last_outputs_path(self.last_outputs_session.as_deref()) else {
+            return;
+        };
+        let result = (|| -> Result<()> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+                // 每个会话一个文件:新会话首次写入时顺带清理早已结束的会话
+                if !path.exists() {
+                    crate::utils::prune_stale_files(parent, LAST_OUTPUT_RETENTION);
+                }
+            }
+            let tmp_path = path.with_extension(format!("tmp{}", std::process::id()));
+            std::fs::write(&tmp_path, serde_json::to_vec(&self.last_outputs)?)?;
+            std::fs::rename(&tmp_path, &path)?;
+            Ok(())
+        })();
+        if let Err(err) = result {
+            if self.config.debug {
+                eprintln!("[statusline] failed to persist last component outputs: {err}");
+            }
+        }
     }
 
     async fn ensure_storage_ready(&mut self, input_data: &InputData) -> Result<()> {
+        // 项目 id 每次按本次输入重新解析,daemon 里上一个请求的项目不能沿用
+        ProjectResolver::set_global_project_id(None);
         if let Some(transcript) = input_data.transcript_path.as_deref() {
             ProjectResolver::set_global_project_id_from_transcript(Some(transcript));
         }
@@ -466,11 +788,20 @@ impl StatuslineGenerator {
             .await?;
             self.storage_initialized = true;
             self.active_project_id = Some(project_id);
+        } else {
+            // daemon 在同一进程里为多个项目各留一个 generator,切回时恢复运行时状态
+            storage::activate_storage(Some(project_id), &self.config.storage);
         }
 
         Ok(())
     }
 
+    /// Use `width` when the config does not pin one, instead of detecting it
+    /// from this process (whose terminal may not be the caller's)
+    pub const fn set_terminal_width(&mut self, width: Option<usize>) {
+        self.terminal_width = width;
+    }
+
     /// Get the current configuration
     #[must_use]
     pub fn config(&self) -> &Config {
@@ -481,6 +812,7 @@ impl StatuslineGenerator {
     pub fn update_config(&mut self, config: Config) {
         self.config = Arc::new(config);
         self.apply_config_preset();
+        self.initialize_components();
         self.theme_renderer = create_theme_renderer(&self.config.theme);
         self.refresh_multiline_renderer();
         // Clear cache to force re-render
@@ -494,19 +826,181 @@ mod tests {
 
     #[test]
     fn test_parse_preset() {
-        let order = StatuslineGenerator::parse_preset("PMBT");
+        let order = StatuslineGenerator::parse_preset("PMBT", &HashMap::new());
         assert_eq!(order, vec!["project", "model", "branch", "tokens"]);
 
-        let order = StatuslineGenerator::parse_preset("TBMP");
+        let order = StatuslineGenerator::parse_preset("TBMP", &HashMap::new());
         assert_eq!(order, vec!["tokens", "branch", "model", "project"]);
 
         // Test with lowercase and mixed case
-        let order = StatuslineGenerator::parse_preset("pmBT");
+        let order = StatuslineGenerator::parse_preset("pmBT", &HashMap::new());
         assert_eq!(order, vec!["project", "model", "branch", "tokens"]);
 
         // Test with invalid characters
-        let order = StatuslineGenerator::parse_preset("PM-BT");
+        let order = StatuslineGenerator::parse_preset("PM-BT", &HashMap::new());
         assert_eq!(order, vec!["project", "model", "branch", "tokens"]);
+
+        let order = StatuslineGenerator::parse_preset("MD", &HashMap::new());
+        assert_eq!(order, vec!["model", "time"]);
+
+        let order = StatuslineGenerator::parse_preset("TC", &HashMap::new());
+        assert_eq!(order, vec!["tokens", "cache"]);
+    }
+
+    #[test]
+    fn test_parse_preset_with_custom_keys() -> anyhow::Result<()> {
+        let config: Config = toml_edit::de::from_str(
+            r#"
+            [components.custom.k8s]
+            command = "kubectl"
+            preset_key = "k"
+
+            [components.custom.shadow]
+            command = "true"
+            preset_key = "P"
+            "#,
+        )?;
+
+        let order = StatuslineGenerator::parse_preset("PKM", &config.components.custom);
+        assert_eq!(order, vec!["project", "k8s", "model"]);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_custom_component_joins_order_and_theme() -> anyhow::Result<()> {
+        let mut config: Config = toml_edit::de::from_str(
+            r#"
+            theme = "powerline"
+
+            [terminal]
+            force_nerd_font = true
+
+            [components]
+            order = ["k8s", "status"]
+
+            [components.custom.k8s]
+            command = "sh"
+            args = ["-c", "echo '{\"text\":\"prod-cluster\",\"icon\":\"K\"}'"]
+            icon_color = "cyan"
+            "#,
+        )?;
+        config.multiline = None;
+
+        let options = GeneratorOptions {
+            preview_mode: true,
+            update_throttling: false,
+            ..GeneratorOptions::default()
+        };
+        let mut generator = StatuslineGenerator::new(config, options);
+        let line = generator.generate(InputData::default()).await?;
+
+        assert!(line.contains("K prod-cluster"));
+        assert!(line.contains('\u{e0b0}'));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_generate_output_keeps_hidden_components() -> anyhow::Result<()> {
+        let mut config: Config = toml_edit::de::from_str(
+            r#"
+            theme = "powerline"
+
+            [terminal]
+            force_text = true
+
+            [components]
+            order = ["k8s", "status"]
+
+            [components.custom.k8s]
+            command = "sh"
+            args = ["-c", "echo '{\"text\":\"prod-cluster\",\"icon\":\"K\"}'"]
+            icon_color = "cyan"
+            "#,
+        )?;
+        config.multiline = None;
+
+        let options = GeneratorOptions {
+            preview_mode: true,
+            update_throttling: false,
+            ..GeneratorOptions::default()
+        };
+        let mut generator = StatuslineGenerator::new(config, options);
+        let output = generator.generate_output(InputData::default()).await?;
+
+        let names: Vec<_> = output.components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["k8s", "status"]);
+        assert_eq!(output.components[0].text, "prod-cluster");
+        assert_eq!(output.components[0].theme_color, "cyan");
+        assert!(!output.components[1].visible);
+        assert_eq!(output.timing.components.len(), 2);
+        assert!(output.timing.components.iter().all(|t| !t.timed_out));
+
+        let json = serde_json::to_value(&output)?;
+        assert!(json.get("lines").is_none());
+        assert_eq!(
+            output.text(),
+            generator.generate(InputData::default()).await?
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_timed_out_component_falls_back() -> anyhow::Result<()> {
+        let temp = tempfile::tempdir()?;
+        let flag = temp.path().join("slow");
+        let mut config: Config = toml_edit::de::from_str(&format!(
+            r#"
+            [terminal]
+            force_text = true
+
+            [components]
+            order = ["probe", "slow"]
+            render_budget = 500
+            timeout_placeholder = "n/a"
+
+            [components.custom.probe]
+            command = "sh"
+            args = ["-c", "if [ -e '{}' ]; then sleep 5; fi; echo fresh"]
+            text_icon = "P"
+
+            [components.custom.slow]
+            command = "sleep"
+            args = ["5"]
+            text_icon = "S"
+            "#,
+            flag.display()
+        ))?;
+        config.multiline = None;
+
+        let options = GeneratorOptions {
+            preview_mode: true,
+            update_throttling: false,
+            disable_cache: true,
+            ..GeneratorOptions::default()
+        };
+        let mut generator = StatuslineGenerator::new(config, options);
+
+        let started = Instant::now();
+        let line = generator.generate(InputData::default()).await?;
+        assert!(line.contains("P fresh"), "{line}");
+        assert!(line.contains("S n/a"), "{line}");
+
+        // probe 这次也超时:沿用上一次的输出而不是占位符
+        std::fs::write(&flag, "")?;
+        let line = generator.generate(InputData::default()).await?;
+        assert!(line.contains("P fresh"), "{line}");
+
+        // 其他会话不会拿到这个会话的旧输出
+        let other = InputData {
+            session_id: Some("other-session".to_string()),
+            ..InputData::default()
+        };
+        let line = generator.generate(other).await?;
+        assert!(line.contains("P n/a"), "{line}");
+        assert!(started.elapsed() < Duration::from_secs(4));
+        Ok(())
     }
 
     #[test]
@@ -7,6 +7,8 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::utils::client_env::ClientEnv;
+
 /// Main input data structure from Claude Code
 ///
 /// This structure represents the JSON data passed to the statusline
@@ -61,6 +63,10 @@ pub struct InputData {
     /// Additional fields for future expansion
     #[serde(flatten)]
     pub extra: Value,
+
+    /// Environment the statusline was invoked with (forwarded by daemon clients)
+    #[serde(skip)]
+    pub env: ClientEnv,
 }
 
 /// Model information
@@ -234,22 +240,42 @@ impl InputData {
         Ok(data)
     }
 
-    /// Parse `InputData` from stdin
+    /// Parse `InputData` from stdin; an interactive terminal reads as empty input
     /// # Errors
     ///
     /// Returns an error when stdin cannot be read or the streamed data fails
     /// to deserialize into structured input metadata.
     pub fn from_stdin() -> anyhow::Result<Self> {
-        use std::io::{self, Read};
+        Self::parse(&Self::read_stdin()?)
+    }
+
+    /// Raw stdin payload; empty when stdin is an interactive terminal
+    /// # Errors
+    ///
+    /// Returns an error when stdin cannot be read.
+    pub fn read_stdin() -> anyhow::Result<String> {
+        use std::io::{self, IsTerminal, Read};
+        // tmux / shell prompt 调用时没有 Claude Code 的 JSON 管道输入
+        if io::stdin().is_terminal() {
+            return Ok(String::new());
+        }
+
         let mut buffer = String::new();
         io::stdin().read_to_string(&mut buffer)?;
+        Ok(buffer)
+    }
 
+    /// Parse a raw payload, treating blank input as the default
+    /// # Errors
+    ///
+    /// Returns an error when a non-blank payload fails to deserialize.
+    pub fn parse(raw: &str) -> anyhow::Result<Self> {
         // Handle empty input by returning default
-        if buffer.trim().is_empty() {
+        if raw.trim().is_empty() {
             return Ok(Self::default());
         }
 
-        Self::from_json(&buffer)
+        Self::from_json(raw)
     }
 
     /// Get the effective project directory
@@ -4,11 +4,14 @@
 //! including input data parsing, configuration management, and the main
 //! generator logic.
 
+pub mod api_cache;
 pub mod generator;
 pub mod input;
 pub mod multiline;
+pub mod output;
 
 // Re-export commonly used types
 pub use generator::{GeneratorOptions, StatuslineGenerator};
 pub use input::{CostInfo, GitInfo, InputData, ModelInfo, WorkspaceInfo, WorktreeInfo};
 pub use multiline::{MultiLineRenderResult, MultiLineRenderer};
+pub use output::{OutputFormat, StatuslineOutput};
@@ -1,8 +1,8 @@
 use std::collections::{BTreeMap, HashMap};
 use std::fmt::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
-use std::time::Duration;
+use std::time::SystemTime;
 
 use anyhow::{anyhow, Context, Result};
 use chrono::{DateTime, TimeZone, Utc};
@@ -14,14 +14,20 @@ use tokio::fs;
 
 use crate::components::base::RenderContext;
 use crate::components::base::TerminalCapabilities;
-#[cfg(test)]
-use crate::components::ColorSupport;
+use crate::components::{BranchComponent, ColorSupport, ContextUsage, TokensComponent};
 use crate::config::component_widgets::{
-    ComponentMultilineConfig, WidgetApiConfig, WidgetApiMethod, WidgetConfig, WidgetFilterConfig,
-    WidgetFilterMode, WidgetType,
+    ComponentMultilineConfig, WidgetApiConfig, WidgetApiMethod, WidgetCommandConfig, WidgetConfig,
+    WidgetFileConfig, WidgetFileFormat, WidgetFilterConfig, WidgetFilterMode, WidgetType,
 };
 use crate::config::{Config, MultilineConfig, MultilineRowConfig};
+use crate::core::api_cache::{ApiBody, ApiRequest, ApiResponseCache};
+use crate::git::GitVersionInfo;
+use crate::storage::{self, transcript::TranscriptAggregates};
+use crate::themes::{self, OutputTarget};
 use crate::utils;
+use crate::utils::client_env::ClientEnv;
+use crate::utils::process::run_with_timeout;
+use crate::utils::width;
 
 static ENV_PATTERN: OnceLock<Result<Regex, regex::Error>> = OnceLock::new();
 static PLACEHOLDER_PATTERN: OnceLock<Result<Regex, regex::Error>> = OnceLock::new();
@@ -50,6 +56,11 @@ pub struct MultiLineRenderer {
     config_base_dir: Option<PathBuf>,
     grid: MultiLineGrid,
     widget_cache: HashMap<String, String>,
+    /// Parsed file widget sources with the mtime they were read at, keyed
+    /// like `widget_cache`; templates are re-rendered against them every time
+    file_data: HashMap<String, (SystemTime, LocalData)>,
+    /// `session.*` template namespace for the current render
+    session: Value,
     log_file: PathBuf,
 }
 
@@ -67,6 +78,8 @@ impl MultiLineRenderer {
             config_base_dir: base_dir,
             grid: MultiLineGrid::default(),
             widget_cache: HashMap::new(),
+            file_data: HashMap::new(),
+            session: Value::Null,
             log_file,
         }
     }
@@ -96,6 +109,7 @@ impl MultiLineRenderer {
         self.config = config;
         self.config_base_dir = base_dir;
         self.widget_cache.clear();
+        self.file_data.clear();
     }
 
     pub async fn render_extension_lines(
@@ -114,6 +128,14 @@ impl MultiLineRenderer {
         };
 
         self.grid.clear();
+        self.session = build_session_value(context);
+        let tokens = TokensComponent::new(self.config.components.tokens.clone());
+        if let Some(usage) = tokens.context_usage(context).await {
+            insert_tokens(&mut self.session, usage);
+        }
+        if let Some(aggregates) = load_transcript_aggregates(context).await {
+            insert_transcript(&mut self.session, &aggregates);
+        }
 
         let component_order = self
             .config
@@ -142,6 +164,14 @@ impl MultiLineRenderer {
                 }
             };
 
+            // 版本信息要走 revwalk 找 tag,只有模板真正引用时才加载
+            if component_name == "branch" && uses_git_version(&component_config) {
+                let branch = BranchComponent::new(self.config.components.branch.clone());
+                if let Some(version) = branch.load_version_info(context).await {
+                    insert_git_version(&mut self.session, &version, Utc::now().timestamp());
+                }
+            }
+
             if let Err(err) = self
                 .render_component_widgets(
                     &component_name,
@@ -175,6 +205,9 @@ impl MultiLineRenderer {
             "tokens" => self.config.components.tokens.base.enabled,
             "usage" => self.config.components.usage.base.enabled,
             "status" => self.config.components.status.base.enabled,
+            "budget" => self.config.components.budget.base.enabled,
+            "time" => self.config.components.time.base.enabled,
+            "cache" => self.config.components.cache.base.enabled,
             _ => true,
         }
     }
@@ -232,7 +265,7 @@ impl MultiLineRenderer {
                 continue;
             }
 
-            if !Self::check_detection(widget_config) {
+            if !Self::check_detection(widget_config, &context.input.env) {
                 continue;
             }
 
@@ -265,6 +298,44 @@ impl MultiLineRenderer {
                         None
                     }
                 },
+                WidgetType::Command => {
+                    match self.render_command_widget(widget_config, context).await {
+                        Ok(value) => value,
+                        Err(err) => {
+                            let log_msg = format!(
+                                "Widget {}.{} command failed:\n  Error: {}\n  Config: program={:?}, args={:?}",
+                                component_name,
+                                widget_name,
+                                err,
+                                widget_config.command.as_ref().map(|c| &c.program),
+                                widget_config.command.as_ref().map(|c| &c.args)
+                            );
+                            self.log_error(&log_msg).await;
+
+                            // 命令失败时不显示widget
+                            None
+                        }
+                    }
+                }
+                WidgetType::File => {
+                    match self
+                        .render_file_widget(&cache_key, widget_config, context)
+                        .await
+                    {
+                        Ok(value) => value,
+                        Err(err) => {
+                            let log_msg = format!(
+                                "Widget {}.{} file read failed:\n  Error: {}\n  Config: path={:?}",
+                                component_name,
+                                widget_name,
+                                err,
+                                widget_config.file.as_ref().map(|f| &f.path)
+                            );
+                            self.log_error(&log_msg).await;
+                            None
+                        }
+                    }
+                }
             };
 
             if let Some(final_text) = widget_output {
@@ -287,7 +358,7 @@ impl MultiLineRenderer {
         }
     }
 
-    fn check_detection(widget: &WidgetConfig) -> bool {
+    fn check_detection(widget: &WidgetConfig, env: &ClientEnv) -> bool {
         let Some(detection) = widget.detection.as_ref() else {
             return true;
         };
@@ -296,7 +367,7 @@ impl MultiLineRenderer {
             return true;
         };
 
-        let Some(value) = std::env::var(env_name).ok() else {
+        let Some(value) = env.var(env_name).ok() else {
             return false;
         };
 
@@ -325,10 +396,16 @@ impl MultiLineRenderer {
         true
     }
 
+    /// Static widgets show `content` literally; only an explicit `template`
+    /// goes through the template engine, so existing braces stay untouched
     fn render_static_widget(&self, widget: &WidgetConfig, context: &RenderContext) -> String {
-        let raw_widget_content = widget.content.as_deref().unwrap_or("");
-        let substituted = substitute_env(raw_widget_content);
-        Self::compose_with_icon(widget, &substituted, &context.terminal, &self.config)
+        let data = with_session(&Value::Null, &self.session);
+        let env = &context.input.env;
+        let rendered = widget.template.as_deref().map_or_else(
+            || substitute_env(widget.content.as_deref().unwrap_or(""), env),
+            |template| render_template(&substitute_env(template, env), &data),
+        );
+        Self::compose_with_icon(widget, &rendered, &data, context, &self.config)
     }
 
     async fn render_api_widget(
@@ -340,104 +417,314 @@ impl MultiLineRenderer {
             return Ok(None);
         };
 
-        let api_data = self.fetch_api_data(api_config).await?;
+        let api_data =
+            Self::fetch_api_data(api_config, context.preview_mode, &context.input.env).await?;
 
         if !Self::passes_filter(widget, &api_data.root) {
             return Ok(None);
         }
 
-        let rendered_text = if let Some(template) = widget.template.as_deref() {
-            let template = substitute_env(template);
-            render_template(&template, &api_data.selected)
+        let template_data = with_session(&api_data.selected, &self.session);
+        let mut rendered_text = if let Some(template) = widget.template.as_deref() {
+            let template = substitute_env(template, &context.input.env);
+            render_template(&template, &template_data)
         } else {
             api_data.selected.to_string()
         };
 
+        if let Some(marker) = api_config.stale_marker.as_deref() {
+            let stale_after = api_config.stale_after.unwrap_or(api_config.cache_ttl);
+            if api_data.age_ms.is_some_and(|age| age >= stale_after) {
+                rendered_text.push(' ');
+                rendered_text.push_str(marker);
+            }
+        }
+
         Ok(Some(Self::compose_with_icon(
             widget,
             &rendered_text,
-            &context.terminal,
+            &template_data,
+            context,
             &self.config,
         )))
     }
 
-    async fn fetch_api_data(&self, config: &WidgetApiConfig) -> Result<ApiData> {
+    async fn render_command_widget(
+        &self,
+        widget: &WidgetConfig,
+        context: &RenderContext,
+    ) -> Result<Option<String>> {
+        let Some(command_config) = widget.command.as_ref() else {
+            return Ok(None);
+        };
+
+        let cwd = context
+            .input
+            .project_dir()
+            .map(PathBuf::from)
+            .filter(|path| path.is_dir());
+        let data = Self::fetch_command_data(command_config, cwd, &context.input.env).await?;
+        Ok(self.render_local_data(widget, &data, context))
+    }
+
+    /// Render a file widget, re-reading the file only when its mtime changed
+    async fn render_file_widget(
+        &mut self,
+        cache_key: &str,
+        widget: &WidgetConfig,
+        context: &RenderContext,
+    ) -> Result<Option<String>> {
+        let Some(file_config) = widget.file.as_ref() else {
+            return Ok(None);
+        };
+
+        let path = resolve_widget_path(
+            &file_config.path,
+            context.input.project_dir(),
+            &context.input.env,
+        );
+        let Ok(modified) = fs::metadata(&path).await.and_then(|meta| meta.modified()) else {
+            // 文件不存在时隐藏widget，而不是继续显示旧内容
+            self.file_data.remove(cache_key);
+            self.widget_cache.remove(cache_key);
+            return Ok(None);
+        };
+
+        let fresh = self
+            .file_data
+            .get(cache_key)
+            .is_some_and(|(seen, _)| *seen == modified);
+        if !fresh {
+            let data = Self::read_file_data(&path, file_config).await?;
+            self.file_data
+                .insert(cache_key.to_string(), (modified, data));
+        }
+
+        // session.* 每次都变,模板必须对缓存的数据重新渲染
+        let rendered = self
+            .file_data
+            .get(cache_key)
+            .and_then(|(_, data)| self.render_local_data(widget, data, context));
+        if rendered.is_none() {
+            self.widget_cache.remove(cache_key);
+        }
+        Ok(rendered)
+    }
+
+    async fn read_file_data(path: &Path, file_config: &WidgetFileConfig) -> Result<LocalData> {
+        let contents = fs::read_to_string(path)
+            .await
+            .with_context(|| format!("Failed to read widget file {}", path.display()))?;
+        let format = file_config
+            .format
+            .clone()
+            .unwrap_or_else(|| WidgetFileFormat::from_path(path));
+        let data = match format {
+            WidgetFileFormat::Json => {
+                let json = serde_json::from_str(&contents)
+                    .with_context(|| format!("Failed to parse JSON file {}", path.display()))?;
+                LocalData::structured(json, file_config.data_path.as_deref())?
+            }
+            WidgetFileFormat::Toml => {
+                let json = toml_edit::de::from_str(&contents)
+                    .with_context(|| format!("Failed to parse TOML file {}", path.display()))?;
+                LocalData::structured(json, file_config.data_path.as_deref())?
+            }
+            WidgetFileFormat::Text => LocalData::plain_text(contents.trim()),
+        };
+        Ok(data)
+    }
+
+    /// Shared filter/template pipeline for command and file widgets
+    fn render_local_data(
+        &self,
+        widget: &WidgetConfig,
+        data: &LocalData,
+        context: &RenderContext,
+    ) -> Option<String> {
+        if !Self::passes_filter(widget, &data.root) {
+            return None;
+        }
+
+        let template_data = with_session(&data.selected, &self.session);
+        let rendered_text = widget.template.as_deref().map_or_else(
+            || {
+                let value = data
+                    .selected
+                    .get("output")
+                    .filter(|_| data.plain_text)
+                    .unwrap_or(&data.selected);
+                json_value_as_string(value)
+            },
+            |template| {
+                render_template(
+                    &substitute_env(template, &context.input.env),
+                    &template_data,
+                )
+            },
+        );
+
+        if rendered_text.is_empty() {
+            return None;
+        }
+
+        Some(Self::compose_with_icon(
+            widget,
+            &rendered_text,
+            &template_data,
+            context,
+            &self.config,
+        ))
+    }
+
+    async fn fetch_command_data(
+        config: &WidgetCommandConfig,
+        cwd: Option<PathBuf>,
+        env: &ClientEnv,
+    ) -> Result<LocalData> {
+        let program = substitute_env(&config.program, env);
+        if program.trim().is_empty() {
+            return Err(anyhow!("Command widget missing program"));
+        }
+
+        let mut command = std::process::Command::new(&program);
+        env.apply_to(&mut command);
+        command
+            .args(config.args.iter().map(|arg| substitute_env(arg, env)))
+            .envs(
+                config
+                    .env
+                    .iter()
+                    .map(|(key, value)| (key.clone(), substitute_env(value, env))),
+            );
+        if let Some(dir) = cwd {
+            command.current_dir(dir);
+        }
+        let timeout = std::time::Duration::from_millis(config.timeout);
+
+        let output =
+            tokio::task::spawn_blocking(move || run_with_timeout(command, None, timeout)).await??;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "'{program}' exited with {}: {}",
+                output.status,
+                output.stderr.trim()
+            ));
+        }
+
+        let stdout = output.stdout.trim();
+        if let Ok(json) = serde_json::from_str::<Value>(stdout) {
+            if json.is_object() || json.is_array() {
+                return LocalData::structured(json, config.data_path.as_deref());
+            }
+        }
+
+        Ok(LocalData::plain_text(stdout))
+    }
+
+    fn build_api_request(config: &WidgetApiConfig, env: &ClientEnv) -> Result<ApiRequest> {
         let endpoint = config
             .endpoint
             .as_ref()
             .ok_or_else(|| anyhow!("API widget missing endpoint"))?;
 
         // 替换endpoint中的环境变量
-        let endpoint = substitute_env(endpoint);
+        let endpoint = substitute_env(endpoint, env);
 
         let url = if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
-            endpoint.clone()
+            endpoint
         } else if let Some(base) = &config.base_url {
             // 替换base_url中的环境变量
-            let base = substitute_env(base);
+            let base = substitute_env(base, env);
             format!("{}{}", base.trim_end_matches('/'), endpoint)
         } else {
             anyhow::bail!("API widget missing base_url for relative endpoint");
         };
 
-        let method_str = match config.method {
+        let method = match config.method {
             WidgetApiMethod::GET => "GET",
             WidgetApiMethod::POST => "POST",
             WidgetApiMethod::PUT => "PUT",
             WidgetApiMethod::DELETE => "DELETE",
         };
 
-        // 使用ureq同步客户端（在tokio::task::spawn_blocking中运行）
-        let url_clone = url.clone();
-        let timeout_ms = config.timeout;
-        let headers = config.headers.clone();
-        let method_str = method_str.to_string();
-
-        let json_result = tokio::task::spawn_blocking(move || -> Result<Value> {
-            let mut request =
-                ureq::request(&method_str, &url_clone).timeout(Duration::from_millis(timeout_ms));
-
-            // 添加headers
-            for (key, value) in &headers {
-                let substituted_value = substitute_env(value);
-                request = request.set(key, &substituted_value);
-            }
+        let headers = config
+            .headers
+            .iter()
+            .map(|(key, value)| (key.clone(), substitute_env(value, env)))
+            .collect();
 
-            // 添加User-Agent
-            request = request.set("User-Agent", "claude-code-statusline/3.0");
+        let query = config
+            .query
+            .iter()
+            .map(|(key, value)| (key.clone(), substitute_env(value, env)))
+            .collect();
 
-            // 发送请求
-            let response = request.call().context("ureq request failed")?;
+        let content_type = config
+            .content_type
+            .as_ref()
+            .map(|value| substitute_env(value, env));
+        let is_form = content_type.as_deref().is_some_and(|value| {
+            value
+                .to_ascii_lowercase()
+                .starts_with("application/x-www-form-urlencoded")
+        });
 
-            // 解析JSON
-            let json: Value = response
-                .into_json()
-                .context("Failed to parse JSON response")?;
+        let body = match config
+            .body
+            .as_ref()
+            .map(|body| substitute_env_in_value(body, env))
+        {
+            None | Some(Value::Null) => None,
+            Some(Value::String(text)) => Some(ApiBody::Raw(text)),
+            Some(Value::Object(fields)) if is_form => Some(ApiBody::Form(
+                fields
+                    .into_iter()
+                    .map(|(key, value)| {
+                        let value = match value {
+                            Value::String(text) => text,
+                            other => other.to_string(),
+                        };
+                        (key, value)
+                    })
+                    .collect(),
+            )),
+            Some(value) if is_form => {
+                anyhow::bail!("API widget form body must be a table, got {value}")
+            }
+            Some(value) => Some(ApiBody::Raw(serde_json::to_string(&value)?)),
+        };
 
-            Ok(json)
+        Ok(ApiRequest {
+            url,
+            method: method.to_string(),
+            headers,
+            query,
+            body,
+            content_type,
+            timeout_ms: config.timeout,
         })
-        .await??;
+    }
 
-        let json = json_result;
+    async fn fetch_api_data(
+        config: &WidgetApiConfig,
+        preview_mode: bool,
+        env: &ClientEnv,
+    ) -> Result<ApiData> {
+        let request = Self::build_api_request(config, env)?;
+        let cache_ttl = config.cache_ttl;
 
-        if let Some(path) = &config.data_path {
-            let selected = {
-                let matches = jsonpath::select(&json, path).map_err(|err| anyhow!(err))?;
-                matches.first().map(|value| (*value).clone())
-            };
-            if let Some(value) = selected {
-                return Ok(ApiData {
-                    root: json,
-                    selected: value,
-                });
-            }
-            return Err(anyhow!("JSONPath {path:?} yielded no results"));
-        }
+        // 使用ureq同步客户端（在tokio::task::spawn_blocking中运行）
+        let (json, age_ms) = tokio::task::spawn_blocking(move || {
+            fetch_with_cache(&request, cache_ttl, !preview_mode)
+        })
+        .await??;
 
-        let selected = json.clone();
+        let selected = select_data_path(&json, config.data_path.as_deref())?;
         Ok(ApiData {
             root: json,
             selected,
+            age_ms,
         })
     }
 
@@ -450,19 +737,243 @@ impl MultiLineRenderer {
 
     fn compose_with_icon(
         widget: &WidgetConfig,
-        content: &str,
-        terminal: &TerminalCapabilities,
+        rendered: &str,
+        data: &Value,
+        context: &RenderContext,
         config: &Config,
     ) -> String {
+        let terminal = &context.terminal;
         let icon = select_widget_icon(widget, terminal, config);
+        let supports_colors = terminal.supports_colors()
+            && config
+                .style
+                .enable_colors
+                .is_enabled(terminal.supports_colors());
+        let color_support = if supports_colors {
+            terminal.color_support
+        } else {
+            ColorSupport::None
+        };
+
+        // 阈值颜色优先于固定的 text_color（语义同 tokens 组件的 safe/warning/danger）
+        let threshold_color = widget.thresholds.as_ref().and_then(|thresholds| {
+            let value = evaluate_expression(&thresholds.value, data)
+                .ok()
+                .and_then(|value| value_to_f64(&value).ok())?;
+            thresholds.color_for(value).map(str::to_string)
+        });
+        let text_color = threshold_color.as_deref().or(widget.text_color.as_deref());
+
+        let text = paint_widget_segment(rendered, text_color, color_support);
         if icon.is_empty() {
-            content.to_string()
+            text
         } else {
-            format!("{icon} {content}")
+            let icon = paint_widget_segment(&icon, widget.icon_color.as_deref(), color_support);
+            format!("{icon} {text}")
+        }
+    }
+}
+
+/// Build the `session.*` template namespace from the current `InputData`
+///
+/// Includes every input field (model, cost, workspace, worktree, `context_window`,
+/// extra fields) plus computed values: `duration_hours`, `cost_per_hour`,
+/// `git.branch`, `project_dir` and `current_dir`.
+fn build_session_value(context: &RenderContext) -> Value {
+    let input = context.input.as_ref();
+    let mut session = serde_json::to_value(input).unwrap_or(Value::Null);
+    let Value::Object(map) = &mut session else {
+        return Value::Null;
+    };
+
+    if let Some(cost) = input.cost.as_ref() {
+        if let Some(duration_ms) = cost.total_duration_ms.filter(|ms| *ms > 0) {
+            #[allow(clippy::cast_precision_loss)]
+            let hours = duration_ms as f64 / HOUR_MS;
+            map.insert("duration_hours".to_string(), number_value(hours));
+            if let Some(total_cost) = cost.total_cost_usd {
+                map.insert(
+                    "cost_per_hour".to_string(),
+                    number_value(total_cost / hours),
+                );
+            }
+        }
+    }
+
+    if let Some(branch) = input.branch() {
+        let git = map
+            .entry("git")
+            .or_insert_with(|| Value::Object(serde_json::Map::new()));
+        if let Value::Object(git) = git {
+            git.insert("branch".to_string(), Value::String(branch.to_string()));
+        }
+    }
+    if let Some(dir) = input.project_dir() {
+        map.insert("project_dir".to_string(), Value::String(dir.to_string()));
+    }
+    if let Some(dir) = input.current_dir() {
+        map.insert("current_dir".to_string(), Value::String(dir.to_string()));
+    }
+
+    session
+}
+
+/// Add `session.tokens.{used,total,percentage}` as the tokens component shows them
+fn insert_tokens(session: &mut Value, usage: ContextUsage) {
+    let Value::Object(map) = session else {
+        return;
+    };
+    map.insert(
+        "tokens".to_string(),
+        serde_json::json!({
+            "used": usage.used,
+            "total": usage.total,
+            "percentage": usage.percentage,
+        }),
+    );
+}
+
+/// Aggregates folded from the whole transcript by the snapshot pass
+async fn load_transcript_aggregates(context: &RenderContext) -> Option<TranscriptAggregates> {
+    if let Some(mock) = context.input.extra.get("__mock__") {
+        return mock
+            .get("transcriptAggregates")
+            .and_then(|aggregates| serde_json::from_value(aggregates.clone()).ok());
+    }
+
+    // preview 模式不读 storage,理由同 tokens / cache 组件
+    if context.preview_mode {
+        return None;
+    }
+    let session_id = context.input.session_id.as_deref()?;
+    let path = context.input.transcript_path.as_deref()?;
+    storage::get_transcript_aggregates(session_id, path)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Add `session.transcript.*` counted over the whole session
+///
+/// `user_prompts`, `assistant_turns`, `tool_uses`, `tool_errors`,
+/// `compactions`, `errors` and the summed `usage.{input,output,
+/// cache_creation_input,cache_read_input}`.
+fn insert_transcript(session: &mut Value, aggregates: &TranscriptAggregates) {
+    let Value::Object(map) = session else {
+        return;
+    };
+    map.insert(
+        "transcript".to_string(),
+        serde_json::json!({
+            "user_prompts": aggregates.user_prompts,
+            "assistant_turns": aggregates.assistant_turns,
+            "tool_uses": aggregates.tool_uses,
+            "tool_errors": aggregates.tool_errors,
+            "compactions": aggregates.compactions,
+            "errors": aggregates.errors,
+            "usage": aggregates.usage,
+        }),
+    );
+}
+
+/// Whether any enabled widget template reads `session.git.*` beyond `branch`
+fn uses_git_version(config: &ComponentMultilineConfig) -> bool {
+    config
+        .widgets
+        .values()
+        .filter(|widget| widget.enabled)
+        .filter_map(|widget| widget.template.as_deref())
+        .any(|template| {
+            template
+                .match_indices("session.git")
+                .any(|(index, needle)| {
+                    let rest = &template[index + needle.len()..];
+                    !rest.strip_prefix(".branch").is_some_and(|tail| {
+                        !tail.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+                    })
+                })
+        })
+}
+
+/// Add commit and tag fields to `session.git` for branch widgets
+///
+/// `commit`, `short_commit`, `message`, `author`, `tag`, `commits_since_tag`,
+/// `tag_label` (`v1.2.0+3`), `last_commit_time` and `last_commit_age` (`3h ago`).
+fn insert_git_version(session: &mut Value, version: &GitVersionInfo, now: i64) {
+    let Value::Object(map) = session else {
+        return;
+    };
+    let git = map
+        .entry("git")
+        .or_insert_with(|| Value::Object(serde_json::Map::new()));
+    let Value::Object(git) = git else {
+        return;
+    };
+
+    git.insert(
+        "commit".to_string(),
+        Value::String(version.commit_id.clone()),
+    );
+    git.insert(
+        "short_commit".to_string(),
+        Value::String(version.short_commit_id.clone()),
+    );
+    git.insert(
+        "message".to_string(),
+        Value::String(version.message.clone()),
+    );
+    git.insert("author".to_string(), Value::String(version.author.clone()));
+    if let Some(tag) = &version.tag {
+        git.insert("tag".to_string(), Value::String(tag.clone()));
+    }
+    if let Some(distance) = version.tag_distance {
+        git.insert("commits_since_tag".to_string(), Value::from(distance));
+    }
+    if let Some(label) = version.tag_label() {
+        git.insert("tag_label".to_string(), Value::String(label));
+    }
+    if let Some(time) = Utc.timestamp_opt(version.timestamp, 0).single() {
+        git.insert(
+            "last_commit_time".to_string(),
+            Value::String(time.to_rfc3339()),
+        );
+    }
+    if let Some(age) = version.relative_age(now) {
+        git.insert("last_commit_age".to_string(), Value::String(age));
+    }
+}
+
+/// Expose `session` alongside widget data; objects keep their own `session` field if present
+fn with_session(data: &Value, session: &Value) -> Value {
+    match data {
+        Value::Object(map) if !map.contains_key("session") => {
+            let mut merged = map.clone();
+            merged.insert("session".to_string(), session.clone());
+            Value::Object(merged)
         }
+        Value::Null => serde_json::json!({ "session": session }),
+        other => other.clone(),
     }
 }
 
+/// Wrap a widget segment in ANSI color codes, downgraded to the terminal's color support
+///
+/// Rows stay ANSI until they are laid out and truncated; the generator
+/// translates finished rows for the output target.
+fn paint_widget_segment(segment: &str, color: Option<&str>, color_support: ColorSupport) -> String {
+    if segment.is_empty() || color_support == ColorSupport::None {
+        return segment.to_string();
+    }
+
+    color
+        .and_then(|name| themes::ansi_fg_with_support(name, color_support, OutputTarget::Ansi))
+        .filter(|code| !code.is_empty())
+        .map_or_else(
+            || segment.to_string(),
+            |code| format!("{code}{segment}{}", themes::ANSI_RESET),
+        )
+}
+
 fn value_matches_filter(filter: &WidgetFilterConfig, data: &Value) -> bool {
     let Some(keyword) = filter.keyword.as_deref() else {
         return true;
@@ -512,9 +1023,105 @@ fn json_value_as_string(value: &Value) -> String {
     }
 }
 
+/// Apply an optional `JSONPath` selection to a widget data source
+fn select_data_path(json: &Value, data_path: Option<&str>) -> Result<Value> {
+    let Some(path) = data_path else {
+        return Ok(json.clone());
+    };
+
+    let matches = jsonpath::select(json, path).map_err(|err| anyhow!(err))?;
+    matches
+        .first()
+        .map(|value| (*value).clone())
+        .ok_or_else(|| anyhow!("JSONPath {path:?} yielded no results"))
+}
+
+/// Data produced by a command or file widget
+struct LocalData {
+    root: Value,
+    selected: Value,
+    /// Source was plain text and is exposed as `output` / `lines`
+    plain_text: bool,
+}
+
+impl LocalData {
+    fn structured(json: Value, data_path: Option<&str>) -> Result<Self> {
+        let selected = select_data_path(&json, data_path)?;
+        Ok(Self {
+            root: json,
+            selected,
+            plain_text: false,
+        })
+    }
+
+    fn plain_text(text: &str) -> Self {
+        let root = serde_json::json!({
+            "output": text,
+            "lines": text.lines().map(str::trim_end).collect::<Vec<_>>(),
+        });
+        Self {
+            selected: root.clone(),
+            root,
+            plain_text: true,
+        }
+    }
+}
+
+/// Resolve a widget file path: env substitution, `~/` expansion, relative to the project dir
+fn resolve_widget_path(raw: &str, project_dir: Option<&str>, env: &ClientEnv) -> PathBuf {
+    let substituted = substitute_env(raw, env);
+    if let Some(rest) = substituted.strip_prefix("~/") {
+        if let Some(home) = utils::home_dir() {
+            return home.join(rest);
+        }
+    }
+
+    let path = PathBuf::from(&substituted);
+    match project_dir {
+        Some(dir) if path.is_relative() => PathBuf::from(dir).join(path),
+        _ => path,
+    }
+}
+
 struct ApiData {
     root: Value,
     selected: Value,
+    /// Age of the response when served from the on-disk cache
+    age_ms: Option<u64>,
+}
+
+/// Resolve an API response through the on-disk cache (stale-while-revalidate).
+///
+/// Fresh entries are returned as-is; expired entries are returned immediately
+/// while a detached process refreshes them. Without an entry the request is
+/// performed inline. `persist` is false in preview mode so nothing is written.
+fn fetch_with_cache(
+    request: &ApiRequest,
+    cache_ttl: u64,
+    persist: bool,
+) -> Result<(Value, Option<u64>)> {
+    if cache_ttl == 0 {
+        return Ok((request.send()?, None));
+    }
+
+    let cache = ApiResponseCache::new();
+    if let Some(entry) = cache.load(request) {
+        let age = entry.age_ms();
+        if age >= cache_ttl && persist {
+            if let Err(err) = cache.spawn_refresh(request) {
+                eprintln!("[statusline] widget cache refresh failed to start: {err}");
+            }
+        }
+        return Ok((entry.response, Some(age)));
+    }
+
+    let response = request.send()?;
+    if persist {
+        if let Err(err) = cache.store(request, &response) {
+            eprintln!("[statusline] failed to write widget cache: {err}");
+        }
+    }
+    Ok((response, None))
 }
 
 #[derive(Default)]
@@ -556,7 +1163,7 @@ impl MultiLineGrid {
                 .join(&row_config.separator);
 
             let line = if row_config.max_width > 0 {
-                truncate_to_width(&joined, row_config.max_width as usize)
+                width::truncate_ansi(&joined, row_config.max_width as usize)
             } else {
                 joined
             };
@@ -568,13 +1175,6 @@ impl MultiLineGrid {
     }
 }
 
-fn truncate_to_width(text: &str, max_width: usize) -> String {
-    if text.chars().count() <= max_width {
-        return text.to_string();
-    }
-    text.chars().take(max_width).collect()
-}
-
 fn select_widget_icon(
     widget: &WidgetConfig,
     terminal: &TerminalCapabilities,
@@ -600,7 +1200,7 @@ fn select_widget_icon(
     widget.text_icon.clone()
 }
 
-fn substitute_env(input: &str) -> String {
+fn substitute_env(input: &str, env: &ClientEnv) -> String {
     // 临时占位符，用于保护转义的美元符号
     const DOLLAR_PLACEHOLDER: &str = "\u{0000}DOLLAR\u{0000}";
 
@@ -612,7 +1212,7 @@ fn substitute_env(input: &str) -> String {
         Ok(pattern) => pattern
             .replace_all(&step1, |captures: &regex::Captures| {
                 let key = &captures[1];
-                std::env::var(key).unwrap_or_default()
+                env.var(key).unwrap_or_default()
             })
             .into_owned(),
         Err(err) => {
@@ -625,10 +1225,31 @@ fn substitute_env(input: &str) -> String {
     step2.replace(DOLLAR_PLACEHOLDER, "$")
 }
 
-fn render_template(template: &str, data: &Value) -> String {
-    let mut result = String::new();
-    let mut last_index = 0;
+/// 递归替换 JSON 值中所有字符串（包括键）里的环境变量
+fn substitute_env_in_value(value: &Value, env: &ClientEnv) -> Value {
+    match value {
+        Value::String(text) => Value::String(substitute_env(text, env)),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| substitute_env_in_value(item, env))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    (
+                        substitute_env(key, env),
+                        substitute_env_in_value(value, env),
+                    )
+                })
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
 
+fn render_template(template: &str, data: &Value) -> String {
     let Some(pattern) = PLACEHOLDER_PATTERN
         .get_or_init(|| Regex::new(r"\{([^{}]+)\}"))
         .as_ref()
@@ -638,31 +1259,181 @@ fn render_template(template: &str, data: &Value) -> String {
         return template.to_string();
     };
 
+    let tokens = tokenize_template(template, pattern);
+    let mut cursor = 0;
+    match parse_template_block(&tokens, &mut cursor) {
+        Ok((nodes, BlockEnd::Eof)) => {
+            let mut result = String::new();
+            render_template_nodes(&nodes, data, &mut result);
+            result
+        }
+        Ok((_, terminator)) => {
+            eprintln!("[statusline] 模板条件块不匹配: 多余的 {terminator:?}");
+            render_template_flat(&tokens, data)
+        }
+        Err(err) => {
+            eprintln!("[statusline] 模板条件块解析失败: {err}");
+            render_template_flat(&tokens, data)
+        }
+    }
+}
+
+/// Lexical pieces of a template: literal text and `{...}` tags
+enum TemplateToken<'a> {
+    Text(&'a str),
+    Tag(&'a str),
+}
+
+/// Parsed template tree with `{if}` / `{elif}` / `{else}` / `{end}` blocks resolved
+enum TemplateNode<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+    Conditional {
+        branches: Vec<(&'a str, Vec<Self>)>,
+        otherwise: Vec<Self>,
+    },
+}
+
+/// Tag that terminated a template block
+#[derive(Debug)]
+enum BlockEnd<'a> {
+    Eof,
+    Elif(&'a str),
+    Else,
+    End,
+}
+
+fn tokenize_template<'a>(template: &'a str, pattern: &Regex) -> Vec<TemplateToken<'a>> {
+    let mut tokens = Vec::new();
+    let mut last_index = 0;
+
     for capture in pattern.captures_iter(template) {
         let (Some(m), Some(expr_match)) = (capture.get(0), capture.get(1)) else {
             continue;
         };
+        if m.start() > last_index {
+            tokens.push(TemplateToken::Text(&template[last_index..m.start()]));
+        }
+        tokens.push(TemplateToken::Tag(expr_match.as_str()));
+        last_index = m.end();
+    }
 
-        result.push_str(&template[last_index..m.start()]);
-        let expr = expr_match.as_str();
-        match render_placeholder(expr, data) {
-            Ok(rendered) => result.push_str(&rendered),
-            Err(err) => {
-                eprintln!("[statusline] 模板渲染失败: {err}");
-                let _ = write!(result, "{{{expr}}}");
+    if last_index < template.len() {
+        tokens.push(TemplateToken::Text(&template[last_index..]));
+    }
+    tokens
+}
+
+/// Split a block keyword (`if` / `elif`) from its condition
+fn strip_block_keyword<'a>(tag: &'a str, keyword: &str) -> Option<&'a str> {
+    let rest = tag.trim_start().strip_prefix(keyword)?;
+    rest.starts_with(char::is_whitespace).then(|| rest.trim())
+}
+
+fn parse_template_block<'a>(
+    tokens: &[TemplateToken<'a>],
+    cursor: &mut usize,
+) -> Result<(Vec<TemplateNode<'a>>, BlockEnd<'a>)> {
+    let mut nodes = Vec::new();
+
+    while let Some(token) = tokens.get(*cursor) {
+        *cursor += 1;
+        let tag = match token {
+            TemplateToken::Text(text) => {
+                nodes.push(TemplateNode::Text(text));
+                continue;
+            }
+            TemplateToken::Tag(tag) => *tag,
+        };
+
+        match tag.trim() {
+            "else" => return Ok((nodes, BlockEnd::Else)),
+            "end" => return Ok((nodes, BlockEnd::End)),
+            _ => {}
+        }
+        if let Some(condition) = strip_block_keyword(tag, "elif") {
+            return Ok((nodes, BlockEnd::Elif(condition)));
+        }
+        let Some(condition) = strip_block_keyword(tag, "if") else {
+            nodes.push(TemplateNode::Placeholder(tag));
+            continue;
+        };
+
+        let mut branches = Vec::new();
+        let mut current = condition;
+        let otherwise = loop {
+            let (body, terminator) = parse_template_block(tokens, cursor)?;
+            branches.push((current, body));
+            match terminator {
+                BlockEnd::Elif(next) => current = next,
+                BlockEnd::Else => match parse_template_block(tokens, cursor)? {
+                    (body, BlockEnd::End) => break body,
+                    (_, other) => {
+                        return Err(anyhow!("Expected {{end}} after {{else}}, found {other:?}"))
+                    }
+                },
+                BlockEnd::End => break Vec::new(),
+                BlockEnd::Eof => return Err(anyhow!("Unterminated {{if {condition}}} block")),
+            }
+        };
+        nodes.push(TemplateNode::Conditional {
+            branches,
+            otherwise,
+        });
+    }
+
+    Ok((nodes, BlockEnd::Eof))
+}
+
+fn render_template_nodes(nodes: &[TemplateNode<'_>], data: &Value, result: &mut String) {
+    for node in nodes {
+        match node {
+            TemplateNode::Text(text) => result.push_str(text),
+            TemplateNode::Placeholder(expr) => render_placeholder_into(expr, data, result),
+            TemplateNode::Conditional {
+                branches,
+                otherwise,
+            } => {
+                let selected = branches
+                    .iter()
+                    .find(|(condition, _)| match evaluate_condition(condition, data) {
+                        Ok(matched) => matched,
+                        Err(err) => {
+                            eprintln!("[statusline] 模板条件求值失败 {condition:?}: {err}");
+                            false
+                        }
+                    })
+                    .map_or(otherwise, |(_, body)| body);
+                render_template_nodes(selected, data, result);
             }
         }
-        last_index = m.end();
     }
+}
 
-    result.push_str(&template[last_index..]);
+/// Fallback rendering without block support (used when blocks are malformed)
+fn render_template_flat(tokens: &[TemplateToken<'_>], data: &Value) -> String {
+    let mut result = String::new();
+    for token in tokens {
+        match token {
+            TemplateToken::Text(text) => result.push_str(text),
+            TemplateToken::Tag(expr) => render_placeholder_into(expr, data, &mut result),
+        }
+    }
     result
 }
 
+fn render_placeholder_into(expr: &str, data: &Value, result: &mut String) {
+    match render_placeholder(expr, data) {
+        Ok(rendered) => result.push_str(&rendered),
+        Err(err) => {
+            eprintln!("[statusline] 模板渲染失败: {err}");
+            let _ = write!(result, "{{{expr}}}");
+        }
+    }
+}
+
 fn render_placeholder(expr: &str, data: &Value) -> Result<String> {
-    let (expr_body, format_spec) = expr
-        .find(':')
-        .map_or((expr, None), |idx| (&expr[..idx], Some(&expr[idx + 1..])));
+    let (expr_body, format_spec) = split_format_spec(expr);
 
     let value = evaluate_expression(expr_body.trim(), data)?;
 
@@ -681,6 +1452,25 @@ fn render_placeholder(expr: &str, data: &Value) -> Result<String> {
     })
 }
 
+/// Split `expr:spec`, ignoring colons inside quotes or belonging to a `?:` ternary
+fn split_format_spec(expr: &str) -> (&str, Option<&str>) {
+    let mut quote = None;
+    let mut pending_ternary = 0_usize;
+
+    for (idx, ch) in expr.char_indices() {
+        match (quote, ch) {
+            (Some(open), _) if ch == open => quote = None,
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '?') => pending_ternary += 1,
+            (None, ':') if pending_ternary > 0 => pending_ternary -= 1,
+            (None, ':') => return (&expr[..idx], Some(&expr[idx + 1..])),
+            _ => {}
+        }
+    }
+
+    (expr, None)
+}
+
 fn evaluate_expression(expr: &str, data: &Value) -> Result<Value> {
     let trimmed = expr.trim();
 
@@ -688,6 +1478,10 @@ fn evaluate_expression(expr: &str, data: &Value) -> Result<Value> {
         return Ok(Number::from_f64(now_timestamp_millis()).map_or(Value::Null, Value::Number));
     }
 
+    if is_conditional_expression(trimmed) {
+        return evaluate_logic_expression(trimmed, data);
+    }
+
     match TIME_DIFF_PATTERN
         .get_or_init(|| Regex::new(r"^(.+?)\s*-\s*(.+?)$"))
         .as_ref()
@@ -999,17 +1793,394 @@ impl<'a> MathParser<'a> {
     }
 }
 
-const fn is_identifier_start(ch: char) -> bool {
-    ch.is_ascii_alphabetic() || ch == '_'
+/// Whether an expression uses comparison, boolean or ternary operators
+fn is_conditional_expression(expr: &str) -> bool {
+    let mut quote = None;
+    let mut previous = ' ';
+    for ch in expr.chars() {
+        match (quote, ch) {
+            (Some(open), _) if ch == open => quote = None,
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '?' | '<' | '>' | '!') => return true,
+            (None, '=' | '&' | '|') if previous == ch => return true,
+            _ => {}
+        }
+        previous = ch;
+    }
+    false
 }
 
-const fn is_identifier_part(ch: char) -> bool {
-    ch.is_ascii_alphanumeric() || ch == '_' || ch == '[' || ch == ']'
+/// Evaluate a `{if ...}` condition to a boolean
+fn evaluate_condition(expr: &str, data: &Value) -> Result<bool> {
+    evaluate_logic_expression(expr, data).map(|value| is_truthy(&value))
 }
 
-fn value_token_to_f64(token: &str, data: &Value) -> f64 {
-    if let Ok(number) = token.parse::<f64>() {
-        return number;
+fn evaluate_logic_expression(expr: &str, data: &Value) -> Result<Value> {
+    let mut parser = LogicParser::new(expr, data)?;
+    let value = parser.parse_ternary()?;
+    parser.expect_end()?;
+    Ok(value)
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|number| number != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+/// Numeric view of a value for comparisons (numbers and numeric strings only)
+fn comparable_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+/// Number value that renders integers without a trailing `.0`
+fn number_value(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < 9e15 {
+        #[allow(clippy::cast_possible_truncation)]
+        return Value::from(number as i64);
+    }
+    Number::from_f64(number).map_or(Value::Null, Value::Number)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum LogicToken {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+}
+
+/// Parser for conditional template expressions
+///
+/// Grammar (lowest precedence first): ternary `a ? b : c`, `||` / `or`,
+/// `&&` / `and`, `!` / `not`, comparisons `< <= > >= == !=`, `+ -`, `* /`,
+/// unary minus, then literals (numbers, quoted strings, `true` / `false` /
+/// `null`), `now()`, field paths and parentheses.
+struct LogicParser<'a> {
+    expr: &'a str,
+    tokens: Vec<LogicToken>,
+    pos: usize,
+    data: &'a Value,
+}
+
+impl<'a> LogicParser<'a> {
+    fn new(expr: &'a str, data: &'a Value) -> Result<Self> {
+        Ok(Self {
+            expr,
+            tokens: tokenize_logic_expression(expr)?,
+            pos: 0,
+            data,
+        })
+    }
+
+    fn parse_ternary(&mut self) -> Result<Value> {
+        let condition = self.parse_or()?;
+        if !self.consume_op("?") {
+            return Ok(condition);
+        }
+        let when_true = self.parse_ternary()?;
+        if !self.consume_op(":") {
+            return Err(anyhow!("Missing ':' in ternary expression: {}", self.expr));
+        }
+        let when_false = self.parse_ternary()?;
+        Ok(if is_truthy(&condition) {
+            when_true
+        } else {
+            when_false
+        })
+    }
+
+    fn parse_or(&mut self) -> Result<Value> {
+        let mut value = self.parse_and()?;
+        while self.consume_op("||") {
+            let rhs = self.parse_and()?;
+            value = Value::Bool(is_truthy(&value) || is_truthy(&rhs));
+        }
+        Ok(value)
+    }
+
+    fn parse_and(&mut self) -> Result<Value> {
+        let mut value = self.parse_not()?;
+        while self.consume_op("&&") {
+            let rhs = self.parse_not()?;
+            value = Value::Bool(is_truthy(&value) && is_truthy(&rhs));
+        }
+        Ok(value)
+    }
+
+    fn parse_not(&mut self) -> Result<Value> {
+        if self.consume_op("!") {
+            let value = self.parse_not()?;
+            return Ok(Value::Bool(!is_truthy(&value)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Value> {
+        let lhs = self.parse_additive()?;
+        for op in ["<=", ">=", "==", "!=", "<", ">"] {
+            if self.consume_op(op) {
+                let rhs = self.parse_additive()?;
+                return Ok(Value::Bool(compare_values(&lhs, &rhs, op)));
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn parse_additive(&mut self) -> Result<Value> {
+        let mut value = self.parse_multiplicative()?;
+        loop {
+            if self.consume_op("+") {
+                let rhs = self.parse_multiplicative()?;
+                value = match (comparable_number(&value), comparable_number(&rhs)) {
+                    (Some(a), Some(b)) => number_value(a + b),
+                    _ => Value::String(json_value_as_string(&value) + &json_value_as_string(&rhs)),
+                };
+            } else if self.consume_op("-") {
+                let rhs = self.parse_multiplicative()?;
+                value = number_value(value_to_f64(&value)? - value_to_f64(&rhs)?);
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Value> {
+        let mut value = self.parse_unary()?;
+        loop {
+            if self.consume_op("*") {
+                let rhs = self.parse_unary()?;
+                value = number_value(value_to_f64(&value)? * value_to_f64(&rhs)?);
+            } else if self.consume_op("/") {
+                let rhs = value_to_f64(&self.parse_unary()?)?;
+                if rhs == 0.0 {
+                    return Err(anyhow!("Division by zero"));
+                }
+                value = number_value(value_to_f64(&value)? / rhs);
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Value> {
+        if self.consume_op("-") {
+            let value = self.parse_unary()?;
+            return Ok(number_value(-value_to_f64(&value)?));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Value> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unexpected end of expression: {}", self.expr))?;
+        self.pos += 1;
+
+        match token {
+            LogicToken::Number(number) => Ok(number_value(number)),
+            LogicToken::Str(text) => Ok(Value::String(text)),
+            LogicToken::Ident(ident) => Ok(match ident.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => Value::Null,
+                "now()" => number_value(now_timestamp_millis()),
+                // 缺失字段按 null 处理，方便写 `{if field}` 判断是否存在
+                path => extract_value(path, self.data).unwrap_or(Value::Null),
+            }),
+            LogicToken::Op("(") => {
+                let value = self.parse_ternary()?;
+                if !self.consume_op(")") {
+                    return Err(anyhow!(
+                        "Unmatched parenthesis in expression: {}",
+                        self.expr
+                    ));
+                }
+                Ok(value)
+            }
+            LogicToken::Op(op) => Err(anyhow!(
+                "Unexpected operator '{op}' in expression: {}",
+                self.expr
+            )),
+        }
+    }
+
+    fn consume_op(&mut self, expected: &str) -> bool {
+        if matches!(self.tokens.get(self.pos), Some(LogicToken::Op(op)) if *op == expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_end(&self) -> Result<()> {
+        if self.pos < self.tokens.len() {
+            return Err(anyhow!(
+                "Unexpected trailing tokens in expression: {}",
+                self.expr
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn compare_values(lhs: &Value, rhs: &Value, op: &str) -> bool {
+    if lhs.is_null() || rhs.is_null() {
+        return match op {
+            "==" => lhs.is_null() && rhs.is_null(),
+            "!=" => lhs.is_null() != rhs.is_null(),
+            // 缺失数据不参与大小比较
+            _ => false,
+        };
+    }
+
+    let ordering = match (comparable_number(lhs), comparable_number(rhs)) {
+        (Some(a), Some(b)) => a.partial_cmp(&b),
+        _ => Some(json_value_as_string(lhs).cmp(&json_value_as_string(rhs))),
+    };
+    let Some(ordering) = ordering else {
+        return op == "!=";
+    };
+
+    match op {
+        "<" => ordering.is_lt(),
+        "<=" => ordering.is_le(),
+        ">" => ordering.is_gt(),
+        ">=" => ordering.is_ge(),
+        "==" => ordering.is_eq(),
+        _ => ordering.is_ne(),
+    }
+}
+
+fn tokenize_logic_expression(expr: &str) -> Result<Vec<LogicToken>> {
+    const TWO_CHAR_OPS: [&str; 6] = ["<=", ">=", "==", "!=", "&&", "||"];
+    const ONE_CHAR_OPS: [&str; 10] = ["(", ")", "?", ":", "+", "-", "*", "/", "<", ">"];
+
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while let Some(&ch) = chars.get(pos) {
+        if ch.is_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        if ch == '"' || ch == '\'' {
+            let mut text = String::new();
+            pos += 1;
+            loop {
+                match chars.get(pos) {
+                    Some('\\') if chars.get(pos + 1).is_some() => {
+                        text.push(chars[pos + 1]);
+                        pos += 2;
+                    }
+                    Some(&c) if c == ch => {
+                        pos += 1;
+                        break;
+                    }
+                    Some(&c) => {
+                        text.push(c);
+                        pos += 1;
+                    }
+                    None => return Err(anyhow!("Unterminated string in expression: {expr}")),
+                }
+            }
+            tokens.push(LogicToken::Str(text));
+            continue;
+        }
+
+        if ch.is_ascii_digit()
+            || (ch == '.' && chars.get(pos + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = pos;
+            while chars
+                .get(pos)
+                .is_some_and(|c| c.is_ascii_digit() || *c == '.')
+            {
+                pos += 1;
+            }
+            let literal: String = chars[start..pos].iter().collect();
+            let number = literal
+                .parse::<f64>()
+                .map_err(|_| anyhow!("Invalid number in expression: {expr}"))?;
+            tokens.push(LogicToken::Number(number));
+            continue;
+        }
+
+        if is_identifier_start(ch) || ch == '$' {
+            let start = pos;
+            while chars
+                .get(pos)
+                .is_some_and(|c| is_identifier_part(*c) || *c == '.' || *c == '$')
+            {
+                pos += 1;
+            }
+            let mut ident: String = chars[start..pos].iter().collect();
+            match ident.as_str() {
+                "and" => tokens.push(LogicToken::Op("&&")),
+                "or" => tokens.push(LogicToken::Op("||")),
+                "not" => tokens.push(LogicToken::Op("!")),
+                _ => {
+                    if ident.eq_ignore_ascii_case("now")
+                        && chars.get(pos) == Some(&'(')
+                        && chars.get(pos + 1) == Some(&')')
+                    {
+                        pos += 2;
+                        ident = "now()".to_string();
+                    }
+                    tokens.push(LogicToken::Ident(ident));
+                }
+            }
+            continue;
+        }
+
+        let rest: String = chars[pos..chars.len().min(pos + 2)].iter().collect();
+        if let Some(op) = TWO_CHAR_OPS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(LogicToken::Op(op));
+            pos += 2;
+            continue;
+        }
+        if ch == '!' {
+            tokens.push(LogicToken::Op("!"));
+            pos += 1;
+            continue;
+        }
+        if let Some(op) = ONE_CHAR_OPS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(LogicToken::Op(op));
+            pos += 1;
+            continue;
+        }
+
+        return Err(anyhow!("Unexpected character '{ch}' in expression: {expr}"));
+    }
+
+    Ok(tokens)
+}
+
+const fn is_identifier_start(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '_'
+}
+
+const fn is_identifier_part(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_' || ch == '[' || ch == ']'
+}
+
+fn value_token_to_f64(token: &str, data: &Value) -> f64 {
+    if let Ok(number) = token.parse::<f64>() {
+        return number;
     }
 
     if token.eq_ignore_ascii_case("now()") {
@@ -1330,6 +2501,7 @@ content = "Hello"
                 color_support: ColorSupport::TrueColor,
                 supports_emoji: true,
                 supports_nerd_font: false,
+                ..TerminalCapabilities::default()
             },
             preview_mode: false,
         };
@@ -1393,6 +2565,426 @@ method = "GET"
         Ok(())
     }
 
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_api_widget_served_from_cache_with_stale_marker() -> TestResult {
+        let mut config = Config {
+            multiline: Some(MultilineConfig {
+                enabled: true,
+                max_rows: 5,
+                rows: HashMap::new(),
+            }),
+            ..Config::default()
+        };
+        config.components.order = vec!["usage".to_string()];
+
+        let temp_dir = tempfile::tempdir()?;
+        std::env::set_var("STATUSLINE_STORAGE_PATH", temp_dir.path().join("storage"));
+        let component_path = temp_dir.path().join("components").join("usage.toml");
+        let component_dir = component_path
+            .parent()
+            .context("component path missing parent directory")?;
+        std::fs::create_dir_all(component_dir)?;
+        // 端口 9 (discard) 不可达：能渲染出内容说明数据来自缓存
+        let widget_toml = r#"
+[widgets.balance]
+enabled = true
+type = "api"
+row = 1
+col = 0
+nerd_icon = ""
+emoji_icon = ""
+text_icon = ""
+template = "{balance}"
+
+[widgets.balance.api]
+endpoint = "http://127.0.0.1:9/balance"
+timeout = 200
+cache_ttl = 60000
+stale_marker = "(stale)"
+"#;
+        std::fs::write(&component_path, widget_toml)?;
+
+        let component_config: ComponentMultilineConfig = toml_edit::de::from_str(widget_toml)?;
+        let api_config = component_config
+            .widgets
+            .get("balance")
+            .and_then(|widget| widget.api.clone())
+            .context("widget api config missing")?;
+        let request = MultiLineRenderer::build_api_request(&api_config, &ClientEnv::default())?;
+        let entry_path = crate::storage::cache_dir()
+            .join("api")
+            .join(format!("{}.json", request.cache_key()));
+        ApiResponseCache::new().store(&request, &json!({"balance": 12.34}))?;
+
+        let context = RenderContext {
+            input: Arc::new(InputData::default()),
+            config: Arc::new(config.clone()),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: true,
+        };
+
+        let mut renderer =
+            MultiLineRenderer::new(config.clone(), Some(temp_dir.path().to_path_buf()));
+        let fresh = renderer.render_extension_lines(&context).await;
+
+        std::fs::write(
+            &entry_path,
+            json!({"fetched_at": 0, "response": {"balance": 1}}).to_string(),
+        )?;
+        let mut renderer = MultiLineRenderer::new(config, Some(temp_dir.path().to_path_buf()));
+        let stale = renderer.render_extension_lines(&context).await;
+        std::env::remove_var("STATUSLINE_STORAGE_PATH");
+
+        assert_eq!(fresh.lines, vec!["12.34".to_string()]);
+        assert_eq!(stale.lines, vec!["1 (stale)".to_string()]);
+        Ok(())
+    }
+
+    /// 仅启用单个组件的多行配置
+    fn multiline_config_for(component: &str) -> Config {
+        let mut config = Config {
+            multiline: Some(MultilineConfig {
+                enabled: true,
+                max_rows: 5,
+                rows: HashMap::new(),
+            }),
+            ..Config::default()
+        };
+        config.components.order = vec![component.to_string()];
+        config
+    }
+
+    /// 单次响应的本地 HTTP 替身，返回收到的原始请求
+    fn spawn_http_stand_in(
+        response_body: &'static str,
+    ) -> Result<(String, std::thread::JoinHandle<Result<String>>)> {
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let address = format!("http://{}", listener.local_addr()?);
+        let handle = std::thread::spawn(move || -> Result<String> {
+            let (stream, _) = listener.accept()?;
+            let mut reader = BufReader::new(stream.try_clone()?);
+            let mut raw = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line)?;
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse()?;
+                    }
+                }
+                raw.push_str(&line);
+                if line == "\r\n" || line.is_empty() {
+                    break;
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body)?;
+            raw.push_str(&String::from_utf8(body)?);
+
+            let mut stream = stream;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response_body}",
+                response_body.len()
+            )?;
+            Ok(raw)
+        });
+        Ok((address, handle))
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_api_widget_sends_body_and_query() -> TestResult {
+        let (address, server) = spawn_http_stand_in(r#"{"data":{"balance":42}}"#)?;
+        std::env::set_var("STATUSLINE_TEST_RELAY_USER", "alice");
+
+        let mut config = Config {
+            multiline: Some(MultilineConfig {
+                enabled: true,
+                max_rows: 5,
+                rows: HashMap::new(),
+            }),
+            ..Config::default()
+        };
+        config.components.order = vec!["usage".to_string()];
+
+        let temp_dir = tempfile::tempdir()?;
+        let component_dir = temp_dir.path().join("components");
+        std::fs::create_dir_all(&component_dir)?;
+        let widget_toml = format!(
+            r#"
+[widgets.balance]
+enabled = true
+type = "api"
+row = 1
+col = 0
+nerd_icon = ""
+emoji_icon = ""
+text_icon = ""
+template = "{{balance}}"
+
+[widgets.balance.api]
+base_url = "{address}"
+endpoint = "/api/balance"
+method = "POST"
+timeout = 2000
+data_path = "$.data"
+
+[widgets.balance.api.query]
+user = "${{STATUSLINE_TEST_RELAY_USER}}"
+
+[widgets.balance.api.body]
+user = "${{STATUSLINE_TEST_RELAY_USER}}"
+fields = ["balance"]
+"#
+        );
+        std::fs::write(component_dir.join("usage.toml"), widget_toml)?;
+
+        let context = RenderContext {
+            input: Arc::new(InputData::default()),
+            config: Arc::new(config.clone()),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: true,
+        };
+        let mut renderer = MultiLineRenderer::new(config, Some(temp_dir.path().to_path_buf()));
+        let result = renderer.render_extension_lines(&context).await;
+        std::env::remove_var("STATUSLINE_TEST_RELAY_USER");
+
+        let raw_request = server
+            .join()
+            .map_err(|_| anyhow::anyhow!("HTTP stand-in panicked"))??;
+        assert_eq!(result.lines, vec!["42".to_string()]);
+        assert!(raw_request.starts_with("POST /api/balance?user=alice HTTP/1.1"));
+        assert!(raw_request
+            .to_ascii_lowercase()
+            .contains("content-type: application/json"));
+        assert!(raw_request.ends_with(r#"{"user":"alice","fields":["balance"]}"#));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_command_widget_renders_plain_and_json_output() -> TestResult {
+        let config = multiline_config_for("project");
+
+        let temp_dir = tempfile::tempdir()?;
+        let project_dir = temp_dir.path().join("workspace");
+        std::fs::create_dir_all(&project_dir)?;
+        let component_dir = temp_dir.path().join("components");
+        std::fs::create_dir_all(&component_dir)?;
+        let widget_toml = r#"
+[widgets.context]
+type = "command"
+row = 1
+col = 0
+nerd_icon = ""
+emoji_icon = ""
+text_icon = ""
+
+[widgets.context.command]
+program = "sh"
+args = ["-c", '''printf 'ctx-%s\n' "$(basename "$PWD")"''']
+
+[widgets.quota]
+type = "command"
+row = 2
+col = 0
+nerd_icon = ""
+emoji_icon = ""
+text_icon = ""
+template = "{used}/{limit}"
+
+[widgets.quota.command]
+program = "sh"
+args = ["-c", '''echo "{\"quota\": {\"used\": $USED, \"limit\": 10}}"''']
+env = { USED = "3" }
+data_path = "$.quota"
+
+[widgets.failing]
+type = "command"
+row = 3
+col = 0
+nerd_icon = ""
+emoji_icon = ""
+text_icon = ""
+
+[widgets.failing.command]
+program = "sh"
+args = ["-c", "exit 1"]
+"#;
+        std::fs::write(component_dir.join("project.toml"), widget_toml)?;
+
+        let input = InputData {
+            cwd: Some(project_dir.to_string_lossy().into_owned()),
+            ..InputData::default()
+        };
+        let context = RenderContext {
+            input: Arc::new(input),
+            config: Arc::new(config.clone()),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: true,
+        };
+        let mut renderer = MultiLineRenderer::new(config, Some(temp_dir.path().to_path_buf()));
+        let result = renderer.render_extension_lines(&context).await;
+
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(
+            result.lines,
+            vec!["ctx-workspace".to_string(), "3/10".to_string()]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_widget_reads_formats_and_tracks_mtime() -> TestResult {
+        let config = multiline_config_for("project");
+
+        let temp_dir = tempfile::tempdir()?;
+        let project_dir = temp_dir.path().join("workspace");
+        std::fs::create_dir_all(project_dir.join(".ci"))?;
+        let ci_path = project_dir.join(".ci").join("result.json");
+        std::fs::write(
+            &ci_path,
+            r#"{"pipeline": {"status": "passed", "duration": 42}}"#,
+        )?;
+        std::fs::write(
+            project_dir.join("quota.toml"),
+            "[quota]\nused = 3\nlimit = 10\n",
+        )?;
+        std::fs::write(project_dir.join("notes.txt"), "first\nsecond\n")?;
+
+        let component_dir = temp_dir.path().join("components");
+        std::fs::create_dir_all(&component_dir)?;
+        let widget_toml = r#"
+[widgets.ci]
+type = "file"
+row = 1
+col = 0
+nerd_icon = ""
+emoji_icon = ""
+text_icon = ""
+template = "CI {status} ({duration}s)"
+
+[widgets.ci.file]
+path = ".ci/result.json"
+data_path = "$.pipeline"
+
+[widgets.ci.filter]
+object = "$.pipeline.status"
+mode = "pattern"
+keyword = "passed|failed"
+
+[widgets.quota]
+type = "file"
+row = 2
+col = 0
+nerd_icon = ""
+emoji_icon = ""
+text_icon = ""
+template = "{used}/{limit}"
+
+[widgets.quota.file]
+path = "quota.toml"
+data_path = "$.quota"
+
+[widgets.notes]
+type = "file"
+row = 3
+col = 0
+nerd_icon = ""
+emoji_icon = ""
+text_icon = ""
+template = "{lines[1]}@{session.session_id}"
+
+[widgets.notes.file]
+path = "notes.txt"
+"#;
+        std::fs::write(component_dir.join("project.toml"), widget_toml)?;
+
+        let context_for = |session_id: &str| RenderContext {
+            input: Arc::new(InputData {
+                session_id: Some(session_id.to_string()),
+                cwd: Some(project_dir.to_string_lossy().into_owned()),
+                ..InputData::default()
+            }),
+            config: Arc::new(config.clone()),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: true,
+        };
+        let context = context_for("s1");
+        let mut renderer =
+            MultiLineRenderer::new(config.clone(), Some(temp_dir.path().to_path_buf()));
+        let first = renderer.render_extension_lines(&context).await;
+        assert_eq!(first.lines, ["CI passed (42s)", "3/10", "second@s1"]);
+
+        // mtime 未变化：沿用已解析的文件数据，session.* 仍按本次输入渲染
+        let modified = std::fs::metadata(&ci_path)?.modified()?;
+        std::fs::write(
+            &ci_path,
+            r#"{"pipeline": {"status": "running", "duration": 7}}"#,
+        )?;
+        std::fs::File::options()
+            .write(true)
+            .open(&ci_path)?
+            .set_modified(modified)?;
+        let unchanged = renderer.render_extension_lines(&context_for("s2")).await;
+        assert_eq!(unchanged.lines, ["CI passed (42s)", "3/10", "second@s2"]);
+
+        // mtime 变化：重新读取，filter 不匹配时隐藏
+        std::fs::File::options()
+            .write(true)
+            .open(&ci_path)?
+            .set_modified(modified + std::time::Duration::from_secs(5))?;
+        let changed = renderer.render_extension_lines(&context).await;
+        assert_eq!(
+            changed.lines,
+            vec!["3/10".to_string(), "second@s1".to_string()]
+        );
+
+        std::fs::remove_file(project_dir.join("quota.toml"))?;
+        let removed = renderer.render_extension_lines(&context).await;
+        assert_eq!(removed.lines, vec!["second@s1".to_string()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_api_request_form_body() -> TestResult {
+        let api_config: WidgetApiConfig = toml_edit::de::from_str(
+            r#"
+endpoint = "https://relay.example.com/query"
+method = "POST"
+content_type = "application/x-www-form-urlencoded"
+body = { key = "abc", days = 7 }
+"#,
+        )?;
+        let request = MultiLineRenderer::build_api_request(&api_config, &ClientEnv::default())?;
+
+        assert_eq!(
+            request.body,
+            Some(ApiBody::Form(vec![
+                ("key".to_string(), "abc".to_string()),
+                ("days".to_string(), "7".to_string()),
+            ]))
+        );
+
+        let raw_config: WidgetApiConfig = toml_edit::de::from_str(
+            r#"
+endpoint = "https://relay.example.com/query"
+method = "POST"
+content_type = "text/plain"
+body = "ping"
+"#,
+        )?;
+        let request = MultiLineRenderer::build_api_request(&raw_config, &ClientEnv::default())?;
+        assert_eq!(request.body, Some(ApiBody::Raw("ping".to_string())));
+        assert_eq!(request.content_type.as_deref(), Some("text/plain"));
+        Ok(())
+    }
+
     #[test]
     fn test_expression_template_rendering() {
         let data = serde_json::json!({
@@ -1410,6 +3002,366 @@ method = "GET"
         assert_eq!(rendered_percent, "100.00%");
     }
 
+    #[test]
+    fn test_logic_expression_operators() -> TestResult {
+        let data = json!({
+            "remaining": 3.5,
+            "plan": "pro",
+            "usage": {"used": 80, "limit": 100},
+            "flags": [],
+        });
+
+        let eval = |expr: &str| evaluate_logic_expression(expr, &data);
+        assert_eq!(eval("remaining < 5")?, json!(true));
+        assert_eq!(eval("remaining >= 3.5 && plan == 'pro'")?, json!(true));
+        assert_eq!(
+            eval("plan != \"pro\" || usage.used / usage.limit > 0.5")?,
+            json!(true)
+        );
+        assert_eq!(eval("!(remaining < 5) or not flags")?, json!(true));
+        assert_eq!(eval("usage.used + 20 == usage.limit")?, json!(true));
+        assert_eq!(eval("(usage.limit - usage.used) * 2")?, json!(40));
+        assert_eq!(eval("missing > 0")?, json!(false));
+        assert_eq!(eval("missing == null")?, json!(true));
+        assert_eq!(eval("remaining < 5 ? 'low' : 'ok'")?, json!("low"));
+        assert_eq!(
+            eval("remaining < 1 ? 'empty' : remaining < 5 ? 'low' : 'ok'")?,
+            json!("low")
+        );
+        assert!(eval("remaining <").is_err());
+        assert!(eval("remaining ? 'a'").is_err());
+        assert!(eval("plan == 'pro").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_format_spec_respects_ternary_and_quotes() {
+        assert_eq!(
+            split_format_spec("quota / 2:.2f"),
+            ("quota / 2", Some(".2f"))
+        );
+        assert_eq!(
+            split_format_spec("a > 1 ? 'x:y' : b:.1f"),
+            ("a > 1 ? 'x:y' : b", Some(".1f"))
+        );
+        assert_eq!(split_format_spec("a ? b : c"), ("a ? b : c", None));
+    }
+
+    #[test]
+    fn test_template_conditional_blocks() {
+        let template = "{if remaining < 5}⚠ low {remaining}{elif remaining < 20}ok {remaining}{else}plenty{end}";
+        assert_eq!(
+            render_template(template, &json!({"remaining": 3})),
+            "⚠ low 3"
+        );
+        assert_eq!(
+            render_template(template, &json!({"remaining": 12})),
+            "ok 12"
+        );
+        assert_eq!(
+            render_template(template, &json!({"remaining": 99})),
+            "plenty"
+        );
+
+        let nested = "[{if active}{if plan == 'pro'}PRO{else}FREE{end}{end}]";
+        assert_eq!(
+            render_template(nested, &json!({"active": true, "plan": "pro"})),
+            "[PRO]"
+        );
+        assert_eq!(render_template(nested, &json!({"active": false})), "[]");
+
+        let ternary = "{balance < 1 ? '余额不足' : balance}";
+        assert_eq!(
+            render_template(ternary, &json!({"balance": 0.5})),
+            "余额不足"
+        );
+        assert_eq!(render_template(ternary, &json!({"balance": 8})), "8");
+
+        let formatted = "{used / limit > 0.9 ? used / limit * 100 : 0:.1f}";
+        assert_eq!(
+            render_template(formatted, &json!({"used": 95, "limit": 100})),
+            "95.0"
+        );
+    }
+
+    #[test]
+    fn test_template_unbalanced_blocks_render_literally() {
+        let data = json!({"value": 1});
+        assert_eq!(render_template("{if value}x", &data), "{if value}x");
+        assert_eq!(render_template("x{end}", &data), "x{end}");
+    }
+
+    #[test]
+    fn test_widget_colors_follow_thresholds_and_color_support() -> TestResult {
+        let widget: WidgetConfig = toml_edit::de::from_str(
+            r##"
+row = 1
+col = 0
+nerd_icon = ""
+emoji_icon = "💰"
+text_icon = "$"
+icon_color = "cyan"
+text_color = "white"
+
+[thresholds]
+value = "remaining"
+warning = 10
+danger = 2
+reverse = true
+danger_color = "#ff0000"
+"##,
+        )?;
+        let config = Config::default();
+        let context_with = |color_support| RenderContext {
+            input: Arc::new(InputData::default()),
+            config: Arc::new(config.clone()),
+            terminal: TerminalCapabilities {
+                color_support,
+                supports_emoji: true,
+                supports_nerd_font: false,
+                ..TerminalCapabilities::default()
+            },
+            preview_mode: true,
+        };
+        let paint = |text: &str, color: &str, support| -> Result<String> {
+            let code = themes::ansi_fg_with_support(color, support, OutputTarget::Ansi)
+                .context("color should resolve")?;
+            Ok(format!("{code}{text}{}", themes::ANSI_RESET))
+        };
+
+        let truecolor = context_with(ColorSupport::TrueColor);
+        let safe = MultiLineRenderer::compose_with_icon(
+            &widget,
+            "42",
+            &json!({"remaining": 42}),
+            &truecolor,
+            &config,
+        );
+        assert_eq!(
+            safe,
+            format!(
+                "{} {}",
+                paint("💰", "cyan", ColorSupport::TrueColor)?,
+                paint("42", "white", ColorSupport::TrueColor)?
+            )
+        );
+
+        let warning = MultiLineRenderer::compose_with_icon(
+            &widget,
+            "5",
+            &json!({"remaining": 5}),
+            &truecolor,
+            &config,
+        );
+        assert!(warning.ends_with(&paint("5", "yellow", ColorSupport::TrueColor)?));
+
+        // 16 色终端按 ColorSupport 降级
+        let basic = context_with(ColorSupport::Basic16);
+        let danger = MultiLineRenderer::compose_with_icon(
+            &widget,
+            "1",
+            &json!({"remaining": 1}),
+            &basic,
+            &config,
+        );
+        assert!(danger.ends_with(&paint("1", "#ff0000", ColorSupport::Basic16)?));
+
+        let plain = MultiLineRenderer::compose_with_icon(
+            &widget,
+            "1",
+            &json!({"remaining": 1}),
+            &context_with(ColorSupport::None),
+            &config,
+        );
+        assert_eq!(plain, "💰 1");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_static_widget_reads_session_namespace() -> TestResult {
+        let config = multiline_config_for("project");
+        let temp_dir = tempfile::tempdir()?;
+        let component_dir = temp_dir.path().join("components");
+        std::fs::create_dir_all(&component_dir)?;
+        let widget_toml = r#"
+[widgets.cost]
+type = "static"
+row = 1
+col = 0
+nerd_icon = ""
+emoji_icon = ""
+text_icon = ""
+template = "{session.model.display_name} \\${session.cost.total_cost_usd / session.duration_hours:.2f}/h ctx {session.tokens.percentage:.0f}%"
+
+[widgets.literal]
+type = "static"
+row = 2
+col = 0
+nerd_icon = ""
+emoji_icon = ""
+text_icon = ""
+content = "deploy {prod} {session.model.id}"
+"#;
+        std::fs::write(component_dir.join("project.toml"), widget_toml)?;
+
+        let input = InputData::from_json(
+            r#"{
+                "model": {"id": "claude-sonnet-4-5-20250929[1m]", "display_name": "Sonnet 4"},
+                "cost": {"total_cost_usd": 3.0, "total_duration_ms": 7200000},
+                "context_window": {
+                    "current_usage": {"input_tokens": 200000, "cache_read_input_tokens": 50000}
+                }
+            }"#,
+        )?;
+        let context = RenderContext {
+            input: Arc::new(input),
+            config: Arc::new(config.clone()),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: true,
+        };
+        let mut renderer = MultiLineRenderer::new(config, Some(temp_dir.path().to_path_buf()));
+        let result = renderer.render_extension_lines(&context).await;
+
+        // 窗口大小与 tokens 组件一致：[1m] 模型按 1M 计算；content 保持字面量
+        assert_eq!(
+            result.lines,
+            [
+                "Sonnet 4 $1.50/h ctx 25%",
+                "deploy {prod} {session.model.id}"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_session_namespace_merges_with_widget_data() -> TestResult {
+        let input = InputData::from_json(
+            r#"{"git": {"branch": "main", "ahead": 2}, "workspace": {"project_dir": "/repo"}}"#,
+        )?;
+        let context = RenderContext {
+            input: Arc::new(input),
+            config: Arc::new(Config::default()),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: true,
+        };
+        let namespace = build_session_value(&context);
+
+        let data = with_session(&json!({"balance": 5}), &namespace);
+        assert_eq!(
+            render_template("{balance} {session.git.branch}+{session.git.ahead}", &data),
+            "5 main+2"
+        );
+        assert_eq!(render_template("{session.project_dir}", &data), "/repo");
+
+        // 响应自带 session 字段时不覆盖
+        let own = with_session(&json!({"session": "remote"}), &namespace);
+        assert_eq!(render_template("{session}", &own), "remote");
+        Ok(())
+    }
+
+    #[test]
+    fn test_transcript_aggregates_reach_session_namespace() -> TestResult {
+        let input = InputData::from_json(r#"{"session_id": "s"}"#)?;
+        let context = RenderContext {
+            input: Arc::new(input),
+            config: Arc::new(Config::default()),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: true,
+        };
+        let mut namespace = build_session_value(&context);
+        let aggregates = TranscriptAggregates {
+            user_prompts: 3,
+            assistant_turns: 7,
+            tool_uses: 5,
+            tool_errors: 1,
+            usage: serde_json::from_value(json!({"input": 120, "output": 40}))?,
+            ..TranscriptAggregates::default()
+        };
+        insert_transcript(&mut namespace, &aggregates);
+
+        let data = with_session(&Value::Null, &namespace);
+        assert_eq!(
+            render_template(
+                "{session.transcript.user_prompts}/{session.transcript.assistant_turns} {session.transcript.tool_errors}/{session.transcript.tool_uses} {session.transcript.usage.output}",
+                &data
+            ),
+            "3/7 1/5 40"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_git_version_loaded_only_for_version_fields() -> TestResult {
+        let config_with = |template: &str| {
+            toml_edit::de::from_str::<ComponentMultilineConfig>(&format!(
+                "[widgets.a]\nrow = 1\ncol = 0\nnerd_icon = \"\"\nemoji_icon = \"\"\ntext_icon = \"\"\ntemplate = \"{template}\"\n"
+            ))
+        };
+        assert!(!uses_git_version(&config_with("{session.git.branch}")?));
+        assert!(!uses_git_version(&config_with("{session.project_dir}")?));
+        assert!(uses_git_version(&config_with(
+            "{session.git.branch} {session.git.tag_label}"
+        )?));
+        assert!(uses_git_version(&config_with("{session.git.branches}")?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_git_version_fields_reach_session_namespace() -> TestResult {
+        let input = InputData::from_json(r#"{"git": {"branch": "main"}}"#)?;
+        let context = RenderContext {
+            input: Arc::new(input),
+            config: Arc::new(Config::default()),
+            terminal: TerminalCapabilities::default(),
+            preview_mode: true,
+        };
+        let mut namespace = build_session_value(&context);
+        let version = GitVersionInfo {
+            commit_id: "0123456789abcdef".to_string(),
+            short_commit_id: "0123456".to_string(),
+            timestamp: 1_700_000_000,
+            tag: Some("v1.2.0".to_string()),
+            tag_distance: Some(3),
+            ..GitVersionInfo::default()
+        };
+        insert_git_version(&mut namespace, &version, 1_700_000_000 + 2 * 86_400);
+
+        let data = with_session(&Value::Null, &namespace);
+        assert_eq!(
+            render_template(
+                "{session.git.branch} {session.git.tag_label} {session.git.short_commit} {session.git.last_commit_age}",
+                &data
+            ),
+            "main v1.2.0+3 0123456 2d ago"
+        );
+        assert_eq!(
+            render_template("{session.git.commits_since_tag}", &data),
+            "3"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_grid_truncates_rows_by_display_width() {
+        let mut config = MultilineConfig::default();
+        config.rows.insert(
+            "1".to_string(),
+            MultilineRowConfig {
+                separator: " | ".to_string(),
+                max_width: 12,
+            },
+        );
+
+        let mut grid = MultiLineGrid::default();
+        grid.set_cell(1, 0, "\x1b[32m余额 ¥12.5\x1b[0m".to_string());
+        grid.set_cell(1, 1, "套餐".to_string());
+        grid.set_cell(2, 0, "short".to_string());
+
+        let lines = grid.render(&config);
+        assert_eq!(lines, vec!["\x1b[32m余额 ¥12.5\x1b[0m …\x1b[0m", "short"]);
+        assert!(width::display_width(&lines[0]) <= 12);
+    }
+
     #[test]
     fn test_value_matches_filter_equals() {
         let filter = WidgetFilterConfig {
@@ -1439,19 +3391,19 @@ method = "GET"
 
         // 测试转义的美元符号
         let input = [r"余额:\$", "{", "quota / 500000:.2f", "}"].concat();
-        let result = substitute_env(&input);
+        let result = substitute_env(&input, &ClientEnv::default());
         let expected_escaped = concat!("余额:$", "{quota / 500000:.2f}");
         assert_eq!(result, expected_escaped);
 
         // 测试混合使用：环境变量和转义的美元符号
         let input = [r"API: ${TEST_VAR}, 余额:\$", "{", "quota:.2f", "}"].concat();
-        let result = substitute_env(&input);
+        let result = substitute_env(&input, &ClientEnv::default());
         let expected_mixed = concat!("API: test_value, 余额:$", "{quota:.2f}");
         assert_eq!(result, expected_mixed);
 
         // 测试仅环境变量
         let input = "API: ${TEST_VAR}";
-        let result = substitute_env(input);
+        let result = substitute_env(input, &ClientEnv::default());
         assert_eq!(result, "API: test_value");
 
         // 清理测试环境变量
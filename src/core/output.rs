@@ -0,0 +1,154 @@
+//! Structured statusline output
+//!
+//! The same render the ANSI statusline is built from, in a shape external
+//! consumers (tmux, editor plugins) can serialize and lay out themselves.
+
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::components::ComponentOutput;
+use crate::themes::OutputTarget;
+use crate::utils::width;
+
+/// How the main run path prints the statusline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Styled terminal text (Claude Code statusline)
+    #[default]
+    Ansi,
+    /// [`StatuslineOutput`] serialized as JSON
+    Json,
+    /// tmux status format (`#[fg=…]`)
+    Tmux,
+    /// zsh prompt with `%{…%}`-wrapped escapes
+    Zsh,
+    /// bash prompt with `\[…\]`-wrapped escapes
+    Bash,
+}
+
+impl OutputFormat {
+    /// Name accepted by `--output`
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Ansi => "ansi",
+            Self::Json => "json",
+            Self::Tmux => "tmux",
+            Self::Zsh => "zsh",
+            Self::Bash => "bash",
+        }
+    }
+
+    /// Target the styled lines are rendered for; `None` for JSON
+    #[must_use]
+    pub const fn target(self) -> Option<OutputTarget> {
+        match self {
+            Self::Ansi => Some(OutputTarget::Ansi),
+            Self::Tmux => Some(OutputTarget::Tmux),
+            Self::Zsh => Some(OutputTarget::Zsh),
+            Self::Bash => Some(OutputTarget::Bash),
+            Self::Json => None,
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "ansi" => Ok(Self::Ansi),
+            "json" => Ok(Self::Json),
+            "tmux" => Ok(Self::Tmux),
+            "zsh" => Ok(Self::Zsh),
+            "bash" => Ok(Self::Bash),
+            other => Err(format!(
+                "未知的输出格式: {other}（可选 ansi / json / tmux / zsh / bash）"
+            )),
+        }
+    }
+}
+
+/// One component as rendered for this refresh
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentEntry {
+    pub name: String,
+    pub text: String,
+    pub icon: Option<String>,
+    pub icon_color: Option<String>,
+    pub text_color: Option<String>,
+    /// Segment color the powerline / capsule themes would use
+    pub theme_color: String,
+    pub visible: bool,
+}
+
+impl ComponentEntry {
+    #[must_use]
+    pub fn new(output: &ComponentOutput, theme_color: String) -> Self {
+        Self {
+            name: output.component_name.clone().unwrap_or_default(),
+            text: output.text.clone(),
+            icon: output.icon.clone().filter(|icon| !icon.is_empty()),
+            icon_color: output.icon_color.clone(),
+            text_color: output.text_color.clone(),
+            theme_color,
+            visible: output.visible,
+        }
+    }
+}
+
+/// Render time of a single component
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentTiming {
+    pub name: String,
+    pub elapsed_ms: u64,
+    /// Missed its deadline (or failed) and fell back to the last output or placeholder
+    pub timed_out: bool,
+}
+
+/// Where the time of one refresh went
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RenderTiming {
+    pub total_ms: u64,
+    pub components_ms: u64,
+    pub multiline_ms: u64,
+    pub components: Vec<ComponentTiming>,
+}
+
+/// Everything one refresh produced
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StatuslineOutput {
+    pub theme: String,
+    /// Components in configured order, including hidden ones
+    pub components: Vec<ComponentEntry>,
+    /// Multiline rows as plain text
+    pub rows: Vec<String>,
+    pub timing: RenderTiming,
+    /// Styled lines in the escape syntax of the target they were rendered for
+    #[serde(skip)]
+    pub lines: Vec<String>,
+}
+
+impl StatuslineOutput {
+    /// The statusline as printed
+    #[must_use]
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Build the plain multiline rows from styled extension lines
+    #[must_use]
+    pub fn plain_rows(lines: &[String]) -> Vec<String> {
+        lines.iter().map(|line| width::strip_ansi(line)).collect()
+    }
+
+    /// Serialize for `--output json`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when JSON serialization fails.
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
@@ -0,0 +1,586 @@
+//! Long-running statusline daemon
+//!
+//! Every refresh normally spawns a fresh process that reloads the TOML
+//! config, re-detects the terminal and rebuilds all components from scratch.
+//! `daemon` keeps a warm [`StatuslineGenerator`] per working directory,
+//! preset and theme behind a Unix socket. The default invocation forwards its
+//! stdin payload there and renders in-process whenever the daemon is absent
+//! or fails.
+//!
+//! Each connection carries one JSON [`DaemonRequest`], answered by one JSON
+//! [`DaemonResponse`]. Config files are re-checked (size and mtime) on every
+//! request, so edits apply on the next refresh without restarting. Requests
+//! carry the client's environment and capability flags, so colors, emoji,
+//! `${VAR}` substitution and widget commands see the client, not the daemon.
+
+use std::collections::HashMap;
+use std::fs;
+use std::net::Shutdown;
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::task;
+
+use crate::config::{Config, ConfigLoader, RuntimeOverrides};
+use crate::core::{GeneratorOptions, InputData, OutputFormat, StatuslineGenerator};
+use crate::storage;
+use crate::utils::client_env::ClientEnv;
+
+/// Socket file name under the shared cache dir
+pub const SOCKET_FILE_NAME: &str = "daemon.sock";
+
+/// How long either side waits on a stalled peer
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Warm generators kept at most (one per cwd / preset / theme / flags)
+const MAX_SESSIONS: usize = 32;
+
+/// Socket path from `STATUSLINE_DAEMON_SOCKET`, else
+/// `<storage>/statusline-pro/cache/daemon.sock`
+#[must_use]
+pub fn socket_path() -> PathBuf {
+    std::env::var_os("STATUSLINE_DAEMON_SOCKET")
+        .filter(|path| !path.is_empty())
+        .map_or_else(
+            || storage::cache_dir().join(SOCKET_FILE_NAME),
+            PathBuf::from,
+        )
+}
+
+/// One render request forwarded by a client
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonRequest {
+    /// Client working directory; selects the project-level config
+    pub cwd: Option<PathBuf>,
+    pub preset: Option<String>,
+    pub theme: Option<String>,
+    /// `--no-colors` / `--force-text` style flags
+    #[serde(default)]
+    pub overrides: RuntimeOverrides,
+    /// `--output` format name
+    pub output: String,
+    /// Columns of the client's terminal, which the daemon cannot see
+    pub columns: Option<usize>,
+    /// Client environment; `None` renders with the daemon's own
+    #[serde(default)]
+    pub env: Option<HashMap<String, String>>,
+    /// Raw stdin payload from Claude Code
+    pub input: String,
+}
+
+/// Rendered statusline or the reason rendering failed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonResponse {
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Render `request` through the daemon listening on `socket`
+///
+/// # Errors
+///
+/// Returns an error when no daemon listens on `socket`, the exchange stalls,
+/// or the daemon could not render; callers fall back to rendering in-process.
+pub fn request(socket: &Path, request: &DaemonRequest) -> Result<String> {
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    serde_json::to_writer(&mut stream, request)?;
+    stream.shutdown(Shutdown::Write)?;
+
+    let response: DaemonResponse = serde_json::from_reader(&mut stream)?;
+    response.output.ok_or_else(|| {
+        anyhow!(response
+            .error
+            .unwrap_or_else(|| "empty daemon response".to_string()))
+    })
+}
+
+/// Serve render requests on `socket` until the process is terminated
+///
+/// # Errors
+///
+/// Returns an error when another daemon already owns `socket` or the socket
+/// cannot be bound.
+pub async fn serve(socket: &Path) -> Result<()> {
+    let listener = Arc::new(bind(socket)?);
+    let daemon = Arc::new(Daemon::default());
+
+    loop {
+        let accept = Arc::clone(&listener);
+        let stream = match task::spawn_blocking(move || accept.accept()).await? {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                eprintln!("[daemon] failed to accept connection: {err}");
+                continue;
+            }
+        };
+
+        // 每个连接一个 task,慢客户端或慢渲染不会挡住其它会话
+        let daemon = Arc::clone(&daemon);
+        tokio::spawn(async move {
+            if let Err(err) = daemon.serve_connection(stream).await {
+                eprintln!("[daemon] dropped request: {err}");
+            }
+        });
+    }
+}
+
+fn bind(socket: &Path) -> Result<UnixListener> {
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            bail!("daemon already running at {}", socket.display());
+        }
+        // 上一个 daemon 未清理的残留 socket
+        fs::remove_file(socket)
+            .with_context(|| format!("failed to remove stale socket {}", socket.display()))?;
+    }
+    if let Some(parent) = socket.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // stdin 里有会话数据,只允许当前用户连接。先在 0700 的临时目录里
+    // bind 并收紧权限,再 rename 到目标路径,其它用户看不到权限放开的窗口
+    let staging = socket.with_file_name(format!(".bind-{}", std::process::id()));
+    fs::DirBuilder::new()
+        .mode(0o700)
+        .create(&staging)
+        .with_context(|| format!("failed to create staging directory {}", staging.display()))?;
+    let staged = staging.join("daemon.sock");
+    let result = UnixListener::bind(&staged)
+        .with_context(|| format!("failed to bind {}", socket.display()))
+        .and_then(|listener| {
+            fs::set_permissions(&staged, fs::Permissions::from_mode(0o600))?;
+            fs::rename(&staged, socket)?;
+            Ok(listener)
+        });
+    let _ = fs::remove_file(&staged);
+    let _ = fs::remove_dir(&staging);
+    result
+}
+
+fn read_request(stream: UnixStream) -> Result<(UnixStream, DaemonRequest)> {
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let request = serde_json::from_reader(&stream)?;
+    Ok((stream, request))
+}
+
+/// Generators are kept apart by everything that changes their config
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SessionKey {
+    cwd: PathBuf,
+    preset: Option<String>,
+    theme: Option<String>,
+    overrides: RuntimeOverrides,
+}
+
+struct Session {
+    generator: StatuslineGenerator,
+    /// Size and mtime of the config files the generator was built from
+    config_stamps: Vec<Option<(u64, SystemTime)>>,
+}
+
+impl Session {
+    async fn load(key: &SessionKey, config_stamps: Vec<Option<(u64, SystemTime)>>) -> Result<Self> {
+        let (config, base_dir) = load_config(key).await?;
+        let options = GeneratorOptions {
+            config_base_dir: base_dir.map(|dir| dir.to_string_lossy().to_string()),
+            update_throttling: false,
+            ..GeneratorOptions::default()
+        };
+        let options = match &key.preset {
+            Some(preset) => options.with_preset(preset.clone()),
+            None => options,
+        };
+        Ok(Self {
+            generator: StatuslineGenerator::new(config, options),
+            config_stamps,
+        })
+    }
+}
+
+/// A session is built lazily by the first request holding its lock
+type SessionSlot = Arc<AsyncMutex<Option<Session>>>;
+
+struct SessionEntry {
+    slot: SessionSlot,
+    last_used: Instant,
+}
+
+/// Warm generators of a running daemon
+///
+/// Connections are read and configs loaded concurrently, but renders run one
+/// at a time: storage keeps the active project id and settings in process
+/// globals, which every render sets and then reads across its awaits. At most
+/// [`MAX_SESSIONS`] generators stay warm, the least recently used is dropped
+/// first.
+#[derive(Default)]
+pub struct Daemon {
+    sessions: Mutex<HashMap<SessionKey, SessionEntry>>,
+    /// Held from storage activation to the last persisted write of a render
+    render: AsyncMutex<()>,
+}
+
+impl Daemon {
+    /// Render one request, reloading the config when its files changed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for an unknown output format, malformed input, or
+    /// when the config cannot be loaded or the statusline fails to render.
+    pub async fn handle(&self, request: DaemonRequest) -> Result<String> {
+        let format: OutputFormat = request.output.parse().map_err(|err: String| anyhow!(err))?;
+        let mut input = InputData::parse(&request.input)?;
+        if let Some(env) = request.env {
+            input.env = ClientEnv::forwarded(env);
+        }
+        let key = SessionKey {
+            cwd: match request.cwd {
+                Some(cwd) => cwd,
+                None => std::env::current_dir()?,
+            },
+            preset: request.preset,
+            theme: request.theme,
+            overrides: request.overrides,
+        };
+
+        let slot = self.checkout(&key);
+        let mut slot = slot.lock().await;
+        let config_stamps = config_stamps(&key.cwd);
+        let session = match &mut *slot {
+            Some(session) => {
+                if session.config_stamps != config_stamps {
+                    session.generator.update_config(load_config(&key).await?.0);
+                    session.config_stamps = config_stamps;
+                }
+                session
+            }
+            empty @ None => empty.insert(Session::load(&key, config_stamps).await?),
+        };
+
+        session.generator.set_terminal_width(request.columns);
+        let render = self.render.lock().await;
+        let output = session.generator.generate_formatted(input, format).await;
+        drop(render);
+        drop(slot);
+        output
+    }
+
+    async fn serve_connection(&self, stream: UnixStream) -> Result<()> {
+        let (stream, request) = task::spawn_blocking(move || read_request(stream)).await??;
+        let response = match self.handle(request).await {
+            Ok(output) => DaemonResponse {
+                output: Some(output),
+                error: None,
+            },
+            Err(err) => DaemonResponse {
+                output: None,
+                error: Some(err.to_string()),
+            },
+        };
+        task::spawn_blocking(move || serde_json::to_writer(&stream, &response))
+            .await?
+            .context("failed to write response")
+    }
+
+    /// Session slot for `key`, evicting the least recently used ones beyond
+    /// [`MAX_SESSIONS`]
+    ///
+    /// An evicted session still rendering finishes on its own `Arc`.
+    fn checkout(&self, key: &SessionKey) -> SessionSlot {
+        let mut sessions = self.sessions.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        let entry = sessions.entry(key.clone()).or_insert_with(|| SessionEntry {
+            slot: SessionSlot::default(),
+            last_used: now,
+        });
+        entry.last_used = now;
+        let slot = Arc::clone(&entry.slot);
+
+        while sessions.len() > MAX_SESSIONS {
+            let Some(oldest) = sessions
+                .iter()
+                .filter(|(candidate, _)| *candidate != key)
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(candidate, _)| candidate.clone())
+            else {
+                break;
+            };
+            sessions.remove(&oldest);
+        }
+        drop(sessions);
+        slot
+    }
+}
+
+/// Config for `key`, with the same overrides `handle_run` applies for the
+/// forwarded flags, and the directory of the file it came from
+async fn load_config(key: &SessionKey) -> Result<(Config, Option<PathBuf>)> {
+    let mut loader = ConfigLoader::new();
+    let mut config = loader.load_for_project(&key.cwd).await?;
+    if let Some(theme) = &key.theme {
+        config.theme.clone_from(theme);
+    }
+    if let Some(preset) = &key.preset {
+        config.preset = Some(preset.clone());
+    }
+    key.overrides.apply(&mut config);
+
+    let base_dir = loader
+        .get_config_source()
+        .and_then(|source| source.path.as_ref())
+        .and_then(|path| path.parent().map(Path::to_path_buf));
+    Ok((config, base_dir))
+}
+
+fn config_stamps(cwd: &Path) -> Vec<Option<(u64, SystemTime)>> {
+    let user = ConfigLoader::new().user_config_path();
+    let project = ConfigLoader::project_config_path_for_path(&cwd.to_string_lossy());
+    user.into_iter()
+        .chain(std::iter::once(project))
+        .map(|path| {
+            let meta = fs::metadata(path).ok()?;
+            Some((meta.len(), meta.modified().ok()?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use serial_test::serial;
+    use tempfile::tempdir;
+
+    async fn render_in_process(
+        cwd: &Path,
+        overrides: RuntimeOverrides,
+        raw_input: &str,
+    ) -> Result<String> {
+        let mut config = ConfigLoader::new().load_for_project(cwd).await?;
+        config.theme = "powerline".to_string();
+        config.preset = Some("PMT".to_string());
+        overrides.apply(&mut config);
+
+        let options = GeneratorOptions {
+            update_throttling: false,
+            ..GeneratorOptions::default()
+        }
+        .with_preset("PMT".to_string());
+        let mut generator = StatuslineGenerator::new(config, options);
+        generator.set_terminal_width(Some(200));
+        generator
+            .generate_formatted(InputData::parse(raw_input)?, OutputFormat::Ansi)
+            .await
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_forwarded_env_renders_like_in_process() -> Result<()> {
+        let storage = tempdir()?;
+        let cwd = tempdir()?;
+        std::env::set_var("STATUSLINE_STORAGE_PATH", storage.path());
+        std::env::set_var("COLORTERM", "truecolor");
+        std::env::remove_var("NO_COLOR");
+
+        let raw_input = json!({
+            "model": { "id": "claude-sonnet-4", "display_name": "Sonnet" },
+            "workspace": { "current_dir": cwd.path(), "project_dir": cwd.path() }
+        })
+        .to_string();
+        let request = |overrides, env| DaemonRequest {
+            cwd: Some(cwd.path().to_path_buf()),
+            preset: Some("PMT".to_string()),
+            theme: Some("powerline".to_string()),
+            overrides,
+            output: "ansi".to_string(),
+            columns: Some(200),
+            env: Some(env),
+            input: raw_input.clone(),
+        };
+
+        let daemon = Daemon::default();
+        for overrides in [
+            RuntimeOverrides::default(),
+            RuntimeOverrides {
+                no_colors: true,
+                force_text: true,
+                ..RuntimeOverrides::default()
+            },
+        ] {
+            let expected = Box::pin(render_in_process(cwd.path(), overrides, &raw_input)).await?;
+            let served = daemon
+                .handle(request(overrides, ClientEnv::capture()))
+                .await?;
+            assert_eq!(served, expected);
+        }
+
+        // 客户端的 NO_COLOR 生效,daemon 自己的环境不参与
+        let mut env = ClientEnv::capture();
+        env.insert("NO_COLOR".to_string(), "1".to_string());
+        let served = daemon
+            .handle(request(RuntimeOverrides::default(), env))
+            .await?;
+        assert!(!served.contains("\x1b[38"));
+
+        std::env::remove_var("STATUSLINE_STORAGE_PATH");
+        std::env::remove_var("COLORTERM");
+        Ok(())
+    }
+
+    #[test]
+    fn test_bind_restricts_socket_before_publishing() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let socket = temp.path().join("daemon.sock");
+        let _listener = bind(&socket)?;
+
+        let mode = fs::metadata(&socket)?.permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(UnixStream::connect(&socket).is_ok());
+        // 临时 bind 目录不会留下
+        assert_eq!(fs::read_dir(temp.path())?.count(), 1);
+        assert!(bind(&socket).is_err());
+        Ok(())
+    }
+
+    fn key(name: &str) -> SessionKey {
+        SessionKey {
+            cwd: PathBuf::from(name),
+            preset: None,
+            theme: None,
+            overrides: RuntimeOverrides::default(),
+        }
+    }
+
+    #[test]
+    fn test_sessions_evict_least_recently_used() {
+        let daemon = Daemon::default();
+        for index in 0..MAX_SESSIONS {
+            daemon.checkout(&key(&index.to_string()));
+        }
+        // 重新用过的会话不会被挤掉
+        std::thread::sleep(Duration::from_millis(1));
+        daemon.checkout(&key("0"));
+        daemon.checkout(&key("new"));
+
+        let sessions = std::mem::take(
+            &mut *daemon
+                .sessions
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        assert_eq!(sessions.len(), MAX_SESSIONS);
+        assert!(sessions.contains_key(&key("0")));
+        assert!(sessions.contains_key(&key("new")));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_busy_session_does_not_block_other_keys() -> Result<()> {
+        let storage = tempdir()?;
+        let cwd = tempdir()?;
+        std::env::set_var("STATUSLINE_STORAGE_PATH", storage.path());
+
+        let daemon = Daemon::default();
+        let busy = DaemonRequest {
+            cwd: Some(cwd.path().to_path_buf()),
+            theme: Some("classic".to_string()),
+            output: "ansi".to_string(),
+            input: "{}".to_string(),
+            ..DaemonRequest::default()
+        };
+        let other = DaemonRequest {
+            theme: Some("capsule".to_string()),
+            ..busy.clone()
+        };
+
+        // 模拟一个正在渲染的同 key 请求
+        let busy_key = SessionKey {
+            cwd: cwd.path().to_path_buf(),
+            preset: None,
+            theme: busy.theme.clone(),
+            overrides: RuntimeOverrides::default(),
+        };
+        let slot = daemon.checkout(&busy_key);
+        let guard = slot.lock().await;
+
+        let rendered = tokio::time::timeout(Duration::from_secs(5), daemon.handle(other)).await;
+        assert!(matches!(rendered, Ok(Ok(_))));
+        let blocked =
+            tokio::time::timeout(Duration::from_millis(50), daemon.handle(busy.clone())).await;
+        assert!(blocked.is_err());
+
+        drop(guard);
+        daemon.handle(busy).await?;
+        std::env::remove_var("STATUSLINE_STORAGE_PATH");
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    #[serial]
+    async fn test_concurrent_projects_keep_their_own_snapshots() -> Result<()> {
+        let storage = tempdir()?;
+        std::env::set_var("STATUSLINE_STORAGE_PATH", storage.path());
+        let projects = storage.path().join("projects");
+
+        let daemon = Arc::new(Daemon::default());
+        let request = |project: &str, round: usize| {
+            let cwd = storage.path().join(project);
+            let session_id = format!("{project}-{round}");
+            let transcript = projects.join(project).join(format!("{session_id}.jsonl"));
+            DaemonRequest {
+                cwd: Some(cwd.clone()),
+                output: "ansi".to_string(),
+                input: json!({
+                    "session_id": session_id,
+                    "transcript_path": transcript,
+                    "workspace": { "current_dir": cwd, "project_dir": cwd }
+                })
+                .to_string(),
+                ..DaemonRequest::default()
+            }
+        };
+
+        for round in 0..5 {
+            let (first, second) = tokio::join!(
+                tokio::spawn({
+                    let daemon = Arc::clone(&daemon);
+                    let request = request("-proj-a", round);
+                    async move { daemon.handle(request).await }
+                }),
+                tokio::spawn({
+                    let daemon = Arc::clone(&daemon);
+                    let request = request("-proj-b", round);
+                    async move { daemon.handle(request).await }
+                }),
+            );
+            first??;
+            second??;
+        }
+
+        for (own, other) in [("-proj-a", "-proj-b"), ("-proj-b", "-proj-a")] {
+            for round in 0..5 {
+                let file = format!("{own}-{round}.json");
+                let sessions = |project: &str| {
+                    projects
+                        .join(project)
+                        .join("statusline-pro")
+                        .join("sessions")
+                        .join(&file)
+                };
+                assert!(sessions(own).exists(), "{file} missing under {own}");
+                assert!(!sessions(other).exists(), "{file} leaked into {other}");
+            }
+        }
+
+        std::env::remove_var("STATUSLINE_STORAGE_PATH");
+        Ok(())
+    }
+}
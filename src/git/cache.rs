@@ -0,0 +1,304 @@
+//! Cross-process cache for collected [`GitInfo`].
+//!
+//! Every statusline refresh is a fresh process, so an in-memory cache never
+//! hits. Entries are stored under the statusline-pro cache dir, keyed by the
+//! repository workdir and collection options, and remember the size / mtime
+//! of the git files that change whenever branch state does (`HEAD`, `index`,
+//! the current, upstream and diff base refs, `packed-refs`, in-progress
+//! operations, including the current rebase step).
+//! An entry is reused only while all of them are unchanged and it is younger
+//! than the configured TTL; the TTL bounds how long plain working-tree edits,
+//! which touch none of those files, can go unnoticed.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::service::{GitCollectionOptions, GitService};
+use super::types::GitInfo;
+use crate::storage;
+
+/// Per-worktree files whose change invalidates an entry
+const GIT_DIR_FILES: &[&str] = &[
+    "HEAD",
+    "index",
+    "MERGE_HEAD",
+    "CHERRY_PICK_HEAD",
+    "REVERT_HEAD",
+    "BISECT_LOG",
+    "rebase-merge",
+    "rebase-apply",
+    // 推进 rebase 步骤只改写这两个文件,目录 mtime 不变
+    "rebase-merge/msgnum",
+    "rebase-apply/next",
+];
+
+/// Shared files whose change invalidates an entry
+const COMMON_DIR_FILES: &[&str] = &["packed-refs", "FETCH_HEAD", "refs/stash"];
+
+/// Entries not rewritten for this long belong to repositories no longer in use
+const ENTRY_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Size and mtime of one watched path (`None` when it does not exist)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    path: PathBuf,
+    stamp: Option<(u64, u64)>,
+}
+
+impl FileStamp {
+    fn read(path: PathBuf) -> Self {
+        let stamp = fs::metadata(&path).ok().map(|meta| {
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |elapsed| {
+                    u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX)
+                });
+            (meta.len(), modified)
+        });
+        Self { path, stamp }
+    }
+
+    fn is_current(&self) -> bool {
+        Self::read(self.path.clone()) == *self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedGitInfo {
+    updated_at: i64,
+    watched: Vec<FileStamp>,
+    info: GitInfo,
+}
+
+/// Disk cache of [`GitInfo`] snapshots shared across statusline processes
+pub struct GitInfoCache {
+    dir: PathBuf,
+}
+
+impl Default for GitInfoCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GitInfoCache {
+    /// Cache rooted at `<storage>/statusline-pro/cache/git`
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_dir(storage::cache_dir().join("git"))
+    }
+
+    #[must_use]
+    pub const fn with_dir(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn entry_path(&self, service: &GitService, options: GitCollectionOptions) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        service.workdir().hash(&mut hasher);
+        service.diff_base().hash(&mut hasher);
+        options.hash(&mut hasher);
+        self.dir.join(format!("{:016x}.json", hasher.finish()))
+    }
+
+    /// Cached info for `service`, if no watched file changed and it is younger than `ttl`
+    #[must_use]
+    pub fn load(
+        &self,
+        service: &GitService,
+        options: GitCollectionOptions,
+        ttl: Duration,
+    ) -> Option<GitInfo> {
+        let content = fs::read(self.entry_path(service, options)).ok()?;
+        let entry: CachedGitInfo = serde_json::from_slice(&content).ok()?;
+
+        let age = Utc::now().timestamp_millis() - entry.updated_at;
+        let fresh = u128::try_from(age).is_ok_and(|age| age < ttl.as_millis());
+        (fresh && entry.watched.iter().all(FileStamp::is_current)).then_some(entry.info)
+    }
+
+    /// Persist `info` together with the current state of the watched files
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the cache directory or entry cannot be written.
+    pub fn store(
+        &self,
+        service: &GitService,
+        options: GitCollectionOptions,
+        info: &GitInfo,
+    ) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let entry = CachedGitInfo {
+            updated_at: Utc::now().timestamp_millis(),
+            watched: watched_files(service, info),
+            info: info.clone(),
+        };
+        let path = self.entry_path(service, options);
+        // 每个仓库 / 选项组合一个文件:新条目首次写入时顺带清理早已不用的条目
+        if !path.exists() {
+            crate::utils::prune_stale_files(&self.dir, ENTRY_RETENTION);
+        }
+        let tmp_path = path.with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&tmp_path, serde_json::to_vec(&entry)?)?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+}
+
+fn watched_files(service: &GitService, info: &GitInfo) -> Vec<FileStamp> {
+    let git_dir = service.git_dir();
+    let common_dir = service.common_dir();
+
+    let branch_ref =
+        (!info.branch.detached).then(|| common_dir.join("refs/heads").join(&info.branch.current));
+    let upstream_ref = info
+        .branch
+        .upstream
+        .as_deref()
+        .map(|upstream| common_dir.join("refs/remotes").join(upstream));
+    let diff_base_ref = info
+        .diff
+        .as_ref()
+        .and_then(|diff| diff.reference.as_deref())
+        .map(|reference| common_dir.join(reference));
+
+    GIT_DIR_FILES
+        .iter()
+        .map(|name| git_dir.join(name))
+        .chain(COMMON_DIR_FILES.iter().map(|name| common_dir.join(name)))
+        .chain(branch_ref)
+        .chain(upstream_ref)
+        .chain(diff_base_ref)
+        .map(FileStamp::read)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use git2::{Repository, Signature};
+    use std::path::Path;
+
+    fn commit_file(repo: &Repository, name: &str, content: &str) -> Result<()> {
+        let workdir = repo.workdir().context("workdir")?;
+        fs::write(workdir.join(name), content)?;
+        let mut index = repo.index()?;
+        index.add_path(Path::new(name))?;
+        index.write()?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let signature = Signature::now("tester", "tester@example.com")?;
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<_> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, name, &tree, &parents)?;
+        Ok(())
+    }
+
+    #[test]
+    fn entry_is_invalidated_by_ref_and_index_changes() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let repo_dir = temp.path().join("repo");
+        let repo = Repository::init(&repo_dir)?;
+        commit_file(&repo, "a.txt", "a")?;
+
+        let cache = GitInfoCache::with_dir(temp.path().join("cache"));
+        let options = GitCollectionOptions::default();
+        let ttl = Duration::from_secs(60);
+        let service = GitService::discover(&repo_dir)?;
+        let info = service.collect_info_with_options(&options);
+
+        assert!(cache.load(&service, options, ttl).is_none());
+        cache.store(&service, options, &info)?;
+        assert_eq!(cache.load(&service, options, ttl), Some(info));
+        assert!(cache.load(&service, options, Duration::ZERO).is_none());
+        let other_options = GitCollectionOptions {
+            include_stash: false,
+            ..options
+        };
+        assert!(cache.load(&service, other_options, ttl).is_none());
+
+        // 新提交会改写 index 与分支 ref
+        std::thread::sleep(Duration::from_millis(20));
+        commit_file(&repo, "b.txt", "b")?;
+        assert!(cache.load(&service, options, ttl).is_none());
+
+        cache.store(
+            &service,
+            options,
+            &service.collect_info_with_options(&options),
+        )?;
+        repo.set_head_detached(repo.head()?.target().context("head target")?)?;
+        assert!(cache.load(&service, options, ttl).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn entry_is_invalidated_by_rebase_step() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let repo_dir = temp.path().join("repo");
+        let repo = Repository::init(&repo_dir)?;
+        commit_file(&repo, "a.txt", "a")?;
+        let rebase_dir = repo.path().join("rebase-merge");
+        fs::create_dir_all(&rebase_dir)?;
+        fs::write(rebase_dir.join("msgnum"), "3\n")?;
+
+        let cache = GitInfoCache::with_dir(temp.path().join("cache"));
+        let options = GitCollectionOptions::default();
+        let ttl = Duration::from_secs(60);
+        let service = GitService::discover(&repo_dir)?;
+        let info = service.collect_info_with_options(&options);
+        cache.store(&service, options, &info)?;
+        assert_eq!(cache.load(&service, options, ttl), Some(info));
+
+        // 同样大小的改写,只有文件自身的 mtime 会变
+        std::thread::sleep(Duration::from_millis(20));
+        fs::write(rebase_dir.join("msgnum"), "4\n")?;
+        assert!(cache.load(&service, options, ttl).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn store_prunes_entries_past_retention() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let repo_dir = temp.path().join("repo");
+        let repo = Repository::init(&repo_dir)?;
+        commit_file(&repo, "a.txt", "a")?;
+
+        let cache_dir = temp.path().join("cache");
+        fs::create_dir_all(&cache_dir)?;
+        let stale = cache_dir.join("0000000000000000.json");
+        let recent = cache_dir.join("1111111111111111.json");
+        fs::write(&stale, "{}")?;
+        fs::write(&recent, "{}")?;
+        fs::File::options()
+            .write(true)
+            .open(&stale)?
+            .set_modified(std::time::SystemTime::now() - ENTRY_RETENTION * 2)?;
+
+        let cache = GitInfoCache::with_dir(cache_dir);
+        let options = GitCollectionOptions::default();
+        let service = GitService::discover(&repo_dir)?;
+        cache.store(
+            &service,
+            options,
+            &service.collect_info_with_options(&options),
+        )?;
+
+        assert!(!stale.exists());
+        assert!(recent.exists());
+        assert!(cache
+            .load(&service, options, Duration::from_secs(60))
+            .is_some());
+        Ok(())
+    }
+}
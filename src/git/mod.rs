@@ -1,5 +1,7 @@
+mod cache;
 mod service;
 mod types;
 
+pub use cache::GitInfoCache;
 pub use service::{GitCollectionOptions, GitService};
 pub use types::*;
@@ -1,19 +1,21 @@
 use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
-use git2::{BranchType, DescribeOptions, Repository, Status, StatusOptions};
+use git2::{BranchType, DescribeFormatOptions, DescribeOptions, Repository, Status, StatusOptions};
 
 use super::types::{
-    GitBranchInfo, GitInfo, GitOperationStatus, GitStashInfo, GitVersionInfo, GitWorkingStatus,
+    GitBranchInfo, GitDiffStat, GitInfo, GitOperationStatus, GitStashInfo, GitVersionInfo,
+    GitWorkingStatus,
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Hash)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct GitCollectionOptions {
     pub include_status: bool,
     pub include_stash: bool,
     pub include_operation: bool,
     pub include_version: bool,
+    pub include_diff: bool,
 }
 
 impl Default for GitCollectionOptions {
@@ -23,6 +25,7 @@ impl Default for GitCollectionOptions {
             include_stash: true,
             include_operation: true,
             include_version: true,
+            include_diff: true,
         }
     }
 }
@@ -32,6 +35,7 @@ pub struct GitService {
     repo: Repository,
     workdir: PathBuf,
     git_dir: PathBuf,
+    diff_base: Option<String>,
 }
 
 impl GitService {
@@ -63,9 +67,23 @@ impl GitService {
             repo,
             workdir,
             git_dir,
+            diff_base: None,
         })
     }
 
+    /// Compare the branch diff stat against `base` (e.g. `main`) instead of the upstream.
+    #[must_use]
+    pub fn with_diff_base(mut self, base: Option<String>) -> Self {
+        self.diff_base = base.filter(|base| !base.trim().is_empty());
+        self
+    }
+
+    /// Configured diff base, if any.
+    #[must_use]
+    pub fn diff_base(&self) -> Option<&str> {
+        self.diff_base.as_deref()
+    }
+
     /// Collect a snapshot of repository state.
     #[must_use]
     pub fn collect_info(&self) -> GitInfo {
@@ -79,7 +97,11 @@ impl GitService {
         let status = if options.include_status {
             self.working_status().unwrap_or_default()
         } else {
-            GitWorkingStatus::default()
+            // 没有采集的状态按干净处理,不能凭空显示脏标记
+            GitWorkingStatus {
+                clean: true,
+                ..GitWorkingStatus::default()
+            }
         };
         let stash = if options.include_stash {
             self.stash_info().unwrap_or_default()
@@ -97,6 +119,13 @@ impl GitService {
             GitVersionInfo::default()
         };
 
+        let diff = if options.include_diff {
+            self.diff_stat(branch.upstream.as_deref())
+                .unwrap_or_default()
+        } else {
+            None
+        };
+
         GitInfo {
             is_repo: true,
             branch,
@@ -104,6 +133,7 @@ impl GitService {
             stash,
             operation,
             version,
+            diff,
         }
     }
 
@@ -220,20 +250,80 @@ impl GitService {
         Ok(GitStashInfo { count })
     }
 
-    fn operation_status(&self) -> GitOperationStatus {
+    /// Detect an in-progress rebase / merge / cherry-pick / revert / bisect.
+    ///
+    /// Only inspects marker files in the git directory, so it is cheap.
+    #[must_use]
+    pub fn operation_status(&self) -> GitOperationStatus {
         let mut status = GitOperationStatus::default();
 
         let git_dir = &self.git_dir;
-        status.rebasing =
-            git_dir.join("rebase-apply").exists() || git_dir.join("rebase-merge").exists();
+        // rebase -i / --merge 用 msgnum/end,旧式 am 风格用 next/last
+        for (dir, step_file, total_file) in [
+            ("rebase-merge", "msgnum", "end"),
+            ("rebase-apply", "next", "last"),
+        ] {
+            let dir = git_dir.join(dir);
+            if !dir.exists() {
+                continue;
+            }
+            status.rebasing = true;
+            status.step = read_number(&dir.join(step_file));
+            status.total = read_number(&dir.join(total_file));
+            status.head_name = std::fs::read_to_string(dir.join("head-name"))
+                .ok()
+                .map(|name| {
+                    let name = name.trim();
+                    name.strip_prefix("refs/heads/").unwrap_or(name).to_string()
+                })
+                .filter(|name| !name.is_empty() && name != "detached HEAD");
+            break;
+        }
         status.merging = git_dir.join("MERGE_HEAD").exists();
-        status.cherry_pick =
-            git_dir.join("CHERRY_PICK_HEAD").exists() || git_dir.join("REVERT_HEAD").exists();
+        status.cherry_pick = git_dir.join("CHERRY_PICK_HEAD").exists();
+        status.reverting = git_dir.join("REVERT_HEAD").exists();
         status.bisecting = git_dir.join("BISECT_LOG").exists();
 
         status
     }
 
+    /// Files changed / insertions / deletions between HEAD and its merge-base with
+    /// the configured base, falling back to `upstream`.
+    ///
+    /// Returns `None` when there is no base to compare against.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the base cannot be resolved or the diff fails.
+    pub fn diff_stat(&self, upstream: Option<&str>) -> Result<Option<GitDiffStat>> {
+        let Some(base) = self.diff_base.as_deref().or(upstream) else {
+            return Ok(None);
+        };
+
+        let head = self.repo.head()?.peel_to_commit()?;
+        let base_commit = self.repo.revparse_single(base)?.peel_to_commit()?;
+        let merge_base = self.repo.merge_base(head.id(), base_commit.id())?;
+        let base_tree = self.repo.find_commit(merge_base)?.tree()?;
+        let diff = self
+            .repo
+            .diff_tree_to_tree(Some(&base_tree), Some(&head.tree()?), None)?;
+        let stats = diff.stats()?;
+
+        let reference = self
+            .repo
+            .resolve_reference_from_short_name(base)
+            .ok()
+            .and_then(|reference| reference.name().map(str::to_string));
+
+        Ok(Some(GitDiffStat {
+            base: base.to_string(),
+            reference,
+            files_changed: stats.files_changed(),
+            insertions: stats.insertions(),
+            deletions: stats.deletions(),
+        }))
+    }
+
     fn version_info(&self) -> Result<GitVersionInfo> {
         let head = self.repo.head()?;
         let commit = head.peel_to_commit()?;
@@ -248,12 +338,27 @@ impl GitService {
             .unwrap_or_default();
         let timestamp = commit.time().seconds();
 
-        let describe = self.repo.describe(
-            DescribeOptions::new()
-                .describe_tags()
-                .show_commit_oid_as_fallback(true),
-        );
-        let tag = describe.ok().and_then(|desc| desc.format(None).ok());
+        // abbreviated_size(0) 只输出标签名,距离单独计算
+        let tag = self
+            .repo
+            .describe(DescribeOptions::new().describe_tags())
+            .ok()
+            .and_then(|desc| {
+                desc.format(Some(DescribeFormatOptions::new().abbreviated_size(0)))
+                    .ok()
+            });
+        let tag_distance = tag.as_deref().and_then(|name| {
+            let tagged = self
+                .repo
+                .revparse_single(&format!("refs/tags/{name}"))
+                .ok()?
+                .peel_to_commit()
+                .ok()?;
+            self.repo
+                .graph_ahead_behind(commit.id(), tagged.id())
+                .ok()
+                .map(|(ahead, _)| ahead)
+        });
 
         Ok(GitVersionInfo {
             commit_id,
@@ -262,6 +367,7 @@ impl GitService {
             author,
             timestamp,
             tag,
+            tag_distance,
         })
     }
 
@@ -270,4 +376,20 @@ impl GitService {
     pub fn workdir(&self) -> &Path {
         &self.workdir
     }
+
+    /// Per-worktree git directory (`HEAD`, `index`, in-progress operations).
+    #[must_use]
+    pub fn git_dir(&self) -> &Path {
+        &self.git_dir
+    }
+
+    /// Git directory shared by all worktrees (refs, `packed-refs`, `FETCH_HEAD`).
+    #[must_use]
+    pub fn common_dir(&self) -> &Path {
+        self.repo.commondir()
+    }
+}
+
+fn read_number(path: &Path) -> Option<usize> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
 }
@@ -1,6 +1,8 @@
 use std::default::Default;
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct GitBranchInfo {
     pub current: String,
     pub upstream: Option<String>,
@@ -9,7 +11,7 @@ pub struct GitBranchInfo {
     pub behind: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct GitWorkingStatus {
     pub clean: bool,
     pub staged: usize,
@@ -18,31 +20,103 @@ pub struct GitWorkingStatus {
     pub conflicted: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct GitStashInfo {
     pub count: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct GitOperationStatus {
     pub rebasing: bool,
     pub merging: bool,
     pub cherry_pick: bool,
+    #[serde(default)]
+    pub reverting: bool,
     pub bisecting: bool,
+    /// Current rebase step (1-based)
+    #[serde(default)]
+    pub step: Option<usize>,
+    /// Total rebase steps
+    #[serde(default)]
+    pub total: Option<usize>,
+    /// Branch being rebased (HEAD is detached meanwhile)
+    #[serde(default)]
+    pub head_name: Option<String>,
+}
+
+impl GitOperationStatus {
+    #[must_use]
+    pub const fn is_active(&self) -> bool {
+        self.rebasing || self.merging || self.cherry_pick || self.reverting || self.bisecting
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct GitVersionInfo {
     pub commit_id: String,
     pub short_commit_id: String,
     pub message: String,
     pub author: String,
     pub timestamp: i64,
+    /// Nearest tag reachable from HEAD
     pub tag: Option<String>,
+    /// Commits between the nearest tag and HEAD
+    #[serde(default)]
+    pub tag_distance: Option<usize>,
+}
+
+impl GitVersionInfo {
+    /// Nearest tag with the commits since it, e.g. `v1.2.0+3` (`v1.2.0` when HEAD is tagged)
+    #[must_use]
+    pub fn tag_label(&self) -> Option<String> {
+        let tag = self.tag.as_deref()?;
+        Some(match self.tag_distance {
+            Some(distance) if distance > 0 => format!("{tag}+{distance}"),
+            _ => tag.to_string(),
+        })
+    }
+
+    /// Age of the last commit relative to `now` (unix seconds), e.g. `3h ago`
+    #[must_use]
+    pub fn relative_age(&self, now: i64) -> Option<String> {
+        if self.commit_id.is_empty() {
+            return None;
+        }
+        let elapsed = (now - self.timestamp).max(0);
+        let label = match elapsed {
+            0..60 => return Some("just now".to_string()),
+            60..3_600 => format!("{}m", elapsed / 60),
+            3_600..86_400 => format!("{}h", elapsed / 3_600),
+            86_400..2_592_000 => format!("{}d", elapsed / 86_400),
+            2_592_000..31_536_000 => format!("{}mo", elapsed / 2_592_000),
+            _ => format!("{}y", elapsed / 31_536_000),
+        };
+        Some(format!("{label} ago"))
+    }
+}
+
+/// Size of the current branch relative to its merge-base with a base branch
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct GitDiffStat {
+    /// Base the diff was taken against (configured base or upstream)
+    pub base: String,
+    /// Full ref name of the base, when it resolved to a reference
+    pub reference: Option<String>,
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl GitDiffStat {
+    /// Changed lines in total
+    #[must_use]
+    pub const fn lines_changed(&self) -> usize {
+        self.insertions + self.deletions
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct GitInfo {
     pub is_repo: bool,
     pub branch: GitBranchInfo,
@@ -50,4 +124,6 @@ pub struct GitInfo {
     pub stash: GitStashInfo,
     pub operation: GitOperationStatus,
     pub version: GitVersionInfo,
+    #[serde(default)]
+    pub diff: Option<GitDiffStat>,
 }
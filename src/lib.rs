@@ -15,6 +15,8 @@
 pub mod components;
 pub mod config;
 pub mod core;
+#[cfg(unix)]
+pub mod daemon;
 pub mod git;
 pub mod storage;
 pub mod terminal;
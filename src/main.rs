@@ -10,11 +10,21 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, bail, Context, Result};
 use clap::{Args as ClapArgs, Parser, Subcommand};
+#[cfg(unix)]
+use claude_code_statusline_pro::daemon::{self, DaemonRequest};
+#[cfg(unix)]
+use claude_code_statusline_pro::utils::client_env::ClientEnv;
 use claude_code_statusline_pro::{
     config::{
-        AutoDetect, ConfigLoader, ConfigSourceType, CreateConfigOptions, TerminalCapabilityHint,
+        AutoDetect, ConfigLoader, ConfigSourceType, CreateConfigOptions, RuntimeOverrides,
+        TerminalCapabilityHint,
     },
-    core::{GeneratorOptions, InputData, StatuslineGenerator},
+    core::{api_cache, GeneratorOptions, InputData, OutputFormat, StatuslineGenerator},
+    storage::{
+        self,
+        report::{CostReport, ReportFormat, ReportGroupBy},
+    },
+    terminal::detector::TerminalDetector,
 };
 use dialoguer::Confirm;
 use toml_edit::{Array, DocumentMut, Item, Table, Value as TomlEditValue};
@@ -75,6 +85,10 @@ struct Cli {
     #[arg(long = "mock")]
     mock: Option<String>,
 
+    /// 输出格式: ansi（默认）/ json（组件列表、多行内容与耗时）/ tmux / zsh / bash
+    #[arg(short = 'o', long = "output", default_value = "ansi")]
+    output: OutputFormat,
+
     /// 子命令
     #[command(subcommand)]
     command: Option<Commands>,
@@ -90,6 +104,14 @@ enum Commands {
     Validate { file: Option<String> },
     /// 环境诊断
     Doctor,
+    /// 汇总所有项目会话的成本报告
+    Report(ReportArgs),
+    /// 常驻后台，通过 Unix socket 为默认调用渲染状态行
+    #[cfg(unix)]
+    Daemon(DaemonArgs),
+    /// 后台刷新 API widget 缓存（内部使用）
+    #[command(name = api_cache::REFRESH_SUBCOMMAND, hide = true)]
+    RefreshWidgetCache,
 }
 
 #[derive(ClapArgs, Debug, Default)]
@@ -158,6 +180,33 @@ struct ConfigSetArgs {
     value_parts: Vec<String>,
 }
 
+#[derive(ClapArgs, Debug, Default)]
+struct ReportArgs {
+    /// 起始日期（含），格式 YYYY-MM-DD
+    #[arg(long)]
+    since: Option<chrono::NaiveDate>,
+
+    /// 截止日期（含），格式 YYYY-MM-DD
+    #[arg(long)]
+    until: Option<chrono::NaiveDate>,
+
+    /// 分组维度（day / project / model）
+    #[arg(short = 'g', long = "group-by", default_value = "day")]
+    group_by: ReportGroupBy,
+
+    /// 输出格式（table / json / csv）
+    #[arg(short = 'f', long = "format", default_value = "table")]
+    format: ReportFormat,
+}
+
+#[cfg(unix)]
+#[derive(ClapArgs, Debug, Default)]
+struct DaemonArgs {
+    /// socket 路径（默认取 STATUSLINE_DAEMON_SOCKET，否则 <storage>/statusline-pro/cache/daemon.sock）
+    #[arg(long)]
+    socket: Option<PathBuf>,
+}
+
 #[derive(ClapArgs, Debug, Default)]
 struct ThemeArgs {
     /// 要应用的主题名称（classic / powerline / capsule）
@@ -187,15 +236,30 @@ struct ConfigInitArgs {
     force: bool,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+fn main() -> Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    let result = runtime.block_on(run(Cli::parse()));
+    // 超时被放弃的组件可能仍占着 spawn_blocking 线程,输出已经打印,不再等它们
+    runtime.shutdown_background();
+    result
+}
 
+async fn run(cli: Cli) -> Result<()> {
     match &cli.command {
         Some(Commands::Config(args)) => handle_config(args).await?,
         Some(Commands::Theme(args)) => handle_theme(args).await?,
         Some(Commands::Validate { file }) => handle_validate(file.as_deref()).await?,
         Some(Commands::Doctor) => handle_doctor().await?,
+        Some(Commands::Report(args)) => handle_report(args)?,
+        #[cfg(unix)]
+        Some(Commands::Daemon(args)) => {
+            let socket = args.socket.clone().unwrap_or_else(daemon::socket_path);
+            daemon::serve(&socket).await?;
+        }
+        Some(Commands::RefreshWidgetCache) => {
+            tokio::task::spawn_blocking(|| api_cache::run_refresh(std::io::stdin().lock()))
+                .await??;
+        }
         None => handle_run(&cli).await?,
     }
 
@@ -213,6 +277,21 @@ async fn handle_run(cli: &Cli) -> Result<()> {
         eprintln!("  - debug: {}", cli.debug);
     }
 
+    let raw_input = if cli.mock.is_some() {
+        None
+    } else {
+        Some(InputData::read_stdin()?)
+    };
+
+    #[cfg(unix)]
+    if let Some(statusline) = raw_input
+        .as_deref()
+        .and_then(|raw| render_via_daemon(cli, raw))
+    {
+        println!("{statusline}");
+        return Ok(());
+    }
+
     let mut loader = ConfigLoader::new();
     let mut config = loader.load(cli.config.as_deref()).await?;
 
@@ -247,7 +326,7 @@ async fn handle_run(cli: &Cli) -> Result<()> {
         config.preset = Some(preset.clone());
     }
 
-    apply_runtime_overrides(cli, &mut config);
+    runtime_overrides(cli).apply(&mut config);
 
     let base_dir = loader
         .get_config_source()
@@ -274,7 +353,7 @@ async fn handle_run(cli: &Cli) -> Result<()> {
             ))
         })?
     } else {
-        InputData::from_stdin()?
+        InputData::parse(raw_input.as_deref().unwrap_or_default())?
     };
 
     if config.debug {
@@ -286,11 +365,56 @@ async fn handle_run(cli: &Cli) -> Result<()> {
         }
     }
 
-    let statusline = generator.generate(input).await?;
+    let statusline = generator.generate_formatted(input, cli.output).await?;
     println!("{statusline}");
     Ok(())
 }
 
+/// 默认调用优先交给 daemon 渲染；daemon 不在或失败时返回 None，回退进程内渲染
+#[cfg(unix)]
+fn render_via_daemon(cli: &Cli, raw_input: &str) -> Option<String> {
+    // 自定义配置文件和调试输出只作用于本次进程
+    if cli.config.is_some() || cli.debug {
+        return None;
+    }
+
+    let request = DaemonRequest {
+        cwd: std::env::current_dir().ok(),
+        preset: cli.preset_override.clone().or_else(|| cli.preset.clone()),
+        theme: cli.theme.clone(),
+        overrides: runtime_overrides(cli),
+        output: cli.output.as_str().to_string(),
+        columns: TerminalDetector::new().detect_width(0),
+        env: Some(ClientEnv::capture()),
+        input: raw_input.to_string(),
+    };
+    daemon::request(&daemon::socket_path(), &request).ok()
+}
+
+fn runtime_overrides(cli: &Cli) -> RuntimeOverrides {
+    RuntimeOverrides {
+        no_colors: cli.no_colors,
+        no_emoji: cli.no_emoji,
+        no_icons: cli.no_icons,
+        force_emoji: cli.force_emoji,
+        force_nerd_font: cli.force_nerd_font,
+        force_text: cli.force_text,
+    }
+}
+
+fn handle_report(args: &ReportArgs) -> Result<()> {
+    if let (Some(since), Some(until)) = (args.since, args.until) {
+        if since > until {
+            bail!("--since ({since}) 不能晚于 --until ({until})");
+        }
+    }
+
+    let records = storage::report::scan_sessions(&storage::base_dir());
+    let report = CostReport::build(&records, args.group_by, args.since, args.until);
+    print!("{}", report.render(args.format)?);
+    Ok(())
+}
+
 async fn handle_config(args: &ConfigArgs) -> Result<()> {
     let mut loader = ConfigLoader::new();
 
@@ -680,32 +804,6 @@ async fn handle_doctor() -> Result<()> {
     Ok(())
 }
 
-fn apply_runtime_overrides(cli: &Cli, config: &mut claude_code_statusline_pro::config::Config) {
-    if cli.no_colors {
-        config.style.enable_colors = AutoDetect::Bool(false);
-    }
-    if cli.no_emoji {
-        config.style.enable_emoji = AutoDetect::Bool(false);
-        config.terminal.force_emoji = false;
-    }
-    if cli.no_icons {
-        config.style.enable_nerd_font = AutoDetect::Bool(false);
-        config.terminal.force_nerd_font = false;
-    }
-
-    if cli.force_emoji {
-        config.terminal.force_emoji = true;
-    }
-    if cli.force_nerd_font {
-        config.terminal.force_nerd_font = true;
-    }
-    if cli.force_text {
-        config.terminal.force_text = true;
-        config.terminal.force_emoji = false;
-        config.terminal.force_nerd_font = false;
-    }
-}
-
 fn bool_icon(value: bool) -> &'static str {
     if value {
         "✅"
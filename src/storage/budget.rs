@@ -0,0 +1,266 @@
+//! Period budget aggregation
+//!
+//! Sums the cost of every stored session inside the current day / week /
+//! month. The cross-session total is cached under the shared cache dir so the
+//! statusline does not rescan every snapshot on each render; the current
+//! session is always added live.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Days, FixedOffset, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::report::load_snapshots;
+use super::types::SessionSnapshot;
+use crate::config::BudgetPeriod;
+
+/// Start (as UTC) of the period containing `now`, evaluated in `timezone`.
+///
+/// `timezone` accepts `local`, `utc` or a fixed offset such as `+08:00`;
+/// anything else falls back to the local timezone.
+#[must_use]
+pub fn period_start(period: BudgetPeriod, timezone: &str, now: DateTime<Utc>) -> DateTime<Utc> {
+    let start = match timezone.trim().to_ascii_lowercase().as_str() {
+        "utc" | "z" => period_start_in(&Utc, period, now),
+        "local" | "" => period_start_in(&Local, period, now),
+        other => other.parse::<FixedOffset>().map_or_else(
+            |_| {
+                eprintln!("[budget] unknown timezone '{timezone}', using local time");
+                period_start_in(&Local, period, now)
+            },
+            |offset| period_start_in(&offset, period, now),
+        ),
+    };
+    start.unwrap_or(now)
+}
+
+fn period_start_in<Tz: TimeZone>(
+    tz: &Tz,
+    period: BudgetPeriod,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let today = now.with_timezone(tz).date_naive();
+    let first_day = match period {
+        BudgetPeriod::Day => today,
+        BudgetPeriod::Week => {
+            today.checked_sub_days(Days::new(u64::from(today.weekday().num_days_from_monday())))?
+        }
+        BudgetPeriod::Month => NaiveDate::from_ymd_opt(today.year(), today.month(), 1)?,
+    };
+
+    // 夏令时切换恰好落在午夜时本地 00:00 不存在,顺延到第一个有效时刻
+    (0..3).find_map(|hour| {
+        tz.from_local_datetime(&first_day.and_hms_opt(hour, 0, 0)?)
+            .earliest()
+            .map(|start| start.with_timezone(&Utc))
+    })
+}
+
+/// Per-session spend since `since`, shared by all statusline processes.
+#[derive(Debug, Serialize, Deserialize)]
+struct SpendCache {
+    since: DateTime<Utc>,
+    computed_at: DateTime<Utc>,
+    sessions: BTreeMap<String, f64>,
+}
+
+/// Total cost since `since` across all sessions stored below `base_dir`.
+///
+/// `current` replaces whatever the cache holds for its own session so the
+/// running session is never stale.
+#[must_use]
+pub fn period_spend(
+    base_dir: &Path,
+    cache_path: &Path,
+    since: DateTime<Utc>,
+    current: Option<&SessionSnapshot>,
+    ttl: Duration,
+) -> f64 {
+    let sessions = load_cache(cache_path, since, ttl).unwrap_or_else(|| {
+        let sessions = scan_spend(base_dir, since);
+        store_cache(cache_path, since, &sessions);
+        sessions
+    });
+
+    let current_id = current.map(|snapshot| snapshot.meta.session_id.as_str());
+    let others: f64 = sessions
+        .iter()
+        .filter(|(id, _)| Some(id.as_str()) != current_id)
+        .map(|(_, cost)| cost)
+        .sum();
+
+    others
+        + current.map_or(0.0, |snapshot| {
+            snapshot
+                .history
+                .cost
+                .cost_since(since, snapshot.meta.last_update_time)
+        })
+}
+
+fn scan_spend(base_dir: &Path, since: DateTime<Utc>) -> BTreeMap<String, f64> {
+    let mut sessions = BTreeMap::new();
+    for (_, snapshot) in load_snapshots(base_dir) {
+        let cost = snapshot
+            .history
+            .cost
+            .cost_since(since, snapshot.meta.last_update_time);
+        if cost > 0.0 {
+            *sessions.entry(snapshot.meta.session_id).or_default() += cost;
+        }
+    }
+    sessions
+}
+
+fn load_cache(path: &Path, since: DateTime<Utc>, ttl: Duration) -> Option<BTreeMap<String, f64>> {
+    let cache: SpendCache = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+    let age = Utc::now()
+        .signed_duration_since(cache.computed_at)
+        .to_std()
+        .ok()?;
+    (cache.since == since && age < ttl).then_some(cache.sessions)
+}
+
+fn store_cache(path: &Path, since: DateTime<Utc>, sessions: &BTreeMap<String, f64>) {
+    let cache = SpendCache {
+        since,
+        computed_at: Utc::now(),
+        sessions: sessions.clone(),
+    };
+    let result = (|| -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp_path = path.with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&tmp_path, serde_json::to_vec(&cache)?)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    })();
+    if let Err(err) = result {
+        eprintln!("[budget] failed to write spend cache: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::types::CostMetrics;
+    use anyhow::{Context, Result};
+
+    fn utc(text: &str) -> Result<DateTime<Utc>> {
+        Ok(DateTime::parse_from_rfc3339(text)?.with_timezone(&Utc))
+    }
+
+    fn snapshot_with_costs(id: &str, costs: &[(&str, f64)]) -> Result<SessionSnapshot> {
+        let mut snapshot = SessionSnapshot::new(id);
+        let mut total = 0.0;
+        for (time, cost) in costs {
+            total += cost;
+            let metrics = CostMetrics {
+                total_cost_usd: total,
+                ..CostMetrics::default()
+            };
+            snapshot.history.cost.apply_at(&metrics, utc(time)?);
+        }
+        snapshot.meta.last_update_time = Some(Utc::now());
+        Ok(snapshot)
+    }
+
+    fn write_snapshot(base: &Path, snapshot: &SessionSnapshot) -> Result<()> {
+        let dir = base
+            .join("projects")
+            .join("demo")
+            .join("statusline-pro")
+            .join("sessions");
+        fs::create_dir_all(&dir)?;
+        fs::write(
+            dir.join(format!("{}.json", snapshot.meta.session_id)),
+            serde_json::to_vec(snapshot)?,
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn period_start_respects_timezone() -> Result<()> {
+        // 周三 2026-03-04 01:30 UTC,东八区已是当天 09:30
+        let now = utc("2026-03-04T01:30:00Z")?;
+
+        assert_eq!(
+            period_start(BudgetPeriod::Day, "utc", now),
+            utc("2026-03-04T00:00:00Z")?
+        );
+        assert_eq!(
+            period_start(BudgetPeriod::Day, "-05:00", now),
+            utc("2026-03-03T05:00:00Z")?
+        );
+        assert_eq!(
+            period_start(BudgetPeriod::Week, "+08:00", now),
+            utc("2026-03-01T16:00:00Z")?
+        );
+        assert_eq!(
+            period_start(BudgetPeriod::Month, "utc", now),
+            utc("2026-03-01T00:00:00Z")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cost_since_uses_hourly_buckets() -> Result<()> {
+        let snapshot = snapshot_with_costs(
+            "s1",
+            &[("2026-03-03T22:10:00Z", 1.5), ("2026-03-04T00:20:00Z", 0.5)],
+        )?;
+
+        let cost = &snapshot.history.cost;
+        assert!((cost.total.total_cost_usd - 2.0).abs() < 1e-9);
+        let today = cost.cost_since(utc("2026-03-04T00:00:00Z")?, None);
+        assert!((today - 0.5).abs() < 1e-9);
+        let this_month = cost.cost_since(utc("2026-03-01T00:00:00Z")?, None);
+        assert!((this_month - 2.0).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn period_spend_adds_live_session_to_cached_total() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let base = temp.path();
+        let cache_path = base.join("cache").join("budget.json");
+        let since = utc("2026-03-04T00:00:00Z")?;
+
+        let other = snapshot_with_costs("other", &[("2026-03-04T03:00:00Z", 2.0)])?;
+        let mut current = snapshot_with_costs("current", &[("2026-03-04T04:00:00Z", 1.0)])?;
+        write_snapshot(base, &other)?;
+        write_snapshot(base, &current)?;
+
+        let ttl = Duration::from_secs(60);
+        let spent = period_spend(base, &cache_path, since, Some(&current), ttl);
+        assert!((spent - 3.0).abs() < 1e-9);
+        assert!(cache_path.exists());
+
+        // 缓存命中:其他会话的新增不可见,当前会话实时累加
+        let other = snapshot_with_costs(
+            "other",
+            &[("2026-03-04T03:00:00Z", 2.0), ("2026-03-04T05:00:00Z", 4.0)],
+        )?;
+        write_snapshot(base, &other)?;
+        let metrics = CostMetrics {
+            total_cost_usd: 1.25,
+            ..CostMetrics::default()
+        };
+        current
+            .history
+            .cost
+            .apply_at(&metrics, utc("2026-03-04T05:00:00Z")?);
+        let spent = period_spend(base, &cache_path, since, Some(&current), ttl);
+        assert!((spent - 3.25).abs() < 1e-9);
+
+        let spent = period_spend(base, &cache_path, since, None, Duration::ZERO);
+        let sessions = load_cache(&cache_path, since, ttl).context("cache rewritten")?;
+        assert_eq!(sessions.len(), 2);
+        assert!((spent - 7.0).abs() < 1e-9);
+        Ok(())
+    }
+}
@@ -2,9 +2,8 @@
 //!
 //! 存储管理器 - 负责会话快照与增量指标的持久化。
 
-use std::fs::{self, File};
-use std::io::{BufRead, BufReader, Seek, SeekFrom};
-use std::path::{Path, PathBuf};
+use std::fs;
+use std::path::PathBuf;
 
 use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
@@ -13,9 +12,9 @@ use serde_json::Value;
 use tokio::fs as async_fs;
 
 use super::project_resolver::ProjectResolver;
+use super::transcript;
 use super::types::{
     CostMetrics, ModelUsageEntry, SessionHistory, SessionSnapshot, StorageConfig, StoragePaths,
-    TokenHistory,
 };
 use super::{current_runtime_config, current_runtime_project_id, set_runtime_project_id};
 use crate::utils;
@@ -198,10 +197,13 @@ impl StorageManager {
             .map(|p| p.to_string_lossy().to_string())
     }
 
+    /// Record the active model and charge it `cost_delta`, the cost added
+    /// by this update
     fn update_model_usage(
         history: &mut SessionHistory,
         model: Option<&Value>,
         timestamp: Option<&str>,
+        cost_delta: f64,
     ) {
         let Some(model) = model else {
             return;
@@ -228,11 +230,13 @@ impl StorageManager {
             if timestamp.is_some() {
                 entry.last_used_at = timestamp;
             }
+            entry.cost_usd += cost_delta;
         } else {
             history.model_usage.push(ModelUsageEntry {
                 id: id.to_string(),
                 display_name,
                 last_used_at: timestamp,
+                cost_usd: cost_delta,
             });
         }
     }
@@ -241,54 +245,20 @@ impl StorageManager {
         snapshot: &mut SessionSnapshot,
         transcript_path: &str,
     ) -> Result<()> {
-        let path = Path::new(transcript_path);
-        if !path.exists() {
-            snapshot.transcript_state.transcript_path = Some(transcript_path.to_string());
-            return Ok(());
-        }
-
-        let metadata = fs::metadata(path)
-            .with_context(|| format!("Failed to read transcript metadata: {transcript_path}"))?;
-        let file_len = metadata.len();
-
-        let mut offset = snapshot.transcript_state.processed_offset;
-        let needs_reset = snapshot.transcript_state.transcript_path.as_deref()
-            != Some(transcript_path)
-            || offset > file_len;
-
-        let mut processed_messages = if needs_reset {
-            0
-        } else {
-            snapshot.transcript_state.processed_messages
-        };
-
-        if needs_reset {
-            offset = 0;
-        }
+        // 旧版本的状态会从头重读,token 历史也跟着重建,避免重复采样
+        let mut tokens = snapshot
+            .history
+            .tokens
+            .take()
+            .filter(|_| snapshot.transcript_state.is_current());
+        let result =
+            transcript::advance(&mut snapshot.transcript_state, transcript_path, |entry| {
+                entry.fold_tokens(&mut tokens);
+            });
+        snapshot.history.tokens = tokens;
+        result?;
 
-        let mut file = File::open(path)
-            .with_context(|| format!("Failed to open transcript: {transcript_path}"))?;
-        file.seek(SeekFrom::Start(offset))
-            .with_context(|| format!("Failed to seek transcript: {transcript_path}"))?;
-        let mut reader = BufReader::new(file);
-
-        let mut buffer = String::new();
-        let mut current_offset = offset;
-        let mut latest_tokens = snapshot.history.tokens.clone();
-        Self::process_transcript_stream(
-            &mut reader,
-            transcript_path,
-            &mut buffer,
-            &mut current_offset,
-            &mut processed_messages,
-            &mut latest_tokens,
-        )?;
-
-        snapshot.transcript_state.transcript_path = Some(transcript_path.to_string());
-        snapshot.transcript_state.processed_offset = current_offset;
-        snapshot.transcript_state.processed_messages = processed_messages;
-
-        if let Some(tokens) = latest_tokens {
+        if let Some(tokens) = &snapshot.history.tokens {
             snapshot
                 .transcript_state
                 .last_message_uuid
@@ -297,121 +267,11 @@ impl StorageManager {
                 .transcript_state
                 .last_timestamp
                 .clone_from(&tokens.last_timestamp);
-            snapshot.history.tokens = Some(tokens);
         }
 
         Ok(())
     }
 
-    fn process_transcript_stream(
-        reader: &mut BufReader<File>,
-        transcript_path: &str,
-        buffer: &mut String,
-        current_offset: &mut u64,
-        processed_messages: &mut u64,
-        latest_tokens: &mut Option<TokenHistory>,
-    ) -> Result<()> {
-        loop {
-            buffer.clear();
-            let bytes_read = reader
-                .read_line(buffer)
-                .with_context(|| format!("Failed to read transcript line: {transcript_path}"))?;
-            if bytes_read == 0 {
-                break;
-            }
-
-            *current_offset += bytes_read as u64;
-
-            let trimmed = buffer.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-
-            *processed_messages += 1;
-
-            let value: Value = match serde_json::from_str(trimmed) {
-                Ok(v) => v,
-                Err(_) => continue,
-            };
-
-            if Self::is_compact_summary(&value) {
-                *latest_tokens = Some(Self::token_entry_from_summary(&value));
-                continue;
-            }
-
-            if let Some(entry) = Self::token_entry_from_message(&value) {
-                *latest_tokens = Some(entry);
-            }
-        }
-
-        Ok(())
-    }
-
-    fn is_compact_summary(value: &Value) -> bool {
-        value
-            .get("isCompactSummary")
-            .and_then(serde_json::Value::as_bool)
-            .unwrap_or(false)
-    }
-
-    fn token_entry_from_summary(value: &Value) -> TokenHistory {
-        TokenHistory {
-            last_timestamp: value
-                .get("timestamp")
-                .and_then(|v| v.as_str())
-                .map(std::string::ToString::to_string),
-            ..TokenHistory::default()
-        }
-    }
-
-    fn token_entry_from_message(value: &Value) -> Option<TokenHistory> {
-        let is_assistant = value
-            .get("type")
-            .and_then(|ty| ty.as_str())
-            .is_some_and(|ty| ty == "assistant");
-        if !is_assistant {
-            return None;
-        }
-
-        let message = value.get("message")?;
-        let usage = message.get("usage")?;
-
-        let input = usage
-            .get("input_tokens")
-            .and_then(serde_json::Value::as_u64)
-            .unwrap_or(0);
-        let output = usage
-            .get("output_tokens")
-            .and_then(serde_json::Value::as_u64)
-            .unwrap_or(0);
-        let cache_creation = usage
-            .get("cache_creation_input_tokens")
-            .and_then(serde_json::Value::as_u64)
-            .unwrap_or(0);
-        let cache_read = usage
-            .get("cache_read_input_tokens")
-            .and_then(serde_json::Value::as_u64)
-            .unwrap_or(0);
-
-        let entry = TokenHistory {
-            input,
-            output,
-            cache_creation_input: cache_creation,
-            cache_read_input: cache_read,
-            context_used: input + output + cache_creation + cache_read,
-            last_message_uuid: value
-                .get("uuid")
-                .and_then(|v| v.as_str())
-                .map(std::string::ToString::to_string),
-            last_timestamp: value
-                .get("timestamp")
-                .and_then(|v| v.as_str())
-                .map(std::string::ToString::to_string),
-        };
-
-        Some(entry)
-    }
-
     fn extract_session_id(input_data: &Value) -> Option<&str> {
         input_data
             .get("session_id")
@@ -475,6 +335,7 @@ impl StorageManager {
         sanitize_latest_value(&mut latest);
         snapshot.latest = latest;
 
+        let previous_cost = snapshot.history.cost.total.total_cost_usd;
         if let Some(cost_value) = Self::extract_cost_value(input_data) {
             let metrics = CostMetrics::from_cost_value(cost_value);
             snapshot.history.cost.apply(&metrics);
@@ -494,7 +355,13 @@ impl StorageManager {
             .as_ref()
             .and_then(|tokens| tokens.last_timestamp.clone());
         let effective_timestamp = input_timestamp.or(token_timestamp_owned.as_deref());
-        Self::update_model_usage(&mut snapshot.history, model_value, effective_timestamp);
+        let cost_delta = (snapshot.history.cost.total.total_cost_usd - previous_cost).max(0.0);
+        Self::update_model_usage(
+            &mut snapshot.history,
+            model_value,
+            effective_timestamp,
+            cost_delta,
+        );
 
         self.save_snapshot(&snapshot)?;
         Ok(snapshot)
@@ -2,8 +2,11 @@
 //!
 //! Provides persistent storage for session snapshots and incremental metrics.
 
+pub mod budget;
 mod manager;
 mod project_resolver;
+pub mod report;
+pub mod transcript;
 mod types;
 
 pub use manager::StorageManager;
@@ -78,11 +81,7 @@ pub async fn initialize_storage_with_settings(
     settings: &SettingsConfig,
 ) -> Result<()> {
     let storage_config = convert_settings(settings);
-    update_runtime_config(storage_config.clone());
-
-    if let Some(ref id) = project_id {
-        update_runtime_project_id(Some(id.clone()));
-    }
+    activate_storage(project_id.clone(), settings);
 
     let mut manager = StorageManager::new()?;
 
@@ -108,6 +107,43 @@ pub async fn initialize_storage(project_id: Option<String>) -> Result<()> {
     initialize_storage_with_settings(project_id, &SettingsConfig::default()).await
 }
 
+/// Resolve the storage root (`STATUSLINE_STORAGE_PATH` or `~/.claude`).
+#[must_use]
+pub fn base_dir() -> PathBuf {
+    runtime_config()
+        .storage_path
+        .or_else(|| {
+            std::env::var("STATUSLINE_STORAGE_PATH")
+                .ok()
+                .map(PathBuf::from)
+        })
+        .unwrap_or_else(|| {
+            crate::utils::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(".claude")
+        })
+}
+
+/// Resolve the shared cache directory (`<storage>/statusline-pro/cache`).
+///
+/// The directory is not created here; writers create it lazily.
+#[must_use]
+pub fn cache_dir() -> PathBuf {
+    base_dir().join("statusline-pro").join("cache")
+}
+
+/// Point the storage runtime at an already initialized project
+///
+/// Long-running processes serving several projects call this when switching
+/// back to one, instead of re-running initialization and startup cleanup.
+pub fn activate_storage(project_id: Option<String>, settings: &SettingsConfig) {
+    update_runtime_config(convert_settings(settings));
+
+    if project_id.is_some() {
+        update_runtime_project_id(project_id);
+    }
+}
+
 pub(crate) fn current_runtime_config() -> types::StorageConfig {
     runtime_config()
 }
@@ -182,3 +218,60 @@ pub async fn get_session_tokens(session_id: &str) -> Result<Option<TokenHistory>
 
     Ok(snapshot.and_then(|snap| snap.history.tokens))
 }
+
+/// Transcript aggregates folded by the snapshot pass for `transcript_path`.
+///
+/// Returns `None` unless the session snapshot has consumed the whole file, so
+/// callers never see facts that lag behind the transcript.
+///
+/// # Errors
+///
+/// Returns an error when snapshot data cannot be loaded or parsed from disk.
+pub async fn get_transcript_aggregates(
+    session_id: &str,
+    transcript_path: &str,
+) -> Result<Option<transcript::TranscriptAggregates>> {
+    let session_id = session_id.to_string();
+    let transcript_path = transcript_path.to_string();
+    task::spawn_blocking(move || {
+        let Some(snapshot) = StorageManager::new()?.get_snapshot(&session_id)? else {
+            return Ok(None);
+        };
+        let state = snapshot.transcript_state;
+        let file_len = std::fs::metadata(&transcript_path)?.len();
+        let current = state.transcript_path.as_deref() == Some(transcript_path.as_str())
+            && state.processed_offset == file_len;
+        Ok(current.then_some(state.aggregates))
+    })
+    .await?
+}
+
+/// Total cost since `since` across every stored session.
+///
+/// `session_id` is read live; the other sessions come from a shared cache that
+/// is rebuilt once it is older than `cache_ttl`.
+///
+/// # Errors
+///
+/// Returns an error when the current session snapshot cannot be loaded.
+pub async fn get_period_spend(
+    since: chrono::DateTime<chrono::Utc>,
+    session_id: Option<&str>,
+    cache_ttl: std::time::Duration,
+) -> Result<f64> {
+    let session_id = session_id.map(str::to_string);
+    task::spawn_blocking(move || {
+        let current = match session_id {
+            Some(id) => StorageManager::new()?.get_snapshot(&id)?,
+            None => None,
+        };
+        Ok(budget::period_spend(
+            &base_dir(),
+            &cache_dir().join("budget.json"),
+            since,
+            current.as_ref(),
+            cache_ttl,
+        ))
+    })
+    .await?
+}
@@ -0,0 +1,758 @@
+//! Aggregate cost report across stored sessions
+//!
+//! 扫描 `<storage>/projects/*/statusline-pro/sessions/*.json` 下所有会话快照，
+//! 按天 / 项目 / 模型汇总成本、时长、代码行数与 token 用量。
+//!
+//! 成本按小时分桶拆到各天、按活跃模型拆到各模型；时长、代码行数与 token
+//! 只有会话累计值，整体记在最后一次更新的那天和最后使用的模型上。
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::Serialize;
+
+use super::transcript::TokenUsage;
+use super::types::{parse_cost_bucket, CostMetrics, SessionSnapshot};
+use crate::utils::width::display_width;
+
+/// Cost differences below this are rounding noise
+const COST_EPSILON: f64 = 1e-9;
+
+/// Grouping dimension of a cost report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportGroupBy {
+    #[default]
+    Day,
+    Project,
+    Model,
+}
+
+impl ReportGroupBy {
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Day => "day",
+            Self::Project => "project",
+            Self::Model => "model",
+        }
+    }
+}
+
+impl FromStr for ReportGroupBy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "day" | "date" => Ok(Self::Day),
+            "project" => Ok(Self::Project),
+            "model" => Ok(Self::Model),
+            other => Err(format!(
+                "未知的分组维度: {other}（可选 day / project / model）"
+            )),
+        }
+    }
+}
+
+/// Output format of a cost report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            other => Err(format!(
+                "未知的输出格式: {other}（可选 table / json / csv）"
+            )),
+        }
+    }
+}
+
+/// Per-session figures extracted from a snapshot
+#[derive(Debug, Clone)]
+pub struct SessionRecord {
+    pub session_id: String,
+    pub project: String,
+    /// Most recently used model id
+    pub model: Option<String>,
+    /// Local date of the last update
+    pub date: Option<NaiveDate>,
+    pub cost: CostMetrics,
+    /// Cost per local date, from the hourly cost buckets
+    pub daily_cost: BTreeMap<NaiveDate, f64>,
+    /// Cost per model id, charged while each model was active
+    pub model_cost: BTreeMap<String, f64>,
+    /// Token totals of the whole session
+    pub tokens: TokenUsage,
+}
+
+impl SessionRecord {
+    fn from_snapshot(snapshot: &SessionSnapshot, project_fallback: &str) -> Self {
+        let local_date = |time: DateTime<Utc>| time.with_timezone(&Local).date_naive();
+        let date = snapshot
+            .meta
+            .last_update_time
+            .or(snapshot.meta.created_at)
+            .map(local_date);
+
+        let model = snapshot
+            .history
+            .model_usage
+            .iter()
+            .max_by(|a, b| a.last_used_at.cmp(&b.last_used_at))
+            .map(|entry| entry.id.clone());
+
+        let cost = snapshot.history.cost.total.clone();
+        let mut daily_cost: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+        for (hour, spent) in &snapshot.history.cost.hourly {
+            if let Some(start) = parse_cost_bucket(hour) {
+                *daily_cost.entry(local_date(start)).or_default() += spent;
+            }
+        }
+        // 分桶之前写入或已过保留期的成本记到会话开始那天
+        let unbucketed = cost.total_cost_usd - daily_cost.values().sum::<f64>();
+        let first_day = snapshot.meta.created_at.map(local_date).or(date);
+        if let (true, Some(day)) = (unbucketed > COST_EPSILON, first_day) {
+            *daily_cost.entry(day).or_default() += unbucketed;
+        }
+
+        let model_cost = snapshot
+            .history
+            .model_usage
+            .iter()
+            .filter(|entry| entry.cost_usd > 0.0)
+            .map(|entry| (entry.id.clone(), entry.cost_usd))
+            .collect();
+
+        Self {
+            session_id: snapshot.meta.session_id.clone(),
+            project: snapshot
+                .meta
+                .project_path
+                .clone()
+                .unwrap_or_else(|| project_fallback.to_string()),
+            model,
+            date,
+            cost,
+            daily_cost,
+            model_cost,
+            tokens: session_tokens(snapshot),
+        }
+    }
+
+    /// Cost that falls in `since..=until`; undated cost only without a range
+    fn cost_in_range(&self, since: Option<NaiveDate>, until: Option<NaiveDate>) -> f64 {
+        if self.daily_cost.is_empty() {
+            return if since.is_none() && until.is_none() {
+                self.cost.total_cost_usd
+            } else {
+                0.0
+            };
+        }
+        self.daily_cost
+            .iter()
+            .filter(|(date, _)| in_range(**date, since, until))
+            .map(|(_, cost)| cost)
+            .sum()
+    }
+
+    /// In-range cost split over the group keys it belongs to
+    fn cost_shares(
+        &self,
+        group_by: ReportGroupBy,
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) -> BTreeMap<String, f64> {
+        let mut shares = BTreeMap::new();
+        match group_by {
+            ReportGroupBy::Day if !self.daily_cost.is_empty() => {
+                for (date, cost) in &self.daily_cost {
+                    if in_range(*date, since, until) {
+                        shares.insert(date.to_string(), *cost);
+                    }
+                }
+            }
+            ReportGroupBy::Model => {
+                let spent = self.cost_in_range(since, until);
+                let total = self.cost.total_cost_usd;
+                let mut remaining = spent;
+                if total > COST_EPSILON {
+                    for (model, cost) in &self.model_cost {
+                        let share = spent * (cost / total).min(1.0);
+                        shares.insert(model.clone(), share);
+                        remaining -= share;
+                    }
+                }
+                // 未按模型记账的成本（旧快照）归到最后使用的模型
+                if remaining > COST_EPSILON {
+                    *shares
+                        .entry(self.group_key(ReportGroupBy::Model))
+                        .or_default() += remaining;
+                }
+            }
+            _ => {
+                let spent = self.cost_in_range(since, until);
+                if spent > COST_EPSILON {
+                    shares.insert(self.group_key(group_by), spent);
+                }
+            }
+        }
+        shares
+    }
+
+    /// Undated sessions are only included when no range is given
+    fn in_range(&self, since: Option<NaiveDate>, until: Option<NaiveDate>) -> bool {
+        self.date.map_or_else(
+            || since.is_none() && until.is_none(),
+            |date| in_range(date, since, until),
+        )
+    }
+
+    fn group_key(&self, group_by: ReportGroupBy) -> String {
+        match group_by {
+            ReportGroupBy::Day => self
+                .date
+                .map_or_else(|| "unknown".to_string(), |date| date.to_string()),
+            ReportGroupBy::Project => self.project.clone(),
+            ReportGroupBy::Model => self.model.clone().unwrap_or_else(|| "unknown".to_string()),
+        }
+    }
+}
+
+/// Aggregated figures for one report group
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ReportRow {
+    pub key: String,
+    pub sessions: u64,
+    pub cost_usd: f64,
+    pub duration_ms: u64,
+    pub lines_added: u64,
+    pub lines_removed: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+}
+
+impl ReportRow {
+    /// Add the session-wide totals that cannot be split by day or model
+    const fn add_totals(&mut self, record: &SessionRecord) {
+        self.duration_ms += record.cost.total_duration_ms;
+        self.lines_added += record.cost.total_lines_added;
+        self.lines_removed += record.cost.total_lines_removed;
+        self.input_tokens += record.tokens.input;
+        self.output_tokens += record.tokens.output;
+        self.cache_creation_tokens += record.tokens.cache_creation_input;
+        self.cache_read_tokens += record.tokens.cache_read_input;
+    }
+}
+
+/// Cost report grouped along one dimension
+#[derive(Debug, Clone, Serialize)]
+pub struct CostReport {
+    pub group_by: ReportGroupBy,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<NaiveDate>,
+    pub rows: Vec<ReportRow>,
+    pub total: ReportRow,
+}
+
+impl CostReport {
+    /// Aggregate `records` over `since..=until`.
+    ///
+    /// Cost is split by day and by model; a session counts in every row it
+    /// contributes cost to, but only once in the total. Duration, lines and
+    /// tokens land on the session's last update day and last used model.
+    #[must_use]
+    pub fn build(
+        records: &[SessionRecord],
+        group_by: ReportGroupBy,
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+    ) -> Self {
+        let mut groups: BTreeMap<String, ReportRow> = BTreeMap::new();
+        let mut total = ReportRow {
+            key: "TOTAL".to_string(),
+            ..ReportRow::default()
+        };
+
+        for record in records {
+            let mut shares = record.cost_shares(group_by, since, until);
+            let primary = record
+                .in_range(since, until)
+                .then(|| record.group_key(group_by));
+            if let Some(key) = &primary {
+                shares.entry(key.clone()).or_default();
+            }
+            if shares.is_empty() {
+                continue;
+            }
+
+            total.sessions += 1;
+            for (key, cost) in shares {
+                let is_primary = primary.as_ref() == Some(&key);
+                let row = groups.entry(key.clone()).or_insert_with(|| ReportRow {
+                    key,
+                    ..ReportRow::default()
+                });
+                row.sessions += 1;
+                row.cost_usd += cost;
+                total.cost_usd += cost;
+                if is_primary {
+                    row.add_totals(record);
+                    total.add_totals(record);
+                }
+            }
+        }
+
+        Self {
+            group_by,
+            since,
+            until,
+            rows: groups.into_values().collect(),
+            total,
+        }
+    }
+
+    /// Render the report in `format`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if JSON serialization fails.
+    pub fn render(&self, format: ReportFormat) -> anyhow::Result<String> {
+        Ok(match format {
+            ReportFormat::Table => self.to_table(),
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+            ReportFormat::Csv => self.to_csv(),
+        })
+    }
+
+    fn to_table(&self) -> String {
+        let header = [
+            self.group_by.label(),
+            "sessions",
+            "cost",
+            "duration",
+            "added",
+            "removed",
+            "input",
+            "output",
+            "cache_write",
+            "cache_read",
+        ];
+        let mut lines: Vec<Vec<String>> = vec![header.iter().map(|h| (*h).to_string()).collect()];
+        for row in self.rows.iter().chain(std::iter::once(&self.total)) {
+            lines.push(vec![
+                row.key.clone(),
+                row.sessions.to_string(),
+                format!("${:.2}", row.cost_usd),
+                format_duration(row.duration_ms),
+                format!("+{}", row.lines_added),
+                format!("-{}", row.lines_removed),
+                row.input_tokens.to_string(),
+                row.output_tokens.to_string(),
+                row.cache_creation_tokens.to_string(),
+                row.cache_read_tokens.to_string(),
+            ]);
+        }
+
+        let columns = lines.first().map_or(0, Vec::len);
+        let widths: Vec<usize> = (0..columns)
+            .map(|col| {
+                lines
+                    .iter()
+                    .map(|line| display_width(&line[col]))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut output = String::new();
+        let last = lines.len() - 1;
+        for (index, line) in lines.iter().enumerate() {
+            if index == last {
+                let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+                let _ = writeln!(output, "{}", rule.join("  "));
+            }
+            let cells: Vec<String> = line
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(col, (cell, width))| {
+                    // 按显示宽度补齐,CJK / emoji 项目名占两列
+                    let padding = " ".repeat(width.saturating_sub(display_width(cell)));
+                    if col == 0 {
+                        format!("{cell}{padding}")
+                    } else {
+                        format!("{padding}{cell}")
+                    }
+                })
+                .collect();
+            let _ = writeln!(output, "{}", cells.join("  ").trim_end());
+        }
+        output
+    }
+
+    fn to_csv(&self) -> String {
+        let mut output = format!(
+            "{},sessions,cost_usd,duration_ms,lines_added,lines_removed,input_tokens,output_tokens,cache_creation_tokens,cache_read_tokens\n",
+            self.group_by.label()
+        );
+        for row in self.rows.iter().chain(std::iter::once(&self.total)) {
+            let _ = writeln!(
+                output,
+                "{},{},{:.4},{},{},{},{},{},{},{}",
+                csv_field(&row.key),
+                row.sessions,
+                row.cost_usd,
+                row.duration_ms,
+                row.lines_added,
+                row.lines_removed,
+                row.input_tokens,
+                row.output_tokens,
+                row.cache_creation_tokens,
+                row.cache_read_tokens
+            );
+        }
+        output
+    }
+}
+
+/// Load every session snapshot below `<base>/projects/*/statusline-pro/sessions`.
+///
+/// Unreadable or malformed files are skipped.
+#[must_use]
+pub fn scan_sessions(base_dir: &Path) -> Vec<SessionRecord> {
+    let mut records: Vec<SessionRecord> = load_snapshots(base_dir)
+        .iter()
+        .map(|(project, snapshot)| SessionRecord::from_snapshot(snapshot, project))
+        .collect();
+    records.sort_by(|a, b| a.session_id.cmp(&b.session_id));
+    records
+}
+
+/// Read raw snapshots together with the project directory name they live in.
+pub(crate) fn load_snapshots(base_dir: &Path) -> Vec<(String, SessionSnapshot)> {
+    let mut snapshots = Vec::new();
+    let Ok(projects) = fs::read_dir(base_dir.join("projects")) else {
+        return snapshots;
+    };
+
+    for project in projects.flatten() {
+        let project_name = project.file_name().to_string_lossy().into_owned();
+        let sessions_dir: PathBuf = project.path().join("statusline-pro").join("sessions");
+        let Ok(entries) = fs::read_dir(&sessions_dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(snapshot) = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<SessionSnapshot>(&content).ok())
+            else {
+                continue;
+            };
+            snapshots.push((project_name.clone(), snapshot));
+        }
+    }
+
+    snapshots
+}
+
+/// Token totals of a session: the transcript usage when it has been read,
+/// else the `cost.*_tokens` of the latest input
+fn session_tokens(snapshot: &SessionSnapshot) -> TokenUsage {
+    let aggregates = &snapshot.transcript_state.aggregates;
+    if aggregates.assistant_turns > 0 {
+        return aggregates.usage;
+    }
+
+    let cost = snapshot.latest.get("cost");
+    let field = |name: &str| {
+        cost.and_then(|cost| cost.get(name))
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or_default()
+    };
+    TokenUsage {
+        input: field("input_tokens"),
+        output: field("output_tokens"),
+        cache_creation_input: field("cache_write_tokens"),
+        cache_read_input: field("cache_read_tokens"),
+    }
+}
+
+fn in_range(date: NaiveDate, since: Option<NaiveDate>, until: Option<NaiveDate>) -> bool {
+    since.is_none_or(|since| date >= since) && until.is_none_or(|until| date <= until)
+}
+
+fn format_duration(duration_ms: u64) -> String {
+    let minutes = duration_ms / 60_000;
+    format!("{}h{:02}m", minutes / 60, minutes % 60)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{Context, Result};
+    use serde_json::json;
+
+    fn write_snapshot(base: &Path, project: &str, snapshot: &serde_json::Value) -> Result<()> {
+        let dir = base
+            .join("projects")
+            .join(project)
+            .join("statusline-pro")
+            .join("sessions");
+        fs::create_dir_all(&dir)?;
+        let session_id = snapshot["meta"]["session_id"]
+            .as_str()
+            .context("session id")?;
+        fs::write(
+            dir.join(format!("{session_id}.json")),
+            serde_json::to_string(snapshot)?,
+        )?;
+        Ok(())
+    }
+
+    fn snapshot(id: &str, project: &str, time: &str, model: &str, cost: f64) -> serde_json::Value {
+        json!({
+            "meta": {"session_id": id, "project_path": project, "last_update_time": time},
+            "history": {
+                "cost": {"total": {
+                    "total_cost_usd": cost,
+                    "total_duration_ms": 1_800_000,
+                    "total_lines_added": 10,
+                    "total_lines_removed": 2
+                }},
+                // 仅最后一轮的用量，不应计入报表
+                "tokens": {"input": 1, "output": 1},
+                "model_usage": [{"id": model, "last_used_at": time}]
+            },
+            "transcript_state": {"aggregates": {
+                "assistant_turns": 4,
+                "usage": {"input": 100, "output": 50, "cache_creation_input": 5, "cache_read_input": 7}
+            }}
+        })
+    }
+
+    fn sample_records() -> Result<Vec<SessionRecord>> {
+        let temp = tempfile::tempdir()?;
+        write_snapshot(
+            temp.path(),
+            "hash-a",
+            &snapshot(
+                "s1",
+                "/work/a",
+                "2026-03-01T12:00:00Z",
+                "claude-sonnet-4",
+                1.5,
+            ),
+        )?;
+        write_snapshot(
+            temp.path(),
+            "hash-a",
+            &snapshot(
+                "s2",
+                "/work/a",
+                "2026-03-02T12:00:00Z",
+                "claude-opus-4",
+                4.0,
+            ),
+        )?;
+        write_snapshot(
+            temp.path(),
+            "hash-b",
+            &snapshot(
+                "s3",
+                "/work/b",
+                "2026-03-02T12:00:00Z",
+                "claude-sonnet-4",
+                0.5,
+            ),
+        )?;
+        fs::write(
+            temp.path()
+                .join("projects/hash-b/statusline-pro/sessions/broken.json"),
+            "{",
+        )?;
+        Ok(scan_sessions(temp.path()))
+    }
+
+    #[test]
+    fn report_groups_by_project_and_model() -> Result<()> {
+        let records = sample_records()?;
+        assert_eq!(records.len(), 3);
+
+        let by_project = CostReport::build(&records, ReportGroupBy::Project, None, None);
+        let keys: Vec<_> = by_project.rows.iter().map(|row| row.key.as_str()).collect();
+        assert_eq!(keys, vec!["/work/a", "/work/b"]);
+        assert_eq!(by_project.rows[0].sessions, 2);
+        assert!((by_project.rows[0].cost_usd - 5.5).abs() < f64::EPSILON);
+        assert_eq!(by_project.total.sessions, 3);
+        assert_eq!(by_project.total.lines_added, 30);
+        assert_eq!(by_project.total.input_tokens, 300);
+
+        let by_model = CostReport::build(&records, ReportGroupBy::Model, None, None);
+        let sonnet = by_model
+            .rows
+            .iter()
+            .find(|row| row.key == "claude-sonnet-4")
+            .context("sonnet row")?;
+        assert_eq!(sonnet.sessions, 2);
+        assert!((sonnet.cost_usd - 2.0).abs() < f64::EPSILON);
+        Ok(())
+    }
+
+    #[test]
+    fn report_filters_by_date_range() -> Result<()> {
+        let records = sample_records()?;
+        let day = NaiveDate::from_ymd_opt(2026, 3, 2).context("date")?;
+
+        let report = CostReport::build(&records, ReportGroupBy::Day, Some(day), Some(day));
+        assert_eq!(report.rows.len(), 1);
+        assert_eq!(report.rows[0].key, "2026-03-02");
+        assert_eq!(report.total.sessions, 2);
+        assert_eq!(report.total.duration_ms, 3_600_000);
+        Ok(())
+    }
+
+    #[test]
+    fn report_splits_cost_by_hourly_buckets_and_model() -> Result<()> {
+        let temp = tempfile::tempdir()?;
+        let mut long = snapshot(
+            "long",
+            "/work/a",
+            "2026-03-03T12:00:00Z",
+            "claude-opus-4",
+            5.0,
+        );
+        long["history"]["cost"]["hourly"] = json!({"2026-03-01T12": 2.0, "2026-03-03T12": 3.0});
+        long["history"]["model_usage"] = json!([
+            {"id": "claude-sonnet-4", "last_used_at": "2026-03-01T12:00:00Z", "cost_usd": 2.0},
+            {"id": "claude-opus-4", "last_used_at": "2026-03-03T12:00:00Z", "cost_usd": 3.0}
+        ]);
+        long["transcript_state"] = json!({});
+        long["latest"] = json!({"cost": {"input_tokens": 900, "output_tokens": 300}});
+        write_snapshot(temp.path(), "hash-a", &long)?;
+        let records = scan_sessions(temp.path());
+
+        let by_day = CostReport::build(&records, ReportGroupBy::Day, None, None);
+        let costs: Vec<_> = by_day.rows.iter().map(|row| row.cost_usd).collect();
+        assert_eq!(costs, vec![2.0, 3.0]);
+        assert_eq!(by_day.total.sessions, 1);
+        assert_eq!(by_day.rows[0].duration_ms, 0);
+        assert_eq!(by_day.rows[1].input_tokens, 900);
+
+        let by_model = CostReport::build(&records, ReportGroupBy::Model, None, None);
+        let costs: Vec<_> = by_model
+            .rows
+            .iter()
+            .map(|row| (row.key.as_str(), row.cost_usd))
+            .collect();
+        assert_eq!(
+            costs,
+            vec![("claude-opus-4", 3.0), ("claude-sonnet-4", 2.0)]
+        );
+        assert!((by_model.total.cost_usd - 5.0).abs() < f64::EPSILON);
+        Ok(())
+    }
+
+    #[test]
+    fn report_renders_csv_and_table() -> Result<()> {
+        let records = sample_records()?;
+        let report = CostReport::build(&records, ReportGroupBy::Project, None, None);
+
+        let csv = report.render(ReportFormat::Csv)?;
+        let mut lines = csv.lines();
+        assert!(lines
+            .next()
+            .is_some_and(|header| header.starts_with("project,sessions,cost_usd")));
+        assert_eq!(
+            lines.next(),
+            Some("/work/a,2,5.5000,3600000,20,4,200,100,10,14")
+        );
+        assert!(csv
+            .lines()
+            .last()
+            .is_some_and(|line| line.starts_with("TOTAL,3,6.0000,")));
+
+        let table = report.render(ReportFormat::Table)?;
+        assert!(table
+            .lines()
+            .last()
+            .is_some_and(|line| line.starts_with("TOTAL")));
+        assert!(table.contains("$6.00"));
+        assert!(table.contains("1h30m"));
+
+        let json: serde_json::Value = serde_json::from_str(&report.render(ReportFormat::Json)?)?;
+        assert_eq!(json["group_by"], "project");
+        assert_eq!(json["total"]["sessions"], 3);
+        Ok(())
+    }
+
+    #[test]
+    fn table_aligns_wide_project_names() -> Result<()> {
+        let report = CostReport {
+            group_by: ReportGroupBy::Project,
+            since: None,
+            until: None,
+            rows: vec![
+                ReportRow {
+                    key: "/work/项目名称".to_string(),
+                    sessions: 1,
+                    ..ReportRow::default()
+                },
+                ReportRow {
+                    key: "/work/abc".to_string(),
+                    sessions: 2,
+                    ..ReportRow::default()
+                },
+            ],
+            total: ReportRow {
+                key: "TOTAL".to_string(),
+                sessions: 3,
+                ..ReportRow::default()
+            },
+        };
+
+        let table = report.render(ReportFormat::Table)?;
+        // 末列右对齐,各行显示宽度一致才说明宽字符没有把列挤歪
+        let widths: Vec<usize> = table.lines().map(display_width).collect();
+        assert!(widths.windows(2).all(|pair| pair[0] == pair[1]));
+        Ok(())
+    }
+
+    #[test]
+    fn csv_field_escapes_separators() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
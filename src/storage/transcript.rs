@@ -0,0 +1,847 @@
+//! Transcript analysis
+//!
+//! The single place that understands Claude Code JSONL transcripts. Each line
+//! is parsed into a [`TranscriptEntry`] carrying typed [`TranscriptEvent`]s;
+//! [`TranscriptAggregates`] folds them into the facts components show (status,
+//! usage totals, tool and error counts).
+//!
+//! The session snapshot pass calls [`advance`], which only reads the lines
+//! appended since the previous refresh and persists the aggregates in
+//! [`TranscriptState`]. [`tail_status`] rebuilds the status from the end of
+//! the file when no snapshot is available (preview mode, missing session id).
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::types::{
+    ContextSample, TokenHistory, TranscriptState, RECENT_CONTEXT_TURNS, TRANSCRIPT_STATE_VERSION,
+};
+
+/// Non-empty lines searched for the most recent tool call
+const RECENT_TOOL_WINDOW: usize = 5;
+
+/// First chunk read by [`tail_status`]; grows 4x until a turn is covered
+const TAIL_CHUNK: u64 = 64 * 1024;
+
+/// Token counts reported by one assistant turn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    #[serde(default)]
+    pub input: u64,
+    #[serde(default)]
+    pub output: u64,
+    #[serde(default)]
+    pub cache_creation_input: u64,
+    #[serde(default)]
+    pub cache_read_input: u64,
+}
+
+impl TokenUsage {
+    fn from_value(usage: &Value) -> Self {
+        let field = |key: &str| usage.get(key).and_then(Value::as_u64).unwrap_or(0);
+        Self {
+            input: field("input_tokens"),
+            output: field("output_tokens"),
+            cache_creation_input: field("cache_creation_input_tokens"),
+            cache_read_input: field("cache_read_input_tokens"),
+        }
+    }
+
+    /// Tokens occupying the context window after this turn
+    #[must_use]
+    pub const fn context_used(&self) -> u64 {
+        self.input + self.output + self.cache_creation_input + self.cache_read_input
+    }
+
+    /// Share of prompt tokens served from the prompt cache, in percent
+    ///
+    /// `None` when the turn sent no prompt tokens at all.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn cache_hit_ratio(&self) -> Option<f64> {
+        let prompt = self.input + self.cache_creation_input + self.cache_read_input;
+        (prompt > 0).then(|| self.cache_read_input as f64 / prompt as f64 * 100.0)
+    }
+
+    const fn accumulate(&mut self, other: &Self) {
+        self.input += other.input;
+        self.output += other.output;
+        self.cache_creation_input += other.cache_creation_input;
+        self.cache_read_input += other.cache_read_input;
+    }
+
+    const fn subtract(&mut self, other: &Self) {
+        self.input = self.input.saturating_sub(other.input);
+        self.output = self.output.saturating_sub(other.output);
+        self.cache_creation_input = self
+            .cache_creation_input
+            .saturating_sub(other.cache_creation_input);
+        self.cache_read_input = self.cache_read_input.saturating_sub(other.cache_read_input);
+    }
+}
+
+/// One fact carried by a transcript entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptEvent {
+    /// Prompt typed by the user (tool results and compact summaries excluded)
+    User,
+    /// Assistant turn that reported usage
+    Assistant {
+        usage: TokenUsage,
+        stop_reason: Option<String>,
+        /// `message.id`, else `requestId`; shared by every line of one response
+        response_id: Option<String>,
+    },
+    ToolUse {
+        name: String,
+    },
+    ToolResult {
+        is_error: bool,
+    },
+    /// Summary written when the conversation was compacted
+    CompactSummary,
+    /// Failure reported by the preceding assistant turn
+    Error {
+        detail: Option<String>,
+    },
+}
+
+/// One parsed transcript line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TranscriptEntry {
+    /// Raw `type` field (`user`, `assistant`, `system`, …)
+    pub entry_type: Option<String>,
+    pub uuid: Option<String>,
+    pub timestamp: Option<String>,
+    pub events: Vec<TranscriptEvent>,
+}
+
+impl TranscriptEntry {
+    /// Parse one non-empty line; `None` when it is not valid JSON
+    #[must_use]
+    pub fn parse_line(line: &str) -> Option<Self> {
+        serde_json::from_str::<Value>(line.trim())
+            .ok()
+            .map(|value| Self::parse(&value))
+    }
+
+    #[must_use]
+    pub fn parse(value: &Value) -> Self {
+        let text = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+        let entry_type = text("type");
+        let message = value.get("message");
+        let content = message
+            .and_then(|message| message.get("content"))
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let mut events = Vec::new();
+
+        if is_compact_summary(value) {
+            events.push(TranscriptEvent::CompactSummary);
+        } else if entry_type.as_deref() == Some("user") {
+            let results: Vec<_> = content
+                .iter()
+                .filter(|item| item_type(item) == Some("tool_result"))
+                .map(|item| TranscriptEvent::ToolResult {
+                    is_error: item.get("is_error").and_then(Value::as_bool) == Some(true),
+                })
+                .collect();
+            if results.is_empty() {
+                events.push(TranscriptEvent::User);
+            }
+            events.extend(results);
+        } else if entry_type.as_deref() == Some("assistant") {
+            if let Some(usage) = message.and_then(|message| message.get("usage")) {
+                events.push(TranscriptEvent::Assistant {
+                    usage: TokenUsage::from_value(usage),
+                    stop_reason: message
+                        .and_then(|message| message.get("stop_reason"))
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                    response_id: message
+                        .and_then(|message| message.get("id"))
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                        .or_else(|| text("requestId")),
+                });
+                if is_error_entry(value) {
+                    events.push(TranscriptEvent::Error {
+                        detail: get_error_details(value),
+                    });
+                }
+            }
+        }
+
+        events.extend(
+            content
+                .iter()
+                .filter(|item| item_type(item) == Some("tool_use"))
+                .filter_map(|item| item.get("name").and_then(Value::as_str))
+                .map(|name| TranscriptEvent::ToolUse {
+                    name: name.to_string(),
+                }),
+        );
+
+        Self {
+            entry_type,
+            uuid: text("uuid"),
+            timestamp: text("timestamp"),
+            events,
+        }
+    }
+
+    /// Fold this entry into the session token history: an assistant turn
+    /// replaces the usage and records its context size, a compaction empties
+    /// the context
+    pub fn fold_tokens(&self, tokens: &mut Option<TokenHistory>) {
+        for event in &self.events {
+            match event {
+                TranscriptEvent::Assistant {
+                    usage, response_id, ..
+                } => {
+                    let context_used = usage.context_used();
+                    let previous = tokens.take();
+                    // 同一次响应拆成多行时按 response_id 认作一轮,采样取最新一行
+                    let repeated = response_id.is_some()
+                        && previous
+                            .as_ref()
+                            .is_some_and(|tokens| tokens.last_response_id == *response_id);
+                    let mut recent_context = previous
+                        .map(|tokens| tokens.recent_context)
+                        .unwrap_or_default();
+                    if repeated {
+                        recent_context.pop();
+                    }
+                    recent_context.push(ContextSample {
+                        context_used,
+                        timestamp: self.timestamp.clone(),
+                    });
+                    let excess = recent_context.len().saturating_sub(RECENT_CONTEXT_TURNS);
+                    recent_context.drain(..excess);
+
+                    *tokens = Some(TokenHistory {
+                        input: usage.input,
+                        output: usage.output,
+                        cache_creation_input: usage.cache_creation_input,
+                        cache_read_input: usage.cache_read_input,
+                        context_used,
+                        last_message_uuid: self.uuid.clone(),
+                        last_response_id: response_id.clone(),
+                        last_timestamp: self.timestamp.clone(),
+                        recent_context,
+                    });
+                }
+                TranscriptEvent::CompactSummary => {
+                    *tokens = Some(TokenHistory {
+                        last_timestamp: self.timestamp.clone(),
+                        ..TokenHistory::default()
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn first_tool_use(&self) -> Option<&str> {
+        self.events.iter().find_map(|event| match event {
+            TranscriptEvent::ToolUse { name } => Some(name.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// Newest transcript facts relevant to the session status
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptStatus {
+    /// `type` of the newest entry that has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_entry_type: Option<String>,
+    /// `stop_reason` of the newest assistant turn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+    /// Whether the newest assistant turn reported an error
+    #[serde(default)]
+    pub error: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_detail: Option<String>,
+    /// `tool_use` name of each of the newest non-empty lines, oldest first
+    #[serde(default)]
+    pub recent_tools: VecDeque<Option<String>>,
+}
+
+impl TranscriptStatus {
+    /// Name of the newest tool call among the last few lines
+    #[must_use]
+    pub fn recent_tool(&self) -> Option<&str> {
+        self.recent_tools
+            .iter()
+            .rev()
+            .find_map(Option::as_deref)
+            .filter(|name| !name.is_empty())
+    }
+}
+
+/// Everything folded from a transcript so far
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptAggregates {
+    #[serde(default)]
+    pub status: TranscriptStatus,
+    #[serde(default)]
+    pub user_prompts: u64,
+    #[serde(default)]
+    pub assistant_turns: u64,
+    #[serde(default)]
+    pub tool_uses: u64,
+    #[serde(default)]
+    pub tool_errors: u64,
+    #[serde(default)]
+    pub compactions: u64,
+    #[serde(default)]
+    pub errors: u64,
+    /// Usage summed over every assistant turn, across compactions
+    #[serde(default)]
+    pub usage: TokenUsage,
+    /// Response id of the newest assistant turn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_response_id: Option<String>,
+    /// Usage of the newest assistant turn as counted in `usage`
+    #[serde(default)]
+    pub last_response_usage: TokenUsage,
+}
+
+impl TranscriptAggregates {
+    /// Fold one raw transcript line
+    pub fn observe_line(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        self.observe(TranscriptEntry::parse_line(line).as_ref());
+    }
+
+    /// Fold one non-empty transcript line; `None` when it is not valid JSON
+    pub fn observe(&mut self, entry: Option<&TranscriptEntry>) {
+        let status = &mut self.status;
+        // 窗口按非空行计数,解析失败的行也占位
+        if status.recent_tools.len() == RECENT_TOOL_WINDOW {
+            status.recent_tools.pop_front();
+        }
+        status.recent_tools.push_back(
+            entry
+                .and_then(TranscriptEntry::first_tool_use)
+                .map(str::to_string),
+        );
+
+        let Some(entry) = entry else {
+            return;
+        };
+        if let Some(entry_type) = &entry.entry_type {
+            status.last_entry_type = Some(entry_type.clone());
+        }
+
+        for event in &entry.events {
+            match event {
+                TranscriptEvent::User => self.user_prompts += 1,
+                TranscriptEvent::Assistant {
+                    usage,
+                    stop_reason,
+                    response_id,
+                } => {
+                    // 同一次响应拆成多行时只算一轮,usage 以最新一行为准
+                    if response_id.is_some() && *response_id == self.last_response_id {
+                        self.usage.subtract(&self.last_response_usage);
+                    } else {
+                        self.assistant_turns += 1;
+                    }
+                    self.usage.accumulate(usage);
+                    self.last_response_id.clone_from(response_id);
+                    self.last_response_usage = *usage;
+                    self.status.stop_reason.clone_from(stop_reason);
+                    self.status.error = false;
+                    self.status.error_detail = None;
+                }
+                TranscriptEvent::ToolUse { .. } => self.tool_uses += 1,
+                TranscriptEvent::ToolResult { is_error } => {
+                    self.tool_errors += u64::from(*is_error);
+                }
+                TranscriptEvent::CompactSummary => self.compactions += 1,
+                TranscriptEvent::Error { detail } => {
+                    self.errors += 1;
+                    self.status.error = true;
+                    self.status.error_detail.clone_from(detail);
+                }
+            }
+        }
+    }
+}
+
+/// Fold the lines appended to the transcript at `path` since `state` was
+/// last advanced, handing every parsed entry to `on_entry` as well
+///
+/// Starts over when the transcript moved or shrank, or the state was written
+/// by another version. A trailing line without a newline is still being
+/// written and is left for the next pass.
+///
+/// # Errors
+///
+/// Returns an error when the transcript cannot be opened or read.
+pub fn advance(
+    state: &mut TranscriptState,
+    path: &str,
+    mut on_entry: impl FnMut(&TranscriptEntry),
+) -> Result<()> {
+    let file_path = Path::new(path);
+    if !file_path.exists() {
+        state.transcript_path = Some(path.to_string());
+        return Ok(());
+    }
+
+    let file_len = file_path
+        .metadata()
+        .with_context(|| format!("Failed to read transcript metadata: {path}"))?
+        .len();
+    if !state.is_current()
+        || state.transcript_path.as_deref() != Some(path)
+        || state.processed_offset > file_len
+    {
+        *state = TranscriptState {
+            version: TRANSCRIPT_STATE_VERSION,
+            transcript_path: Some(path.to_string()),
+            ..TranscriptState::default()
+        };
+    }
+
+    let mut file =
+        File::open(file_path).with_context(|| format!("Failed to open transcript: {path}"))?;
+    file.seek(SeekFrom::Start(state.processed_offset))
+        .with_context(|| format!("Failed to seek transcript: {path}"))?;
+    let mut reader = BufReader::new(file);
+    let mut buffer = String::new();
+
+    loop {
+        buffer.clear();
+        let bytes_read = reader
+            .read_line(&mut buffer)
+            .with_context(|| format!("Failed to read transcript line: {path}"))?;
+        // 末尾未写完的行留到下次,避免半行被跳过
+        if bytes_read == 0 || !buffer.ends_with('\n') {
+            break;
+        }
+        state.processed_offset += bytes_read as u64;
+
+        if buffer.trim().is_empty() {
+            continue;
+        }
+        state.processed_messages += 1;
+
+        let entry = TranscriptEntry::parse_line(&buffer);
+        state.aggregates.observe(entry.as_ref());
+        if let Some(entry) = &entry {
+            on_entry(entry);
+        }
+    }
+
+    Ok(())
+}
+
+/// Status rebuilt from the end of the transcript at `path`
+///
+/// Reads backwards in growing chunks until the newest assistant turn is
+/// covered, so the cost is bounded by the size of the last turn rather than
+/// the whole session.
+#[must_use]
+pub fn tail_status(path: &Path) -> Option<TranscriptStatus> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let mut window = TAIL_CHUNK.min(len);
+
+    loop {
+        let start = len - window;
+        file.seek(SeekFrom::Start(start)).ok()?;
+        let mut bytes = Vec::new();
+        (&mut file).take(window).read_to_end(&mut bytes).ok()?;
+
+        let text = String::from_utf8_lossy(&bytes);
+        // 窗口起点落在行中间时丢弃残行
+        let text = if start > 0 {
+            text.split_once('\n').map_or("", |(_, rest)| rest)
+        } else {
+            &text
+        };
+
+        let mut aggregates = TranscriptAggregates::default();
+        for line in text.lines() {
+            aggregates.observe_line(line);
+        }
+        if aggregates.assistant_turns > 0 || start == 0 {
+            return Some(aggregates.status);
+        }
+        window = window.saturating_mul(4).min(len);
+    }
+}
+
+fn item_type(item: &Value) -> Option<&str> {
+    item.get("type").and_then(Value::as_str)
+}
+
+fn is_compact_summary(value: &Value) -> bool {
+    value
+        .get("isCompactSummary")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+}
+
+fn is_error_entry(entry: &Value) -> bool {
+    if entry
+        .get("toolUseResult")
+        .is_some_and(tool_use_indicates_error)
+    {
+        return true;
+    }
+
+    if let Some(message) = entry.get("message") {
+        if is_stop_sequence_message(message)
+            && message_contains_matching_text(message, text_indicates_error)
+        {
+            return true;
+        }
+    }
+
+    false
+}
+
+fn get_error_details(entry: &Value) -> Option<String> {
+    if let Some(tool_use_result) = entry.get("toolUseResult") {
+        if let Some(error_msg) = tool_use_result.get("error").and_then(Value::as_str) {
+            return error_detail_from_message(error_msg);
+        }
+    }
+
+    entry.get("message").and_then(|message| {
+        if is_stop_sequence_message(message) {
+            message_error_detail(message)
+        } else {
+            None
+        }
+    })
+}
+
+fn tool_use_indicates_error(tool_use_result: &Value) -> bool {
+    if let Some(error_msg) = tool_use_result.get("error").and_then(Value::as_str) {
+        return !is_blocked_error_message(error_msg);
+    }
+
+    tool_use_result
+        .get("type")
+        .and_then(Value::as_str)
+        .is_some_and(|ty| ty.eq_ignore_ascii_case("error"))
+}
+
+fn is_stop_sequence_message(message: &Value) -> bool {
+    message
+        .get("stop_reason")
+        .and_then(Value::as_str)
+        .is_some_and(|reason| reason == "stop_sequence")
+}
+
+fn message_contains_matching_text(message: &Value, predicate: impl Fn(&str) -> bool) -> bool {
+    message
+        .get("content")
+        .and_then(Value::as_array)
+        .is_some_and(|items| {
+            items
+                .iter()
+                .filter_map(|item| {
+                    if item_type(item)? != "text" {
+                        return None;
+                    }
+                    item.get("text").and_then(Value::as_str)
+                })
+                .any(predicate)
+        })
+}
+
+fn text_indicates_error(text: &str) -> bool {
+    (text.starts_with("API Error: 403") && text.contains("user quota is not enough"))
+        || text.contains("filter")
+}
+
+fn error_detail_from_message(error_msg: &str) -> Option<String> {
+    if is_blocked_error_message(error_msg) {
+        None
+    } else {
+        Some(error_msg.to_string())
+    }
+}
+
+fn message_error_detail(message: &Value) -> Option<String> {
+    let items = message.get("content").and_then(Value::as_array)?;
+
+    items.iter().find_map(|item| {
+        if item_type(item)? != "text" {
+            return None;
+        }
+
+        let text = item.get("text").and_then(Value::as_str)?;
+        if text.starts_with("API Error: 403") && text.contains("user quota is not enough") {
+            Some("403 quota insufficient".to_string())
+        } else if text.contains("filter") {
+            Some("Filter error".to_string())
+        } else {
+            None
+        }
+    })
+}
+
+fn is_blocked_error_message(message: &str) -> bool {
+    message.contains("was blocked") || message.contains("For security")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use serde_json::json;
+    use std::io::Write;
+
+    fn assistant(stop_reason: &str, tool: Option<&str>) -> String {
+        let content = tool.map_or_else(
+            || json!([{"type": "text", "text": "ok"}]),
+            |name| json!([{"type": "tool_use", "name": name}]),
+        );
+        json!({
+            "type": "assistant",
+            "message": {"usage": {"input_tokens": 1}, "stop_reason": stop_reason, "content": content}
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn entries_parse_into_typed_events() {
+        let tool_turn = TranscriptEntry::parse_line(&assistant("tool_use", Some("Bash")));
+        assert_eq!(
+            tool_turn.map(|entry| entry.events),
+            Some(vec![
+                TranscriptEvent::Assistant {
+                    usage: TokenUsage {
+                        input: 1,
+                        ..TokenUsage::default()
+                    },
+                    stop_reason: Some("tool_use".to_string()),
+                    response_id: None,
+                },
+                TranscriptEvent::ToolUse {
+                    name: "Bash".to_string()
+                },
+            ])
+        );
+
+        let result = json!({
+            "type": "user",
+            "message": {"content": [{"type": "tool_result", "is_error": true}]}
+        });
+        assert_eq!(
+            TranscriptEntry::parse(&result).events,
+            vec![TranscriptEvent::ToolResult { is_error: true }]
+        );
+
+        let summary = json!({"type": "user", "isCompactSummary": true, "timestamp": "t"});
+        let summary = TranscriptEntry::parse(&summary);
+        assert_eq!(summary.events, vec![TranscriptEvent::CompactSummary]);
+
+        assert!(TranscriptEntry::parse_line("not json").is_none());
+    }
+
+    #[test]
+    fn token_folding_tracks_context_growth_until_compaction() -> Result<()> {
+        let turn = |id: &str, context: u64, minute: u32| {
+            TranscriptEntry::parse(&json!({
+                "type": "assistant",
+                "timestamp": format!("2025-01-01T00:{minute:02}:00Z"),
+                "message": {"id": id, "usage": {"input_tokens": context}}
+            }))
+        };
+
+        let mut tokens = None;
+        for entry in [
+            turn("msg_1", 10_000, 0),
+            turn("msg_1", 10_000, 0),
+            turn("msg_2", 14_000, 2),
+            turn("msg_3", 18_000, 4),
+        ] {
+            entry.fold_tokens(&mut tokens);
+        }
+        let history = tokens.clone().ok_or_else(|| anyhow::anyhow!("tokens"))?;
+        // 重复的响应行只记一次
+        assert_eq!(history.recent_context.len(), 3);
+        let growth = history
+            .context_growth(5)
+            .ok_or_else(|| anyhow::anyhow!("growth"))?;
+        assert!((growth.per_turn - 4_000.0).abs() < f64::EPSILON);
+        assert_eq!(growth.per_minute.map(f64::round), Some(2_000.0));
+
+        TranscriptEntry::parse(&json!({"type": "user", "isCompactSummary": true}))
+            .fold_tokens(&mut tokens);
+        let history = tokens.ok_or_else(|| anyhow::anyhow!("tokens"))?;
+        assert_eq!(history.context_used, 0);
+        assert!(history.context_growth(5).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn split_responses_are_keyed_by_response_id() -> Result<()> {
+        let line = |id: &str, input: u64, output: u64| {
+            TranscriptEntry::parse(&json!({
+                "type": "assistant",
+                "message": {"id": id, "usage": {"input_tokens": input, "output_tokens": output}}
+            }))
+        };
+        // 两轮真实对话的上下文恰好相同;一次响应拆成的两行 usage 不同
+        let entries = [
+            line("msg_1", 10_000, 0),
+            line("msg_2", 10_000, 0),
+            line("msg_3", 12_000, 100),
+            line("msg_3", 12_000, 400),
+        ];
+
+        let mut tokens = None;
+        let mut aggregates = TranscriptAggregates::default();
+        for entry in &entries {
+            entry.fold_tokens(&mut tokens);
+            aggregates.observe(Some(entry));
+        }
+
+        let history = tokens.ok_or_else(|| anyhow::anyhow!("tokens"))?;
+        let samples: Vec<_> = history
+            .recent_context
+            .iter()
+            .map(|sample| sample.context_used)
+            .collect();
+        assert_eq!(samples, vec![10_000, 10_000, 12_400]);
+        assert_eq!(history.last_response_id.as_deref(), Some("msg_3"));
+
+        assert_eq!(aggregates.assistant_turns, 3);
+        assert_eq!(aggregates.usage.input, 32_000);
+        assert_eq!(aggregates.usage.output, 400);
+        Ok(())
+    }
+
+    #[test]
+    fn incremental_folding_matches_a_full_pass() {
+        let lines = [
+            assistant("tool_use", Some("Bash")),
+            json!({"type": "user", "message": {"content": "result"}}).to_string(),
+            "not json".to_string(),
+            assistant("tool_use", Some("Read")),
+        ];
+
+        let mut full = TranscriptAggregates::default();
+        for line in &lines {
+            full.observe_line(line);
+        }
+
+        let mut incremental = TranscriptAggregates::default();
+        for line in &lines[..2] {
+            incremental.observe_line(line);
+        }
+        assert_eq!(incremental.status.last_entry_type.as_deref(), Some("user"));
+        for line in &lines[2..] {
+            incremental.observe_line(line);
+        }
+
+        assert_eq!(incremental, full);
+        assert_eq!(full.status.recent_tool(), Some("Read"));
+        assert_eq!(full.status.stop_reason.as_deref(), Some("tool_use"));
+        assert_eq!(full.assistant_turns, 2);
+        assert_eq!(full.user_prompts, 1);
+        assert_eq!(full.tool_uses, 2);
+        assert_eq!(full.usage.input, 2);
+    }
+
+    #[test]
+    fn multi_line_responses_count_once() {
+        let line = |id: &str, block: Value| {
+            json!({
+                "type": "assistant",
+                "requestId": format!("req_{id}"),
+                "message": {
+                    "id": id,
+                    "usage": {"input_tokens": 10, "output_tokens": 5},
+                    "content": [block]
+                }
+            })
+            .to_string()
+        };
+        let lines = [
+            line("msg_1", json!({"type": "thinking", "thinking": "…"})),
+            line("msg_1", json!({"type": "text", "text": "ok"})),
+            line("msg_1", json!({"type": "tool_use", "name": "Bash"})),
+            json!({"type": "user", "message": {"content": [{"type": "tool_result"}]}}).to_string(),
+            line("msg_2", json!({"type": "text", "text": "done"})),
+        ];
+
+        let mut aggregates = TranscriptAggregates::default();
+        for line in &lines {
+            aggregates.observe_line(line);
+        }
+        assert_eq!(aggregates.assistant_turns, 2);
+        assert_eq!(aggregates.usage.input, 20);
+        assert_eq!(aggregates.usage.output, 10);
+        assert_eq!(aggregates.tool_uses, 1);
+
+        // 没有 message.id 时退回 requestId
+        let entry = TranscriptEntry::parse(&json!({
+            "type": "assistant",
+            "requestId": "req_3",
+            "message": {"usage": {"input_tokens": 1}}
+        }));
+        assert!(matches!(
+            entry.events.first(),
+            Some(TranscriptEvent::Assistant { response_id: Some(id), .. }) if id == "req_3"
+        ));
+    }
+
+    #[test]
+    fn unversioned_state_is_folded_again() -> Result<()> {
+        let mut file = tempfile::NamedTempFile::new()?;
+        writeln!(file, "{}", assistant("end_turn", None))?;
+        let path = file.path().to_string_lossy().to_string();
+
+        // 引入聚合之前的快照:偏移已经在文件末尾,聚合为空
+        let mut state: TranscriptState = serde_json::from_value(json!({
+            "transcript_path": path,
+            "processed_offset": file.as_file().metadata()?.len(),
+            "processed_messages": 1
+        }))?;
+        advance(&mut state, &path, |_| {})?;
+
+        assert!(state.is_current());
+        assert_eq!(state.aggregates.assistant_turns, 1);
+        assert_eq!(state.processed_messages, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn tail_status_reads_back_until_the_last_turn() -> Result<()> {
+        let mut file = tempfile::NamedTempFile::new()?;
+        writeln!(file, "{}", assistant("tool_use", Some("Bash")))?;
+        // 最后一轮之后的大段 user 行迫使窗口扩大
+        let filler = json!({"type": "user", "message": {"content": "x".repeat(1024)}});
+        for _ in 0..200 {
+            writeln!(file, "{filler}")?;
+        }
+
+        let status = tail_status(file.path()).ok_or_else(|| anyhow::anyhow!("status"))?;
+        assert_eq!(status.stop_reason.as_deref(), Some("tool_use"));
+        assert_eq!(status.last_entry_type.as_deref(), Some("user"));
+        // 工具名只看最近几行
+        assert_eq!(status.recent_tool(), None);
+        Ok(())
+    }
+}
@@ -2,9 +2,19 @@
 //!
 //! Defines the snapshot structures that persist Claude Code session data.
 
-use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 
+use super::transcript::{TokenUsage, TranscriptAggregates};
+
+/// Key format of [`CostHistory::hourly`] buckets (UTC hour)
+pub const COST_BUCKET_FORMAT: &str = "%Y-%m-%dT%H";
+
+/// Hourly buckets older than this are dropped on update
+const COST_BUCKET_RETENTION_DAYS: i64 = 62;
+
 /// Storage configuration mirroring the TypeScript settings
 #[derive(Debug, Clone)]
 pub struct StorageConfig {
@@ -119,10 +129,20 @@ pub struct CostHistory {
     pub accumulated: CostMetrics,
     #[serde(default)]
     pub total: CostMetrics,
+    /// Cost increments keyed by UTC hour ([`COST_BUCKET_FORMAT`]), used by period budgets
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hourly: BTreeMap<String, f64>,
 }
 
 impl CostHistory {
     pub fn apply(&mut self, new_metrics: &CostMetrics) {
+        self.apply_at(new_metrics, Utc::now());
+    }
+
+    /// Apply `new_metrics` and attribute the cost increase to the hour of `at`.
+    pub fn apply_at(&mut self, new_metrics: &CostMetrics, at: DateTime<Utc>) {
+        let previous_cost = self.total.total_cost_usd;
+
         if self.current.total_cost_usd > 0.0
             && new_metrics.total_cost_usd < self.current.total_cost_usd
         {
@@ -159,9 +179,51 @@ impl CostHistory {
             total_lines_removed: self.current.total_lines_removed
                 + self.accumulated.total_lines_removed,
         };
+
+        let delta = self.total.total_cost_usd - previous_cost;
+        if delta > 0.0 {
+            *self
+                .hourly
+                .entry(at.format(COST_BUCKET_FORMAT).to_string())
+                .or_default() += delta;
+        }
+        let cutoff = (at - Duration::days(COST_BUCKET_RETENTION_DAYS))
+            .format(COST_BUCKET_FORMAT)
+            .to_string();
+        self.hourly.retain(|hour, _| *hour >= cutoff);
+    }
+
+    /// Cost spent at or after `since`.
+    ///
+    /// Buckets are hourly, so a bucket counts when any part of it falls after
+    /// `since`. Snapshots written before buckets existed attribute their whole
+    /// total to `last_update`.
+    #[must_use]
+    pub fn cost_since(&self, since: DateTime<Utc>, last_update: Option<DateTime<Utc>>) -> f64 {
+        if self.hourly.is_empty() {
+            return if last_update.is_some_and(|time| time >= since) {
+                self.total.total_cost_usd
+            } else {
+                0.0
+            };
+        }
+
+        self.hourly
+            .iter()
+            .filter(|(hour, _)| {
+                parse_cost_bucket(hour).is_some_and(|start| start + Duration::hours(1) > since)
+            })
+            .map(|(_, cost)| cost)
+            .sum()
     }
 }
 
+pub(super) fn parse_cost_bucket(hour: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(&format!("{hour}:00"), "%Y-%m-%dT%H:%M")
+        .ok()
+        .map(|naive| Utc.from_utc_datetime(&naive))
+}
+
 /// Cost metrics captured from Claude Code.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CostMetrics {
@@ -205,6 +267,9 @@ impl CostMetrics {
     }
 }
 
+/// Assistant turns kept in [`TokenHistory::recent_context`]
+pub const RECENT_CONTEXT_TURNS: usize = 20;
+
 /// Token usage extracted from transcript updates.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TokenHistory {
@@ -220,8 +285,85 @@ pub struct TokenHistory {
     pub context_used: u64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_message_uuid: Option<String>,
+    /// `message.id` / `requestId` of the newest turn; split lines of one
+    /// response share it and update its sample instead of adding one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_response_id: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_timestamp: Option<String>,
+    /// Context size after each of the newest turns since the last
+    /// compaction, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recent_context: Vec<ContextSample>,
+}
+
+impl TokenHistory {
+    /// Usage reported by the newest assistant turn
+    #[must_use]
+    pub const fn usage(&self) -> TokenUsage {
+        TokenUsage {
+            input: self.input,
+            output: self.output,
+            cache_creation_input: self.cache_creation_input,
+            cache_read_input: self.cache_read_input,
+        }
+    }
+
+    /// Average context growth over the newest `turns` turns
+    ///
+    /// `None` until two turns are recorded or while the context is not
+    /// growing.
+    #[must_use]
+    pub fn context_growth(&self, turns: usize) -> Option<ContextGrowth> {
+        let start = self.recent_context.len().saturating_sub(turns.max(1) + 1);
+        let samples = &self.recent_context[start..];
+        let (first, last) = (samples.first()?, samples.last()?);
+        if last.context_used <= first.context_used {
+            return None;
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let grown = (last.context_used - first.context_used) as f64;
+        #[allow(clippy::cast_precision_loss)]
+        let per_turn = grown / (samples.len() - 1) as f64;
+        let timestamp = |sample: &ContextSample| {
+            sample
+                .timestamp
+                .as_deref()
+                .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+        };
+        let per_minute = timestamp(first)
+            .zip(timestamp(last))
+            .map(|(start, end)| (end - start).num_milliseconds())
+            .filter(|elapsed| *elapsed > 0)
+            .map(|elapsed| {
+                #[allow(clippy::cast_precision_loss)]
+                let minutes = elapsed as f64 / 60_000.0;
+                grown / minutes
+            });
+
+        Some(ContextGrowth {
+            per_turn,
+            per_minute,
+        })
+    }
+}
+
+/// Context size reported by one assistant turn
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct ContextSample {
+    pub context_used: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+}
+
+/// Recent context growth rate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContextGrowth {
+    /// Tokens added per assistant turn
+    pub per_turn: f64,
+    /// Tokens added per minute, when the turns carry timestamps
+    pub per_minute: Option<f64>,
 }
 
 /// Track which models have been observed during this session.
@@ -232,11 +374,25 @@ pub struct ModelUsageEntry {
     pub display_name: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_used_at: Option<String>,
+    /// Cost spent while this model was the active one
+    #[serde(default)]
+    pub cost_usd: f64,
 }
 
+/// Layout of [`TranscriptState`]; a state written by another version is
+/// folded again from the start of the transcript
+///
+/// 1: aggregates added, 2: multi-line responses counted once, 3: split
+/// responses keyed by response id in the token history as well
+pub const TRANSCRIPT_STATE_VERSION: u32 = 3;
+
 /// Internal transcript processing state.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TranscriptState {
+    /// [`TRANSCRIPT_STATE_VERSION`] the state was folded with; 0 for
+    /// snapshots written before versioning
+    #[serde(default)]
+    pub version: u32,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub transcript_path: Option<String>,
     #[serde(default)]
@@ -247,4 +403,15 @@ pub struct TranscriptState {
     pub last_message_uuid: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_timestamp: Option<String>,
+    /// Facts folded from every processed line (see [`super::transcript`])
+    #[serde(default)]
+    pub aggregates: TranscriptAggregates,
+}
+
+impl TranscriptState {
+    /// Whether the state was folded by this version
+    #[must_use]
+    pub const fn is_current(&self) -> bool {
+        self.version == TRANSCRIPT_STATE_VERSION
+    }
 }
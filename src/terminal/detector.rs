@@ -3,19 +3,26 @@
 //! Detects terminal capabilities including color support, emoji support,
 //! and Nerd Font support.
 
-use std::env;
-
 use crate::components::{ColorSupport, TerminalCapabilities};
 use crate::config::AutoDetect;
+use crate::utils::client_env::ClientEnv;
 
 /// Terminal detector for capability detection
-pub struct TerminalDetector;
+pub struct TerminalDetector {
+    env: ClientEnv,
+}
 
 impl TerminalDetector {
-    /// Create a new terminal detector
+    /// Create a terminal detector reading the process environment
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_env(ClientEnv::default())
+    }
+
+    /// Create a terminal detector reading `env` (a daemon client's environment)
     #[must_use]
-    pub const fn new() -> Self {
-        Self
+    pub const fn with_env(env: ClientEnv) -> Self {
+        Self { env }
     }
 
     /// Detect terminal capabilities
@@ -35,6 +42,7 @@ impl TerminalDetector {
                 color_support: ColorSupport::None,
                 supports_emoji: false,
                 supports_nerd_font: false,
+                ..TerminalCapabilities::default()
             };
         }
 
@@ -42,7 +50,7 @@ impl TerminalDetector {
         let color_support = if force_nerd_font || force_emoji {
             ColorSupport::TrueColor // If we're forcing special fonts, assume full color support
         } else {
-            Self::detect_color_support(enable_colors)
+            self.detect_color_support(enable_colors)
         };
 
         let supports_emoji = if force_emoji {
@@ -50,66 +58,99 @@ impl TerminalDetector {
         } else if force_nerd_font {
             false // Nerd Font takes precedence
         } else {
-            Self::detect_emoji_support(enable_emoji)
+            self.detect_emoji_support(enable_emoji)
         };
 
         let supports_nerd_font = if force_nerd_font {
             true
         } else {
-            Self::detect_nerd_font_support(enable_nerd_font)
+            self.detect_nerd_font_support(enable_nerd_font)
         };
 
         // Debug output to help troubleshoot detection issues
-        if std::env::var("DEBUG").is_ok() {
+        if self.env.var("DEBUG").is_ok() {
             eprintln!("[调试] 终端能力检测结果:");
             eprintln!("  - color_support: {color_support:?}");
             eprintln!("  - supports_emoji: {supports_emoji}");
             eprintln!("  - supports_nerd_font: {supports_nerd_font}");
-            eprintln!("  - TERM_PROGRAM: {:?}", std::env::var("TERM_PROGRAM"));
-            eprintln!("  - TERM: {:?}", std::env::var("TERM"));
-            eprintln!("  - COLORTERM: {:?}", std::env::var("COLORTERM"));
+            eprintln!("  - TERM_PROGRAM: {:?}", self.env.var("TERM_PROGRAM"));
+            eprintln!("  - TERM: {:?}", self.env.var("TERM"));
+            eprintln!("  - COLORTERM: {:?}", self.env.var("COLORTERM"));
         }
 
         TerminalCapabilities {
             color_support,
             supports_emoji,
             supports_nerd_font,
+            ..TerminalCapabilities::default()
+        }
+    }
+
+    /// Detect the columns available to the statusline
+    ///
+    /// A non-zero `override_width` wins, then `COLUMNS`, then the size of the
+    /// controlling tty. Returns `None` when the width cannot be determined.
+    #[must_use]
+    pub fn detect_width(&self, override_width: u16) -> Option<usize> {
+        if override_width > 0 {
+            return Some(usize::from(override_width));
+        }
+
+        if let Some(columns) = self
+            .env
+            .var("COLUMNS")
+            .ok()
+            .and_then(|value| value.trim().parse::<usize>().ok())
+            .filter(|columns| *columns > 0)
+        {
+            return Some(columns);
         }
+
+        // 转发来的环境属于另一个进程,这里的 tty 不是客户端的
+        if !self.env.is_process() {
+            return None;
+        }
+
+        // stdout 被 Claude Code 管道接管,window_size 会改查 /dev/tty
+        crossterm::terminal::window_size()
+            .ok()
+            .map(|size| usize::from(size.columns))
+            .filter(|columns| *columns > 0)
     }
 
     /// Detect color support level
-    fn detect_color_support(enable_colors: &AutoDetect) -> ColorSupport {
+    fn detect_color_support(&self, enable_colors: &AutoDetect) -> ColorSupport {
         match enable_colors {
             AutoDetect::Bool(false) => ColorSupport::None,
             AutoDetect::Bool(true) => ColorSupport::TrueColor, // Explicit enable assumes full support
             AutoDetect::Auto(_) => {
                 // Auto-detect based on environment
-                Self::detect_color_level()
+                self.detect_color_level()
             }
         }
     }
 
     /// Detect the actual color support level from environment
-    fn detect_color_level() -> ColorSupport {
+    fn detect_color_level(&self) -> ColorSupport {
         // Check NO_COLOR env var first (https://no-color.org/)
-        if env::var("NO_COLOR").is_ok() {
+        if self.env.var("NO_COLOR").is_ok() {
             return ColorSupport::None;
         }
 
         // Check COLORTERM for truecolor support
-        if let Ok(colorterm) = env::var("COLORTERM") {
+        if let Ok(colorterm) = self.env.var("COLORTERM") {
             if colorterm == "truecolor" || colorterm == "24bit" {
                 return ColorSupport::TrueColor;
             }
         }
 
         // Check for Windows Terminal (supports truecolor)
-        if env::var("WT_SESSION").is_ok() {
+        if self.env.var("WT_SESSION").is_ok() {
             return ColorSupport::TrueColor;
         }
 
         // Check TERM_PROGRAM for known truecolor terminals
-        if let Ok(term_program) = env::var("TERM_PROGRAM") {
+        if let Ok(term_program) = self.env.var("TERM_PROGRAM") {
             match term_program.as_str() {
                 "iTerm.app" | "Hyper" | "vscode" => return ColorSupport::TrueColor,
                 "Apple_Terminal" => return ColorSupport::Extended256, // macOS Terminal: 256 only
@@ -118,7 +159,7 @@ impl TerminalDetector {
         }
 
         // Check for modern terminals that support truecolor
-        if let Ok(term) = env::var("TERM") {
+        if let Ok(term) = self.env.var("TERM") {
             // Terminals known to support truecolor
             if term.contains("kitty")
                 || term.contains("alacritty")
@@ -146,16 +187,17 @@ impl TerminalDetector {
         }
 
         // Check for GNOME Terminal and Konsole (both support truecolor)
-        if env::var("GNOME_TERMINAL_SERVICE").is_ok() || env::var("KONSOLE_VERSION").is_ok() {
+        if self.env.var("GNOME_TERMINAL_SERVICE").is_ok() || self.env.var("KONSOLE_VERSION").is_ok()
+        {
             return ColorSupport::TrueColor;
         }
 
         // Check if running in CI/CD environments (usually support 256 colors)
-        if env::var("CI").is_ok()
-            || env::var("GITHUB_ACTIONS").is_ok()
-            || env::var("GITLAB_CI").is_ok()
-            || env::var("BUILDKITE").is_ok()
-            || env::var("CIRCLECI").is_ok()
+        if self.env.var("CI").is_ok()
+            || self.env.var("GITHUB_ACTIONS").is_ok()
+            || self.env.var("GITLAB_CI").is_ok()
+            || self.env.var("BUILDKITE").is_ok()
+            || self.env.var("CIRCLECI").is_ok()
         {
             return ColorSupport::Extended256;
         }
@@ -168,7 +210,7 @@ impl TerminalDetector {
         #[cfg(not(unix))]
         {
             // On Windows, check if we're in ConEmu
-            if env::var("ConEmuPID").is_ok() {
+            if self.env.var("ConEmuPID").is_ok() {
                 ColorSupport::TrueColor
             } else {
                 ColorSupport::Basic16
@@ -177,33 +219,33 @@ impl TerminalDetector {
     }
 
     /// Detect emoji support
-    fn detect_emoji_support(enable_emoji: &AutoDetect) -> bool {
+    fn detect_emoji_support(&self, enable_emoji: &AutoDetect) -> bool {
         match enable_emoji {
             AutoDetect::Bool(false) => false,
             AutoDetect::Bool(true) => true,
             AutoDetect::Auto(_) => {
                 // Auto-detect based on terminal type
-                Self::check_emoji_capable_terminal()
+                self.check_emoji_capable_terminal()
             }
         }
     }
 
     /// Detect Nerd Font support
-    fn detect_nerd_font_support(enable_nerd_font: &AutoDetect) -> bool {
+    fn detect_nerd_font_support(&self, enable_nerd_font: &AutoDetect) -> bool {
         match enable_nerd_font {
             AutoDetect::Bool(false) => false,
             AutoDetect::Bool(true) => true,
             AutoDetect::Auto(_) => {
                 // Auto-detect based on font environment
-                Self::check_nerd_font_env()
+                self.check_nerd_font_env()
             }
         }
     }
 
     /// Check if terminal supports emoji
-    fn check_emoji_capable_terminal() -> bool {
+    fn check_emoji_capable_terminal(&self) -> bool {
         // Check terminal type
-        if let Ok(term_program) = env::var("TERM_PROGRAM") {
+        if let Ok(term_program) = self.env.var("TERM_PROGRAM") {
             match term_program.as_str() {
                 "iTerm.app" | "Terminal.app" | "Hyper" | "vscode" => return true,
                 "tmux" => {
@@ -215,12 +257,12 @@ impl TerminalDetector {
         }
 
         // Check for Windows Terminal
-        if env::var("WT_SESSION").is_ok() {
+        if self.env.var("WT_SESSION").is_ok() {
             return true;
         }
 
         // Check for modern terminal emulators
-        if let Ok(term) = env::var("TERM") {
+        if let Ok(term) = self.env.var("TERM") {
             if term.contains("kitty")
                 || term.contains("alacritty")
                 || term.contains("wezterm")
@@ -231,12 +273,13 @@ impl TerminalDetector {
         }
 
         // Check for GNOME Terminal and Konsole
-        if env::var("GNOME_TERMINAL_SERVICE").is_ok() || env::var("KONSOLE_VERSION").is_ok() {
+        if self.env.var("GNOME_TERMINAL_SERVICE").is_ok() || self.env.var("KONSOLE_VERSION").is_ok()
+        {
             return true;
         }
 
         // Check locale for UTF-8 support (necessary for emoji)
-        if let Ok(lang) = env::var("LANG") {
+        if let Ok(lang) = self.env.var("LANG") {
             if lang.to_uppercase().contains("UTF-8") || lang.to_uppercase().contains("UTF8") {
                 // If we have UTF-8 locale, assume basic emoji support
                 return true;
@@ -248,15 +291,15 @@ impl TerminalDetector {
     }
 
     /// Check if Nerd Font is likely installed
-    fn check_nerd_font_env() -> bool {
+    fn check_nerd_font_env(&self) -> bool {
         // Check for explicit Nerd Font environment variable
-        if env::var("NERD_FONT").is_ok() || env::var("NERD_FONTS").is_ok() {
+        if self.env.var("NERD_FONT").is_ok() || self.env.var("NERD_FONTS").is_ok() {
             return true;
         }
 
         // Check terminal font settings (terminal-specific)
         // This is a heuristic and may not be 100% accurate
-        if let Ok(term_font) = env::var("TERMINAL_FONT") {
+        if let Ok(term_font) = self.env.var("TERMINAL_FONT") {
             if term_font.to_lowercase().contains("nerd")
                 || term_font.contains("NF")
                 || term_font.contains("Powerline")
@@ -266,7 +309,7 @@ impl TerminalDetector {
         }
 
         // Check for popular terminal emulators that commonly support Nerd Fonts
-        if let Ok(term_program) = env::var("TERM_PROGRAM") {
+        if let Ok(term_program) = self.env.var("TERM_PROGRAM") {
             match term_program.as_str() {
                 "iTerm.app" => {
                     // iTerm2 users often have Nerd Fonts installed
@@ -277,11 +320,11 @@ impl TerminalDetector {
                 "vscode" => {
                     // VSCode terminals may have Nerd Fonts, check for indicators
                     // Priority: Nerd Font > Emoji for better visual consistency
-                    if env::var("VSCODE_NERD_FONT").is_ok() {
+                    if self.env.var("VSCODE_NERD_FONT").is_ok() {
                         return true;
                     }
                     // Check if LC_TERMINAL explicitly set (might indicate font config)
-                    if let Ok(lc_term) = env::var("LC_TERMINAL") {
+                    if let Ok(lc_term) = self.env.var("LC_TERMINAL") {
                         if lc_term.to_lowercase().contains("nerd") {
                             return true;
                         }
@@ -294,7 +337,7 @@ impl TerminalDetector {
         }
 
         // Check for modern terminal emulators that bundle Nerd Fonts
-        if let Ok(term) = env::var("TERM") {
+        if let Ok(term) = self.env.var("TERM") {
             if term.contains("kitty") || term.contains("wezterm") {
                 // Kitty and WezTerm users typically have Nerd Fonts
                 return true;
@@ -315,6 +358,7 @@ impl Default for TerminalDetector {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
 
     #[test]
     fn test_force_text_mode() {
@@ -399,6 +443,29 @@ mod tests {
         assert!(caps.supports_nerd_font);
     }
 
+    #[test]
+    fn test_forwarded_env_drives_detection() {
+        let detector = TerminalDetector::with_env(ClientEnv::forwarded(HashMap::from([
+            ("NO_COLOR".to_string(), "1".to_string()),
+            ("COLUMNS".to_string(), "120".to_string()),
+        ])));
+        let caps = detector.detect(
+            &AutoDetect::Auto("auto".to_string()),
+            &AutoDetect::Bool(false),
+            &AutoDetect::Bool(false),
+            false,
+            false,
+            false,
+        );
+
+        assert_eq!(caps.color_support, ColorSupport::None);
+        assert_eq!(detector.detect_width(0), Some(120));
+
+        // 转发的环境没有 COLUMNS 时不去问 daemon 自己的 tty
+        let detector = TerminalDetector::with_env(ClientEnv::forwarded(HashMap::new()));
+        assert_eq!(detector.detect_width(0), None);
+    }
+
     #[test]
     fn test_color_support_methods() {
         assert!(!ColorSupport::None.has_colors());
@@ -5,7 +5,7 @@
 
 use anyhow::Result;
 
-use super::{ansi_bg, ansi_fg, colorize_segment, reapply_colors, ThemeRenderer, ANSI_RESET};
+use super::{ansi_bg, ansi_fg, colorize_segment, segment_content, OutputTarget, ThemeRenderer};
 use crate::components::{ComponentOutput, RenderContext};
 
 pub struct CapsuleThemeRenderer;
@@ -24,6 +24,7 @@ impl CapsuleThemeRenderer {
         supports_colors: bool,
     ) -> String {
         let style = &context.config.style;
+        let target = context.terminal.output_target;
         let (separator_core, apply_padding) = if style.separator.is_empty() {
             (" | ".trim(), true)
         } else if style.separator == "|" {
@@ -45,6 +46,7 @@ impl CapsuleThemeRenderer {
             raw_separator.as_str(),
             Some(style.separator_color.as_str()),
             supports_colors,
+            target,
         );
 
         let mut parts = Vec::new();
@@ -56,6 +58,7 @@ impl CapsuleThemeRenderer {
                     icon,
                     component.icon_color.as_deref(),
                     supports_colors,
+                    target,
                 ));
                 if !component.text.is_empty() {
                     part.push(' ');
@@ -66,6 +69,7 @@ impl CapsuleThemeRenderer {
                 &component.text,
                 component.text_color.as_deref(),
                 supports_colors,
+                target,
             ));
 
             if !part.is_empty() {
@@ -105,44 +109,38 @@ impl CapsuleThemeRenderer {
         color: &str,
         preserve_internal: bool,
         fg_color: &str,
+        target: OutputTarget,
     ) -> String {
         let mut segment = String::new();
 
-        if let Some(fg) = ansi_fg(color).as_ref() {
+        if let Some(fg) = ansi_fg(color, target).as_ref() {
             segment.push_str(fg);
         }
         segment.push(Self::LEFT_CAP);
-        segment.push_str(ANSI_RESET);
+        segment.push_str(&target.reset());
 
-        let bg_seq = ansi_bg(color);
-        let fg_seq = ansi_fg(fg_color);
-
-        if let Some(bg) = bg_seq.as_ref() {
+        if let Some(bg) = ansi_bg(color, target).as_ref() {
             segment.push_str(bg);
         }
-        if let Some(fg) = fg_seq.as_ref() {
+        if let Some(fg) = ansi_fg(fg_color, target).as_ref() {
             segment.push_str(fg);
         }
         segment.push(' ');
-        if preserve_internal {
-            if let (Some(bg), Some(fg)) = (bg_seq.as_ref(), fg_seq.as_ref()) {
-                segment.push_str(&reapply_colors(content, bg, fg));
-            } else if let Some(bg) = bg_seq.as_ref() {
-                segment.push_str(&reapply_colors(content, bg, ""));
-            } else {
-                segment.push_str(content);
-            }
-        } else {
-            segment.push_str(content);
-        }
+        segment.push_str(&segment_content(
+            content,
+            color,
+            fg_color,
+            preserve_internal,
+            target,
+        ));
         segment.push(' ');
-        segment.push_str(ANSI_RESET);
+        segment.push_str(&target.reset());
 
-        if let Some(fg) = ansi_fg(color).as_ref() {
+        if let Some(fg) = ansi_fg(color, target).as_ref() {
             segment.push_str(fg);
         }
         segment.push(Self::RIGHT_CAP);
-        segment.push_str(ANSI_RESET);
+        segment.push_str(&target.reset());
 
         segment
     }
@@ -194,6 +192,7 @@ impl ThemeRenderer for CapsuleThemeRenderer {
                 &color,
                 preserve,
                 fg_color,
+                context.terminal.output_target,
             ));
         }
 
@@ -238,6 +237,7 @@ mod tests {
                 },
                 supports_emoji: true,
                 supports_nerd_font: nerd_font,
+                ..TerminalCapabilities::default()
             },
         }
     }
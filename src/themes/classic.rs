@@ -44,6 +44,7 @@ impl ThemeRenderer for ClassicThemeRenderer {
 
         // Determine separator string (respect before/after spacing)
         let style = &context.config.style;
+        let target = context.terminal.output_target;
         let (separator_core, apply_padding) = if style.separator.is_empty() {
             (self.separator.trim(), true)
         } else if style.separator == "|" {
@@ -63,6 +64,7 @@ impl ThemeRenderer for ClassicThemeRenderer {
             &raw_separator,
             Some(style.separator_color.as_str()),
             supports_colors,
+            target,
         );
 
         // Collect visible components
@@ -81,6 +83,7 @@ impl ThemeRenderer for ClassicThemeRenderer {
                     icon,
                     component.icon_color.as_deref(),
                     supports_colors,
+                    target,
                 ));
                 if !component.text.is_empty() {
                     part.push(' ');
@@ -92,6 +95,7 @@ impl ThemeRenderer for ClassicThemeRenderer {
                 &component.text,
                 component.text_color.as_deref(),
                 supports_colors,
+                target,
             ));
 
             if !part.is_empty() {
@@ -216,4 +220,49 @@ mod tests {
         assert_eq!(result, "One / Two");
         Ok(())
     }
+
+    #[test]
+    fn test_classic_theme_fits_terminal_width() -> TestResult {
+        let theme = ClassicThemeRenderer::new();
+        let ctx = create_test_context();
+
+        let segment = |text: &str, priority: u8, min_width: usize| {
+            let mut output = ComponentOutput::new(text.to_string());
+            output.priority = priority;
+            output.min_width = min_width;
+            output
+        };
+        let components = vec![
+            segment("claude-statusline", 70, 8),
+            segment("Sonnet", 90, 0),
+            segment("$1.20 (42%)", 40, 0),
+        ];
+        let full = "claude-statusline | Sonnet | $1.20 (42%)";
+
+        assert_eq!(theme.render_within(&components, &[], &ctx, None)?, full);
+        assert_eq!(theme.render_within(&components, &[], &ctx, Some(80))?, full);
+        // 先丢弃优先级最低的 usage
+        assert_eq!(
+            theme.render_within(&components, &[], &ctx, Some(30))?,
+            "claude-statusline | Sonnet"
+        );
+        // 再把 project 缩写到刚好放下,但不低于 min_width
+        assert_eq!(
+            theme.render_within(&components, &[], &ctx, Some(22))?,
+            "claude-statu… | Sonnet"
+        );
+        assert_eq!(
+            theme.render_within(&components, &[], &ctx, Some(12))?,
+            "Sonnet"
+        );
+
+        let mut wide = components;
+        wide[0].max_width = 6;
+        assert_eq!(theme.render_within(&wide, &[], &ctx, None)?, full);
+        assert_eq!(
+            theme.render_within(&wide, &[], &ctx, Some(80))?,
+            "claud… | Sonnet | $1.20 (42%)"
+        );
+        Ok(())
+    }
 }
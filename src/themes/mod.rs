@@ -3,17 +3,19 @@
 //! Provides different visual themes for the statusline.
 
 use anyhow::Result;
-use crossterm::style::{Color, Stylize};
 
 use crate::components::{ColorSupport, ComponentOutput, RenderContext};
+use crate::utils::width;
 
 pub mod capsule;
 pub mod classic;
 pub mod powerline;
+pub mod target;
 
 pub use capsule::CapsuleThemeRenderer;
 pub use classic::ClassicThemeRenderer;
 pub use powerline::PowerlineThemeRenderer;
+pub use target::OutputTarget;
 
 fn clamp_component(value: f32) -> u8 {
     #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
@@ -31,83 +33,90 @@ fn lighten(color: (u8, u8, u8), amount: f32) -> (u8, u8, u8) {
     (lerp(r), lerp(g), lerp(b))
 }
 
-/// Apply ANSI colors to a segment if supported
+/// Color a segment for the output target if supported
+///
+/// The segment itself is escaped for the target as well, so it is safe to
+/// embed whether or not a color applies.
 pub(crate) fn colorize_segment(
     segment: &str,
     color_name: Option<&str>,
     supports_colors: bool,
+    target: OutputTarget,
 ) -> String {
-    if !supports_colors {
-        return segment.to_string();
-    }
-
-    color_name.and_then(parse_color).map_or_else(
-        || segment.to_string(),
-        |color| segment.with(color).to_string(),
+    let params = color_name
+        .filter(|_| supports_colors)
+        .and_then(parse_color)
+        .map(|index| format!("38;5;{index}"));
+
+    params.map_or_else(
+        || target.apply(segment),
+        |params| {
+            format!(
+                "{}{}{}",
+                target.sgr(&params),
+                target.apply(segment),
+                target.sgr("39")
+            )
+        },
     )
 }
 
 pub(crate) const ANSI_RESET: &str = "\x1b[0m";
 
-/// Generate foreground ANSI escape sequence based on color support level
-pub(crate) fn ansi_fg_with_support(color: &str, color_support: ColorSupport) -> Option<String> {
+/// Generate a foreground color sequence for the target, downgraded to the color support level
+pub(crate) fn ansi_fg_with_support(
+    color: &str,
+    color_support: ColorSupport,
+    target: OutputTarget,
+) -> Option<String> {
     let rgb = resolve_color(color)?;
-    Some(format_fg_color(rgb, color_support))
+    Some(
+        format_fg_params(rgb, color_support).map_or_else(String::new, |params| target.sgr(&params)),
+    )
 }
 
-/// Generate background ANSI escape sequence based on color support level
-pub(crate) fn ansi_bg_with_support(color: &str, color_support: ColorSupport) -> Option<String> {
+/// Generate a background color sequence for the target, downgraded to the color support level
+pub(crate) fn ansi_bg_with_support(
+    color: &str,
+    color_support: ColorSupport,
+    target: OutputTarget,
+) -> Option<String> {
     let rgb = resolve_color(color)?;
-    Some(format_bg_color(rgb, color_support))
+    Some(
+        format_bg_params(rgb, color_support).map_or_else(String::new, |params| target.sgr(&params)),
+    )
 }
 
 /// Legacy function - assumes `TrueColor` support
-pub(crate) fn ansi_fg(color: &str) -> Option<String> {
-    ansi_fg_with_support(color, ColorSupport::TrueColor)
+pub(crate) fn ansi_fg(color: &str, target: OutputTarget) -> Option<String> {
+    ansi_fg_with_support(color, ColorSupport::TrueColor, target)
 }
 
 /// Legacy function - assumes `TrueColor` support
-pub(crate) fn ansi_bg(color: &str) -> Option<String> {
-    ansi_bg_with_support(color, ColorSupport::TrueColor)
+pub(crate) fn ansi_bg(color: &str, target: OutputTarget) -> Option<String> {
+    ansi_bg_with_support(color, ColorSupport::TrueColor, target)
 }
 
-/// Format foreground color based on support level
-fn format_fg_color(rgb: (u8, u8, u8), color_support: ColorSupport) -> String {
+/// SGR parameters of a foreground color at the given support level
+fn format_fg_params(rgb: (u8, u8, u8), color_support: ColorSupport) -> Option<String> {
     let (r, g, b) = rgb;
     match color_support {
-        ColorSupport::None => String::new(),
-        ColorSupport::Basic16 => {
-            let ansi = rgb_to_ansi16(r, g, b);
-            format!("\x1b[{ansi}m")
-        }
-        ColorSupport::Extended256 => {
-            let code = rgb_to_ansi256(r, g, b);
-            format!("\x1b[38;5;{code}m")
-        }
-        ColorSupport::TrueColor => {
-            format!("\x1b[38;2;{r};{g};{b}m")
-        }
+        ColorSupport::None => None,
+        ColorSupport::Basic16 => Some(rgb_to_ansi16(r, g, b).to_string()),
+        ColorSupport::Extended256 => Some(format!("38;5;{}", rgb_to_ansi256(r, g, b))),
+        ColorSupport::TrueColor => Some(format!("38;2;{r};{g};{b}")),
     }
 }
 
-/// Format background color based on support level
-fn format_bg_color(rgb: (u8, u8, u8), color_support: ColorSupport) -> String {
+/// SGR parameters of a background color at the given support level
+fn format_bg_params(rgb: (u8, u8, u8), color_support: ColorSupport) -> Option<String> {
     let (r, g, b) = rgb;
     match color_support {
-        ColorSupport::None => String::new(),
-        ColorSupport::Basic16 => {
-            let ansi = rgb_to_ansi16(r, g, b);
-            // Convert foreground code to background code (add 10)
-            let bg_code = ansi + 10;
-            format!("\x1b[{bg_code}m")
-        }
-        ColorSupport::Extended256 => {
-            let code = rgb_to_ansi256(r, g, b);
-            format!("\x1b[48;5;{code}m")
-        }
-        ColorSupport::TrueColor => {
-            format!("\x1b[48;2;{r};{g};{b}m")
-        }
+        ColorSupport::None => None,
+        // Convert foreground code to background code (add 10)
+        ColorSupport::Basic16 => Some((rgb_to_ansi16(r, g, b) + 10).to_string()),
+        ColorSupport::Extended256 => Some(format!("48;5;{}", rgb_to_ansi256(r, g, b))),
+        ColorSupport::TrueColor => Some(format!("48;2;{r};{g};{b}")),
     }
 }
 
@@ -226,6 +235,32 @@ pub(crate) fn reapply_colors(content: &str, bg_seq: &str, fg_seq: &str) -> Strin
     processed
 }
 
+/// Segment content escaped for the target
+///
+/// With `preserve_internal` the content keeps its own ANSI colors; the
+/// segment background and foreground are restored after each of its resets
+/// before the whole content is translated for the target.
+pub(crate) fn segment_content(
+    content: &str,
+    bg_color: &str,
+    fg_color: &str,
+    preserve_internal: bool,
+    target: OutputTarget,
+) -> String {
+    if !preserve_internal {
+        return target.apply(content);
+    }
+
+    let content = match (
+        ansi_bg(bg_color, OutputTarget::Ansi),
+        ansi_fg(fg_color, OutputTarget::Ansi),
+    ) {
+        (Some(bg), fg) => reapply_colors(content, &bg, fg.as_deref().unwrap_or_default()),
+        (None, _) => content.to_string(),
+    };
+    target.apply(&content)
+}
+
 fn resolve_color(name: &str) -> Option<(u8, u8, u8)> {
     let normalized = name.trim().to_lowercase();
     if normalized.is_empty() {
@@ -282,24 +317,28 @@ fn resolve_color(name: &str) -> Option<(u8, u8, u8)> {
     Some(nord)
 }
 
-fn parse_color(name: &str) -> Option<Color> {
+/// Map a named color to its 256-color palette index
+///
+/// 直接给出调色板序号而不走 crossterm 的 `Colored`:后者按进程的 `NO_COLOR`
+/// 决定输出,在 daemon 里读到的是 daemon 自己的环境而不是客户端的。
+fn parse_color(name: &str) -> Option<u8> {
     match name.trim().to_lowercase().as_str() {
-        "black" => Some(Color::Black),
-        "red" => Some(Color::Red),
-        "green" => Some(Color::Green),
-        "yellow" | "orange" | "bright_orange" => Some(Color::Yellow),
-        "blue" => Some(Color::Blue),
-        "magenta" | "purple" | "pink" | "bright_pink" => Some(Color::Magenta),
-        "cyan" => Some(Color::Cyan),
-        "white" | "bright_white" => Some(Color::White),
-        "gray" | "grey" => Some(Color::Grey),
-        "bright_black" => Some(Color::DarkGrey),
-        "bright_red" => Some(Color::DarkRed),
-        "bright_green" => Some(Color::DarkGreen),
-        "bright_yellow" => Some(Color::DarkYellow),
-        "bright_blue" => Some(Color::DarkBlue),
-        "bright_magenta" | "bright_purple" => Some(Color::DarkMagenta),
-        "bright_cyan" => Some(Color::DarkCyan),
+        "black" => Some(0),
+        "bright_red" => Some(1),
+        "bright_green" => Some(2),
+        "bright_yellow" => Some(3),
+        "bright_blue" => Some(4),
+        "bright_magenta" | "bright_purple" => Some(5),
+        "bright_cyan" => Some(6),
+        "gray" | "grey" => Some(7),
+        "bright_black" => Some(8),
+        "red" => Some(9),
+        "green" => Some(10),
+        "yellow" | "orange" | "bright_orange" => Some(11),
+        "blue" => Some(12),
+        "magenta" | "purple" | "pink" | "bright_pink" => Some(13),
+        "cyan" => Some(14),
+        "white" | "bright_white" => Some(15),
         _ => None,
     }
 }
@@ -349,6 +388,89 @@ pub trait ThemeRenderer: Send + Sync {
 
     /// Get theme name
     fn name(&self) -> &str;
+
+    /// Render components so the line fits in `max_width` columns
+    ///
+    /// Text wider than a component's `max_width` is abbreviated up front.
+    /// While the line is still too wide, the lowest-priority segment (the
+    /// rightmost one on ties) is abbreviated down to its `min_width`, then
+    /// dropped. `None` renders without a width limit. Widths are measured on
+    /// the ANSI rendering; other targets render once more after fitting.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the renderer fails to format the statusline.
+    fn render_within(
+        &self,
+        components: &[ComponentOutput],
+        colors: &[String],
+        context: &RenderContext,
+        max_width: Option<usize>,
+    ) -> Result<String> {
+        let Some(max_width) = max_width else {
+            return self.render(components, colors, context);
+        };
+
+        // tmux / shell 的转义不是 ANSI,按 ANSI 渲染来量宽度
+        let target = context.terminal.output_target;
+        let mut measure_context = context.clone();
+        measure_context.terminal.output_target = OutputTarget::Ansi;
+
+        let mut components = components.to_vec();
+        let mut colors = colors.to_vec();
+        for component in &mut components {
+            if component.max_width > 0 {
+                component.text = width::truncate_ansi(&component.text, component.max_width);
+            }
+        }
+
+        loop {
+            let line = self.render(&components, &colors, &measure_context)?;
+            let line_width = width::display_width(&line);
+            let fitted = line_width <= max_width;
+            let idx = lowest_priority_index(&components).filter(|_| !fitted);
+            let Some(idx) = idx else {
+                return if target == OutputTarget::Ansi {
+                    Ok(line)
+                } else {
+                    self.render(&components, &colors, context)
+                };
+            };
+
+            let component = &mut components[idx];
+            let text_width = width::display_width(&component.text);
+            let min_width = component.min_width;
+            if min_width > 0 && text_width > min_width {
+                let target = text_width
+                    .saturating_sub(line_width - max_width)
+                    .max(min_width);
+                component.text = width::truncate_ansi(&component.text, target);
+            } else {
+                // colors 只为带组件名的输出生成,按同样规则对齐
+                if component.component_name.is_some() {
+                    let color_idx = components[..idx]
+                        .iter()
+                        .filter(|other| other.component_name.is_some())
+                        .count();
+                    if color_idx < colors.len() {
+                        colors.remove(color_idx);
+                    }
+                }
+                components.remove(idx);
+            }
+        }
+    }
+}
+
+/// Index of the visible component that gives way first
+fn lowest_priority_index(components: &[ComponentOutput]) -> Option<usize> {
+    components
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, component)| component.visible)
+        .min_by_key(|(_, component)| component.priority)
+        .map(|(idx, _)| idx)
 }
 
 /// Create a theme renderer based on the theme name
@@ -5,7 +5,7 @@
 
 use anyhow::Result;
 
-use super::{ansi_bg, ansi_fg, colorize_segment, reapply_colors, ThemeRenderer, ANSI_RESET};
+use super::{ansi_bg, ansi_fg, colorize_segment, segment_content, OutputTarget, ThemeRenderer};
 use crate::components::{ComponentOutput, RenderContext};
 
 /// Powerline theme renderer
@@ -26,6 +26,7 @@ impl PowerlineThemeRenderer {
         supports_colors: bool,
     ) -> String {
         let style = &context.config.style;
+        let target = context.terminal.output_target;
         let (separator_core, apply_padding) = if style.separator.is_empty() {
             (" | ".trim(), true)
         } else if style.separator == "|" {
@@ -46,6 +47,7 @@ impl PowerlineThemeRenderer {
             raw_separator.as_str(),
             Some(style.separator_color.as_str()),
             supports_colors,
+            target,
         );
 
         let mut parts = Vec::new();
@@ -57,6 +59,7 @@ impl PowerlineThemeRenderer {
                     icon,
                     component.icon_color.as_deref(),
                     supports_colors,
+                    target,
                 ));
                 if !component.text.is_empty() {
                     part.push(' ');
@@ -67,6 +70,7 @@ impl PowerlineThemeRenderer {
                 &component.text,
                 component.text_color.as_deref(),
                 supports_colors,
+                target,
             ));
 
             if !part.is_empty() {
@@ -125,46 +129,36 @@ impl PowerlineThemeRenderer {
         next_bg: Option<&str>,
         preserve_internal: bool,
         fg_color: &str,
+        target: OutputTarget,
     ) -> String {
         let mut segment = String::new();
 
-        let bg_seq = ansi_bg(bg_color);
-        let fg_seq = ansi_fg(fg_color);
-
-        if let Some(bg) = bg_seq.as_ref() {
+        if let Some(bg) = ansi_bg(bg_color, target).as_ref() {
             segment.push_str(bg);
         }
-        if let Some(fg) = fg_seq.as_ref() {
+        if let Some(fg) = ansi_fg(fg_color, target).as_ref() {
             segment.push_str(fg);
         }
 
         segment.push(' ');
-        if preserve_internal {
-            if let (Some(bg), Some(fg)) = (bg_seq.as_ref(), fg_seq.as_ref()) {
-                segment.push_str(&reapply_colors(content, bg, fg));
-            } else if let Some(bg) = bg_seq.as_ref() {
-                segment.push_str(&reapply_colors(content, bg, ""));
-            } else {
-                segment.push_str(content);
-            }
-        } else {
-            segment.push_str(content);
-        }
+        segment.push_str(&segment_content(
+            content,
+            bg_color,
+            fg_color,
+            preserve_internal,
+            target,
+        ));
         segment.push(' ');
 
-        segment.push_str(ANSI_RESET);
-        if let Some(next) = next_bg {
-            if let Some(bg) = ansi_bg(next).as_ref() {
-                segment.push_str(bg);
-            }
-            if let Some(fg) = ansi_fg(bg_color).as_ref() {
-                segment.push_str(fg);
-            }
-        } else if let Some(fg) = ansi_fg(bg_color).as_ref() {
+        segment.push_str(&target.reset());
+        if let Some(bg) = next_bg.and_then(|next| ansi_bg(next, target)) {
+            segment.push_str(&bg);
+        }
+        if let Some(fg) = ansi_fg(bg_color, target).as_ref() {
             segment.push_str(fg);
         }
         segment.push(Self::POWERLINE_SEPARATOR);
-        segment.push_str(ANSI_RESET);
+        segment.push_str(&target.reset());
 
         segment
     }
@@ -225,30 +219,32 @@ impl ThemeRenderer for PowerlineThemeRenderer {
         let fg_color = &context.config.themes.powerline.fg;
 
         // Prepend start symbol (powerline reverse triangle)
+        let target = context.terminal.output_target;
         let mut rendered = String::new();
         if let Some((_, Some(color), _)) = prepared.iter().find(|(_, color, _)| color.is_some()) {
-            if let Some(fg) = ansi_fg(color).as_ref() {
+            if let Some(fg) = ansi_fg(color, target).as_ref() {
                 rendered.push_str(fg);
             }
             rendered.push(Self::POWERLINE_START);
-            rendered.push_str(ANSI_RESET);
+            rendered.push_str(&target.reset());
         }
 
         for idx in 0..prepared.len() {
-            let (ref segment_content, ref color_opt, preserve_internal) = prepared[idx];
+            let (ref segment_text, ref color_opt, preserve_internal) = prepared[idx];
             if color_opt.is_none() {
-                rendered.push_str(segment_content);
+                rendered.push_str(&target.apply(segment_text));
                 continue;
             }
 
             if let Some(color) = color_opt.as_deref() {
                 let next_color = Self::next_visible_color(&prepared, idx);
                 rendered.push_str(&Self::render_segment(
-                    segment_content,
+                    segment_text,
                     color,
                     next_color.as_deref(),
                     preserve_internal,
                     fg_color,
+                    target,
                 ));
             }
         }
@@ -294,6 +290,7 @@ mod tests {
                 },
                 supports_emoji: true,
                 supports_nerd_font: nerd_font,
+                ..TerminalCapabilities::default()
             },
         }
     }
@@ -330,4 +327,48 @@ mod tests {
         assert_eq!(result, "📁 Project | 🌿 main");
         Ok(())
     }
+
+    #[test]
+    fn test_powerline_theme_emits_tmux_styles() -> TestResult {
+        let theme = PowerlineThemeRenderer::new();
+        let mut ctx = create_test_context(true, true);
+        ctx.terminal.output_target = OutputTarget::Tmux;
+
+        let components = vec![ComponentOutput::new("#1".to_string())];
+        let result = theme.render(&components, &["blue".to_string()], &ctx)?;
+        assert!(!result.contains('\x1b'));
+        assert!(result.contains("#[bg=#81a1c1]#[fg=#eceff4] ##1 #[default]"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_powerline_theme_drops_segment_with_its_color() -> TestResult {
+        let theme = PowerlineThemeRenderer::new();
+        let ctx = create_test_context(true, true);
+
+        let mut usage = ComponentOutput::new("$1.20".to_string()).with_component_name("usage");
+        usage.priority = 10;
+        let components = vec![
+            ComponentOutput::new("Project".to_string()).with_component_name("project"),
+            usage,
+            ComponentOutput::new("main".to_string()).with_component_name("branch"),
+        ];
+        let colors = vec!["blue".to_string(), "red".to_string(), "green".to_string()];
+
+        let full = theme.render_within(&components, &colors, &ctx, None)?;
+        let fitted = theme.render_within(
+            &components,
+            &colors,
+            &ctx,
+            Some(crate::utils::width::display_width(&full) - 1),
+        )?;
+        assert!(!fitted.contains("$1.20"));
+        let expected = theme.render(
+            &[components[0].clone(), components[2].clone()],
+            &["blue".to_string(), "green".to_string()],
+            &ctx,
+        )?;
+        assert_eq!(fitted, expected);
+        Ok(())
+    }
 }
@@ -0,0 +1,272 @@
+//! Output targets for rendered statuslines
+//!
+//! Where the line ends up decides how styling has to look: tmux wants
+//! `#[fg=…]` style directives, while zsh and bash prompts need every escape
+//! marked as zero-width (`%{…%}` / `\[…\]`) or the shell miscounts the prompt
+//! width and wraps the cursor. Themes emit their own colors through
+//! [`OutputTarget::sgr`]; content that arrives with ANSI already baked in
+//! (command output, widget rows) goes through [`OutputTarget::apply`].
+
+use crate::utils::width::{ansi_segments, AnsiSegment};
+
+/// Where a rendered statusline is embedded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputTarget {
+    /// Raw ANSI, as Claude Code and plain terminals expect
+    #[default]
+    Ansi,
+    /// tmux `status-left` / `status-right` format
+    Tmux,
+    /// zsh `PROMPT` / `RPROMPT`
+    Zsh,
+    /// bash `PS1`, set from `PROMPT_COMMAND` so escapes are decoded
+    Bash,
+}
+
+impl OutputTarget {
+    /// One SGR parameter list (`38;2;136;192;208`, `0`, …) in this target's syntax
+    #[must_use]
+    pub fn sgr(self, params: &str) -> String {
+        match self {
+            Self::Ansi => format!("\x1b[{params}m"),
+            Self::Zsh => format!("%{{\x1b[{params}m%}}"),
+            Self::Bash => format!("\\[\x1b[{params}m\\]"),
+            Self::Tmux => {
+                let styles = tmux_styles(params);
+                if styles.is_empty() {
+                    String::new()
+                } else {
+                    format!("#[{}]", styles.join(","))
+                }
+            }
+        }
+    }
+
+    /// Reset every attribute (`ESC[0m` for ANSI)
+    #[must_use]
+    pub fn reset(self) -> String {
+        self.sgr("0")
+    }
+
+    /// Rewrite text that may carry its own ANSI escapes for this target
+    ///
+    /// Printable text is escaped for the target; SGR sequences are translated.
+    /// Shells keep every other escape as a zero-width block, while tmux has no
+    /// way to express non-SGR sequences (OSC hyperlinks, cursor moves) in a
+    /// status line, so it keeps their text and drops the sequence.
+    #[must_use]
+    pub fn apply(self, line: &str) -> String {
+        if self == Self::Ansi {
+            return line.to_string();
+        }
+
+        let mut result = String::with_capacity(line.len() * 2);
+        let mut escapes = String::new();
+        for segment in ansi_segments(line) {
+            match segment {
+                AnsiSegment::Escape(sequence) => escapes.push_str(sequence),
+                AnsiSegment::Text(text) => {
+                    self.push_escapes(&mut result, &escapes);
+                    escapes.clear();
+                    self.push_text(&mut result, text);
+                }
+            }
+        }
+        self.push_escapes(&mut result, &escapes);
+        result
+    }
+
+    /// Emit a run of adjacent escape sequences as one zero-width block
+    fn push_escapes(self, out: &mut String, escapes: &str) {
+        if escapes.is_empty() {
+            return;
+        }
+        match self {
+            Self::Ansi => out.push_str(escapes),
+            Self::Zsh => {
+                out.push_str("%{");
+                out.push_str(escapes);
+                out.push_str("%}");
+            }
+            Self::Bash => {
+                out.push_str("\\[");
+                out.push_str(escapes);
+                out.push_str("\\]");
+            }
+            Self::Tmux => {
+                let styles: Vec<String> = ansi_segments(escapes)
+                    .into_iter()
+                    .filter_map(|segment| match segment {
+                        AnsiSegment::Escape(sequence) => sequence
+                            .strip_prefix("\x1b[")
+                            .and_then(|rest| rest.strip_suffix('m')),
+                        AnsiSegment::Text(_) => None,
+                    })
+                    .flat_map(tmux_styles)
+                    .collect();
+                // OSC 超链接等非 SGR 序列 tmux 无法表达,只保留文本
+                if !styles.is_empty() {
+                    out.push_str("#[");
+                    out.push_str(&styles.join(","));
+                    out.push(']');
+                }
+            }
+        }
+    }
+
+    /// Emit printable text, escaping what the target would interpret
+    fn push_text(self, out: &mut String, text: &str) {
+        for ch in text.chars() {
+            match (self, ch) {
+                (Self::Tmux, '#') => out.push_str("##"),
+                (Self::Zsh, '%') => out.push_str("%%"),
+                // PS1 先解码反斜杠转义再做参数展开/命令替换,两层都要转义
+                (Self::Bash, '\\') => out.push_str("\\\\\\\\"),
+                (Self::Bash, '$' | '`') => {
+                    out.push_str("\\\\");
+                    out.push(ch);
+                }
+                _ => out.push(ch),
+            }
+        }
+    }
+}
+
+/// tmux style attributes equivalent to one SGR parameter list
+fn tmux_styles(params: &str) -> Vec<String> {
+    let params: Vec<&str> = params.split(';').collect();
+    let mut styles = Vec::new();
+    let mut idx = 0;
+
+    while idx < params.len() {
+        let param = params[idx];
+        idx += 1;
+        // 冒号子参数只有下划线样式 (4:3 波浪线等) 在 tmux 中有对应
+        if let Some(("4", variant)) = param.split_once(':') {
+            styles.extend(underscore_style(variant).map(str::to_string));
+            continue;
+        }
+        // 空参数等同于 0 (`ESC[m` 即重置)
+        let Ok(code) = (if param.is_empty() {
+            Ok(0)
+        } else {
+            param.parse::<u16>()
+        }) else {
+            continue;
+        };
+        let style = match code {
+            0 => "default".to_string(),
+            1 => "bold".to_string(),
+            2 => "dim".to_string(),
+            3 => "italics".to_string(),
+            4 => "underscore".to_string(),
+            5 | 6 => "blink".to_string(),
+            7 => "reverse".to_string(),
+            8 => "hidden".to_string(),
+            9 => "strikethrough".to_string(),
+            21 => "double-underscore".to_string(),
+            22 => "nobold,nodim".to_string(),
+            23 => "noitalics".to_string(),
+            24 => "nounderscore".to_string(),
+            25 => "noblink".to_string(),
+            27 => "noreverse".to_string(),
+            28 => "nohidden".to_string(),
+            29 => "nostrikethrough".to_string(),
+            53 => "overline".to_string(),
+            55 => "nooverline".to_string(),
+            30..=37 => format!("fg=colour{}", code - 30),
+            90..=97 => format!("fg=colour{}", code - 90 + 8),
+            40..=47 => format!("bg=colour{}", code - 40),
+            100..=107 => format!("bg=colour{}", code - 100 + 8),
+            39 => "fg=default".to_string(),
+            49 => "bg=default".to_string(),
+            59 => "us=default".to_string(),
+            38 | 48 | 58 => {
+                let key = match code {
+                    38 => "fg",
+                    48 => "bg",
+                    _ => "us",
+                };
+                let rest: Vec<u16> = params[idx..]
+                    .iter()
+                    .take(4)
+                    .map_while(|value| value.parse().ok())
+                    .collect();
+                match rest.as_slice() {
+                    [5, n, ..] => {
+                        idx += 2;
+                        format!("{key}=colour{n}")
+                    }
+                    [2, r, g, b, ..] => {
+                        idx += 4;
+                        format!("{key}=#{r:02x}{g:02x}{b:02x}")
+                    }
+                    _ => continue,
+                }
+            }
+            _ => continue,
+        };
+        styles.push(style);
+    }
+
+    styles
+}
+
+/// tmux attribute for an `ESC[4:<variant>m` underline style
+fn underscore_style(variant: &str) -> Option<&'static str> {
+    match variant {
+        "0" => Some("nounderscore"),
+        "1" => Some("underscore"),
+        "2" => Some("double-underscore"),
+        "3" => Some("curly-underscore"),
+        "4" => Some("dotted-underscore"),
+        "5" => Some("dashed-underscore"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINE: &str = "\x1b[38;2;136;192;208m\x1b[48;5;24m50%\x1b[0m #1 $(x)\\";
+
+    #[test]
+    fn tmux_target_translates_sgr_to_styles() {
+        assert_eq!(
+            OutputTarget::Tmux.apply(LINE),
+            "#[fg=#88c0d0,bg=colour24]50%#[default] ##1 $(x)\\"
+        );
+        assert_eq!(
+            OutputTarget::Tmux.apply("\x1b[1;31mA\x1b[39;22m\x1b]8;;u\x07B"),
+            "#[bold,fg=colour1]A#[fg=default,nobold,nodim]B"
+        );
+        assert_eq!(
+            OutputTarget::Tmux.apply("\x1b[4:3;53;58;5;1mX\x1b[;9mY"),
+            "#[curly-underscore,overline,us=colour1]X#[default,strikethrough]Y"
+        );
+    }
+
+    #[test]
+    fn sgr_emits_target_native_sequences() {
+        let params = "38;2;136;192;208";
+        assert_eq!(OutputTarget::Ansi.sgr(params), "\x1b[38;2;136;192;208m");
+        assert_eq!(OutputTarget::Tmux.sgr(params), "#[fg=#88c0d0]");
+        assert_eq!(OutputTarget::Zsh.reset(), "%{\x1b[0m%}");
+        assert_eq!(OutputTarget::Bash.reset(), "\\[\x1b[0m\\]");
+        assert_eq!(OutputTarget::Tmux.reset(), "#[default]");
+    }
+
+    #[test]
+    fn shell_targets_wrap_escapes_as_zero_width() {
+        assert_eq!(
+            OutputTarget::Zsh.apply(LINE),
+            "%{\x1b[38;2;136;192;208m\x1b[48;5;24m%}50%%%{\x1b[0m%} #1 $(x)\\"
+        );
+        assert_eq!(
+            OutputTarget::Bash.apply(LINE),
+            "\\[\x1b[38;2;136;192;208m\x1b[48;5;24m\\]50%\\[\x1b[0m\\] #1 \\\\$(x)\\\\\\\\"
+        );
+        assert_eq!(OutputTarget::Ansi.apply(LINE), LINE);
+    }
+}
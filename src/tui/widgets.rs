@@ -35,7 +35,7 @@ pub struct WidgetFile {
 pub struct WidgetEntry {
     pub name: String,
     pub enabled: bool,
-    pub kind: String, // "static" | "api"
+    pub kind: String, // "static" | "api" | "command" | "file"
     pub row: u32,
     pub col: u32,
 }
@@ -116,6 +116,8 @@ fn parse_file(path: &Path) -> Option<WidgetFile> {
             kind: match cfg.kind {
                 claude_code_statusline_pro::config::WidgetType::Static => "static".to_string(),
                 claude_code_statusline_pro::config::WidgetType::Api => "api".to_string(),
+                claude_code_statusline_pro::config::WidgetType::Command => "command".to_string(),
+                claude_code_statusline_pro::config::WidgetType::File => "file".to_string(),
             },
             row: cfg.row,
             col: cfg.col,
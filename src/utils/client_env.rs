@@ -0,0 +1,76 @@
+//! Environment of the process the statusline renders for
+//!
+//! In-process renders read the process environment. The daemon renders for
+//! many clients, so each request carries its client's variables and every
+//! lookup on the render path (terminal detection, `${VAR}` substitution,
+//! widget detection, spawned commands) goes through [`ClientEnv`].
+
+use std::collections::HashMap;
+use std::env::{self, VarError};
+use std::process::Command;
+use std::sync::Arc;
+
+/// Variables visible to one render
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientEnv {
+    /// Variables forwarded by a client; `None` reads the process environment
+    forwarded: Option<Arc<HashMap<String, String>>>,
+}
+
+impl ClientEnv {
+    /// Variables captured from another process
+    #[must_use]
+    pub fn forwarded(vars: HashMap<String, String>) -> Self {
+        Self {
+            forwarded: Some(Arc::new(vars)),
+        }
+    }
+
+    /// Capture the process environment for forwarding (non-UTF-8 entries are skipped)
+    #[must_use]
+    pub fn capture() -> HashMap<String, String> {
+        env::vars_os()
+            .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+            .collect()
+    }
+
+    /// Same contract as [`std::env::var`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`VarError::NotPresent`] when `key` is unset and
+    /// [`VarError::NotUnicode`] for non-UTF-8 process variables.
+    pub fn var(&self, key: &str) -> Result<String, VarError> {
+        self.forwarded.as_ref().map_or_else(
+            || env::var(key),
+            |vars| vars.get(key).cloned().ok_or(VarError::NotPresent),
+        )
+    }
+
+    /// Whether this is the environment of the current process, whose tty is the client's
+    #[must_use]
+    pub const fn is_process(&self) -> bool {
+        self.forwarded.is_none()
+    }
+
+    /// Let `command` inherit this environment instead of the current process's
+    pub fn apply_to(&self, command: &mut Command) {
+        if let Some(vars) = &self.forwarded {
+            command.env_clear().envs(vars.iter());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forwarded_env_shadows_process_env() {
+        let env = ClientEnv::forwarded(HashMap::from([("TERM".to_string(), "xterm".to_string())]));
+        assert_eq!(env.var("TERM").as_deref(), Ok("xterm"));
+        assert_eq!(env.var("PATH"), Err(VarError::NotPresent));
+        assert!(!env.is_process());
+        assert!(ClientEnv::default().is_process());
+    }
+}
@@ -6,7 +6,6 @@
 use crate::core::InputData;
 use crate::utils::home_dir;
 use serde_json::Value;
-use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -56,14 +55,16 @@ pub fn resolve_effort_level(input: &InputData) -> Option<EffortLevel> {
         return None;
     }
 
-    env::var(ENV_KEY)
+    input
+        .env
+        .var(ENV_KEY)
         .ok()
         .and_then(|value| EffortLevel::from_value(&value))
         .or_else(|| resolve_settings_effort(input))
 }
 
 fn has_claude_code_context(input: &InputData) -> bool {
-    env::var_os(CLAUDECODE_ENV_KEY).is_some()
+    input.env.var(CLAUDECODE_ENV_KEY).is_ok()
         || input.extra.get("version").and_then(Value::as_str).is_some()
 }
 
@@ -110,6 +111,7 @@ mod tests {
     use crate::core::{InputData, WorkspaceInfo};
     use anyhow::Result;
     use serial_test::serial;
+    use std::env;
     use std::ffi::OsString;
     use tempfile::tempdir;
 
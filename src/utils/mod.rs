@@ -1,12 +1,16 @@
 //! 实用工具模块
 //!
-//! 包含跨平台 home 目录解析和模型 ID 解析等辅助函数。
+//! 包含跨平台 home 目录解析、模型 ID 解析和终端显示宽度计算等辅助函数。
 
+pub mod client_env;
 pub mod effort;
 pub mod model_parser;
+pub mod process;
+pub mod width;
 
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 /// 获取用户主目录，优先尊重 `HOME` 环境变量。
 ///
@@ -43,6 +47,24 @@ pub fn home_dir() -> Option<PathBuf> {
     dirs::home_dir()
 }
 
+/// Remove files in `dir` not modified within `retention`
+pub fn prune_stale_files(dir: &Path, retention: Duration) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > retention);
+        if stale {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
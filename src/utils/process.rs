@@ -0,0 +1,135 @@
+//! External process helpers.
+//!
+//! 以超时方式运行外部命令，供自定义组件和命令类 widget 共用。
+
+use anyhow::{anyhow, Context, Result};
+use std::io::{Read, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Captured result of a finished child process
+#[derive(Debug, Clone)]
+pub struct ProcessOutput {
+    pub status: ExitStatus,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Run `command` to completion, killing it once `timeout` elapses.
+///
+/// `stdin` is written and stdout/stderr are drained on helper threads, so a
+/// child that never reads its input or fills a pipe first cannot block past
+/// the timeout.
+///
+/// # Errors
+///
+/// Returns an error when the program cannot be spawned or when it does not
+/// exit before the timeout.
+pub fn run_with_timeout(
+    mut command: Command,
+    stdin: Option<&[u8]>,
+    timeout: Duration,
+) -> Result<ProcessOutput> {
+    command
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let program = command.get_program().to_string_lossy().to_string();
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("failed to spawn '{program}'"))?;
+
+    let stdout_reader = child.stdout.take().map(spawn_reader);
+    let stderr_reader = child.stderr.take().map(spawn_reader);
+    let stdin_writer = stdin.zip(child.stdin.take()).map(|(payload, mut pipe)| {
+        let payload = payload.to_vec();
+        thread::spawn(move || {
+            // 子进程可能不读取 stdin，写入失败（broken pipe）不视为错误
+            let _ = pipe.write_all(&payload);
+        })
+    });
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            // 子进程退出后管道关闭，写线程随 broken pipe 结束，不等待它
+            drop(stdin_writer);
+            return Err(anyhow!(
+                "'{program}' timed out after {}ms",
+                timeout.as_millis()
+            ));
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    if let Some(writer) = stdin_writer {
+        let _ = writer.join();
+    }
+
+    Ok(ProcessOutput {
+        status,
+        stdout: join_reader(stdout_reader),
+        stderr: join_reader(stderr_reader),
+    })
+}
+
+fn spawn_reader<R: Read + Send + 'static>(mut source: R) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = source.read_to_end(&mut buffer);
+        String::from_utf8_lossy(&buffer).into_owned()
+    })
+}
+
+fn join_reader(handle: Option<thread::JoinHandle<String>>) -> String {
+    handle
+        .and_then(|handle| handle.join().ok())
+        .unwrap_or_default()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captures_stdout_and_stdin() -> Result<()> {
+        let command = Command::new("cat");
+        let output = run_with_timeout(command, Some(b"hello"), Duration::from_secs(5))?;
+        assert!(output.status.success());
+        assert_eq!(output.stdout, "hello");
+        Ok(())
+    }
+
+    #[test]
+    fn timeout_holds_when_child_ignores_large_stdin() {
+        let mut command = Command::new("sleep");
+        command.arg("5");
+        let payload = vec![b'x'; 1024 * 1024];
+        let started = Instant::now();
+        let result = run_with_timeout(command, Some(&payload), Duration::from_millis(100));
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn kills_process_after_timeout() {
+        let mut command = Command::new("sleep");
+        command.arg("5");
+        let started = Instant::now();
+        let result = run_with_timeout(command, None, Duration::from_millis(100));
+        assert!(result.is_err());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,229 @@
+//! Terminal display width helpers.
+//!
+//! Measures text the way a terminal lays it out: ANSI escape sequences take
+//! no columns, CJK and emoji take two, Nerd Font glyphs (private use area)
+//! take one.
+
+use unicode_width::UnicodeWidthStr;
+
+/// Ellipsis appended to truncated text
+pub const ELLIPSIS: &str = "…";
+
+/// SGR reset appended when truncated text carried styles
+pub const ANSI_RESET: &str = "\x1b[0m";
+
+/// A run of printable text or a single ANSI escape sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnsiSegment<'a> {
+    Text(&'a str),
+    Escape(&'a str),
+}
+
+/// Split `text` into printable runs and ANSI escape sequences (CSI, OSC and
+/// two-byte escapes). An unterminated sequence runs to the end of the text.
+#[must_use]
+pub fn ansi_segments(text: &str) -> Vec<AnsiSegment<'_>> {
+    let bytes = text.as_bytes();
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        if bytes[idx] != 0x1b {
+            idx += 1;
+            continue;
+        }
+        if start < idx {
+            segments.push(AnsiSegment::Text(&text[start..idx]));
+        }
+        let end = escape_end(bytes, idx);
+        segments.push(AnsiSegment::Escape(&text[idx..end]));
+        start = end;
+        idx = end;
+    }
+    if start < bytes.len() {
+        segments.push(AnsiSegment::Text(&text[start..]));
+    }
+
+    segments
+}
+
+/// Byte offset just past the escape sequence starting at `start`
+fn escape_end(bytes: &[u8], start: usize) -> usize {
+    let len = bytes.len();
+    match bytes.get(start + 1) {
+        // CSI: ESC [ 参数… 终止字节 0x40..=0x7E
+        Some(b'[') => (start + 2..len)
+            .find(|&idx| (0x40..=0x7e).contains(&bytes[idx]))
+            .map_or(len, |idx| idx + 1),
+        // OSC: ESC ] … BEL 或 ESC \
+        Some(b']') => (start + 2..len)
+            .find_map(|idx| match bytes[idx] {
+                0x07 => Some(idx + 1),
+                0x1b if bytes.get(idx + 1) == Some(&b'\\') => Some(idx + 2),
+                _ => None,
+            })
+            .unwrap_or(len),
+        // 其余两字节转义;不截断 UTF-8 字符
+        Some(next) if next.is_ascii() => start + 2,
+        _ => start + 1,
+    }
+}
+
+/// Columns occupied by `text`, ignoring ANSI escape sequences
+#[must_use]
+pub fn display_width(text: &str) -> usize {
+    ansi_segments(text)
+        .into_iter()
+        .map(|segment| match segment {
+            AnsiSegment::Text(run) => run.width(),
+            AnsiSegment::Escape(_) => 0,
+        })
+        .sum()
+}
+
+/// `text` with every ANSI escape sequence removed
+#[must_use]
+pub fn strip_ansi(text: &str) -> String {
+    ansi_segments(text)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            AnsiSegment::Text(run) => Some(run),
+            AnsiSegment::Escape(_) => None,
+        })
+        .collect()
+}
+
+/// Truncate `text` to `max_width` columns without breaking ANSI escape sequences
+///
+/// Escape sequences before the cut are kept intact and take no columns. A cut
+/// result ends with [`ELLIPSIS`] and, when it carries any escape sequence, with
+/// [`ANSI_RESET`] so colors do not leak into whatever follows.
+#[must_use]
+pub fn truncate_ansi(text: &str, max_width: usize) -> String {
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+
+    let budget = max_width.saturating_sub(ELLIPSIS.width());
+    let mut result = String::with_capacity(text.len());
+    let mut used = 0;
+    let mut styled = false;
+
+    'segments: for segment in ansi_segments(text) {
+        match segment {
+            AnsiSegment::Escape(sequence) => {
+                result.push_str(sequence);
+                styled = true;
+            }
+            AnsiSegment::Text(run) => {
+                let mut kept = 0;
+                for (idx, ch) in run.char_indices() {
+                    let end = idx + ch.len_utf8();
+                    if used + run[..end].width() > budget {
+                        result.push_str(&run[..kept]);
+                        break 'segments;
+                    }
+                    kept = end;
+                }
+                result.push_str(run);
+                used += run.width();
+            }
+        }
+    }
+
+    result.push_str(ELLIPSIS);
+    if styled {
+        result.push_str(ANSI_RESET);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn display_width_ignores_escapes_and_counts_wide_glyphs() {
+        assert_eq!(display_width("main"), 4);
+        assert_eq!(display_width("\x1b[38;2;1;2;3mmain\x1b[0m"), 4);
+        assert_eq!(display_width("项目"), 4);
+        assert_eq!(display_width("⚡1"), 3);
+        assert_eq!(display_width("\u{e0a0} dev"), 5);
+        assert_eq!(
+            display_width("\x1b]8;;https://example.com\x07link\x1b]8;;\x07"),
+            4
+        );
+    }
+
+    #[test]
+    fn truncate_plain_text_respects_display_width() {
+        assert_eq!(truncate_ansi("feature/login", 8), "feature…");
+        assert_eq!(truncate_ansi("中文项目名称", 7), "中文项…");
+        assert_eq!(truncate_ansi("short", 8), "short");
+        assert!(display_width(&truncate_ansi("中文项目名称", 6)) <= 6);
+    }
+
+    #[test]
+    fn truncate_ansi_keeps_escapes_and_resets() {
+        let colored = "\x1b[31m红色分支\x1b[0m feature";
+        assert_eq!(truncate_ansi(colored, 40), colored);
+        assert_eq!(truncate_ansi(colored, 5), "\x1b[31m红色…\x1b[0m");
+        assert_eq!(truncate_ansi("plain text", 6), "plain…");
+        assert_eq!(truncate_ansi("\x1b[32mok\x1b[0m", 0), "");
+    }
+
+    fn escapes(text: &str) -> Vec<&str> {
+        ansi_segments(text)
+            .into_iter()
+            .filter_map(|segment| match segment {
+                AnsiSegment::Escape(sequence) => Some(sequence),
+                AnsiSegment::Text(_) => None,
+            })
+            .collect()
+    }
+
+    /// 混合 ASCII、CJK、emoji、Nerd Font 字形与颜色转义的输入
+    fn mixed_text() -> impl Strategy<Value = String> {
+        let part = prop_oneof![
+            "[a-z /_-]{1,6}",
+            "[\u{4e00}-\u{4e2f}]{1,4}",
+            "[⚡🌿📁✅]{1,2}",
+            "[\u{e0a0}\u{f07c}]{1,2}",
+            (30u8..38).prop_map(|code| format!("\x1b[{code}m")),
+            (0u8..=255, 0u8..=255, 0u8..=255)
+                .prop_map(|(r, g, b)| format!("\x1b[38;2;{r};{g};{b}m")),
+            Just(ANSI_RESET.to_string()),
+        ];
+        prop::collection::vec(part, 0..12).prop_map(|parts| parts.concat())
+    }
+
+    proptest! {
+        #[test]
+        fn truncate_ansi_fits_width(text in mixed_text(), max_width in 0usize..30) {
+            let truncated = truncate_ansi(&text, max_width);
+            prop_assert!(display_width(&truncated) <= max_width);
+
+            if display_width(&text) <= max_width {
+                prop_assert_eq!(&truncated, &text);
+            } else if max_width > 0 {
+                // 可见文本是原文的前缀,转义序列原样保留
+                let visible = strip_ansi(&truncated);
+                let kept = visible.strip_suffix(ELLIPSIS).unwrap_or(&visible);
+                prop_assert!(strip_ansi(&text).starts_with(kept));
+
+                let original = escapes(&text);
+                let mut kept_escapes = escapes(&truncated);
+                if !kept_escapes.is_empty() {
+                    prop_assert!(truncated.ends_with(ANSI_RESET));
+                    kept_escapes.pop();
+                }
+                prop_assert!(original.starts_with(&kept_escapes));
+            }
+        }
+    }
+}
@@ -12,6 +12,32 @@ fn cli_mock_scenario_runs() {
     assert.stdout(predicate::str::is_empty().not());
 }
 
+#[test]
+#[allow(deprecated)]
+fn cli_json_output_lists_components() {
+    let storage_dir = tempdir().expect("create temp storage");
+    let mut cmd = Command::cargo_bin("claude-code-statusline-pro").expect("binary available");
+    let output = cmd
+        .env("STATUSLINE_STORAGE_PATH", storage_dir.path())
+        .args(["--mock", "dev", "--output", "json", "--force-text"])
+        .output()
+        .expect("run binary");
+    assert!(output.status.success());
+
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid json");
+    let components = value["components"].as_array().expect("components array");
+    assert_eq!(components[0]["name"], "project");
+    assert!(components
+        .iter()
+        .all(|component| component["visible"].is_boolean()));
+    assert!(value["rows"].is_array());
+    assert_eq!(
+        value["timing"]["components"].as_array().map(Vec::len),
+        Some(components.len())
+    );
+    assert!(!String::from_utf8_lossy(&output.stdout).contains('\x1b'));
+}
+
 #[test]
 #[allow(deprecated)]
 fn cli_config_init_force_creates_files() {
@@ -44,3 +70,113 @@ fn cli_config_init_force_creates_files() {
     let components_dir = config_path.parent().unwrap().join("components");
     assert!(components_dir.exists(), "components directory missing");
 }
+
+#[test]
+#[allow(deprecated)]
+fn cli_report_aggregates_sessions_as_csv() {
+    let storage_dir = tempdir().expect("create temp storage");
+    let sessions_dir = storage_dir
+        .path()
+        .join("projects")
+        .join("hash-a")
+        .join("statusline-pro")
+        .join("sessions");
+    fs::create_dir_all(&sessions_dir).expect("create sessions dir");
+    for (id, time, cost) in [
+        ("s1", "2026-03-01T12:00:00Z", 1.25),
+        ("s2", "2026-03-05T12:00:00Z", 2.0),
+    ] {
+        let snapshot = serde_json::json!({
+            "meta": {"session_id": id, "project_path": "/work/a", "last_update_time": time},
+            "history": {"cost": {"total": {"total_cost_usd": cost, "total_lines_added": 3}}}
+        });
+        fs::write(
+            sessions_dir.join(format!("{id}.json")),
+            snapshot.to_string(),
+        )
+        .expect("write snapshot");
+    }
+
+    let mut cmd = Command::cargo_bin("claude-code-statusline-pro").expect("binary available");
+    cmd.env("STATUSLINE_STORAGE_PATH", storage_dir.path())
+        .args([
+            "report",
+            "--group-by",
+            "project",
+            "--format",
+            "csv",
+            "--since",
+            "2026-03-02",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("/work/a,1,2.0000,0,3,0,0,0,0,0"));
+}
+
+#[cfg(unix)]
+#[test]
+#[allow(deprecated)]
+fn cli_daemon_serves_default_invocation_and_reloads_config() {
+    use std::process::Stdio;
+    use std::time::{Duration, Instant};
+
+    struct KillOnDrop(std::process::Child);
+    impl Drop for KillOnDrop {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    let temp = tempdir().expect("create temp dir");
+    let daemon_home = temp.path().join("daemon-home");
+    let config_dir = daemon_home.join(".claude").join("statusline-pro");
+    fs::create_dir_all(&config_dir).expect("create config dir");
+    let write_config = |icon: &str| {
+        fs::write(
+            config_dir.join("config.toml"),
+            format!(
+                "[terminal]\nforce_text = true\n[components]\norder = [\"project\"]\n\
+                 [components.project]\ntext_icon = \"{icon}\"\n"
+            ),
+        )
+        .expect("write config");
+    };
+    write_config("D1");
+
+    let socket = temp.path().join("daemon.sock");
+    let binary = assert_cmd::cargo::cargo_bin("claude-code-statusline-pro");
+    let _daemon = KillOnDrop(
+        std::process::Command::new(&binary)
+            .arg("daemon")
+            .env("HOME", &daemon_home)
+            .env("STATUSLINE_STORAGE_PATH", temp.path().join("storage"))
+            .env("STATUSLINE_DAEMON_SOCKET", &socket)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("spawn daemon"),
+    );
+    let started = Instant::now();
+    while !socket.exists() && started.elapsed() < Duration::from_secs(10) {
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    // 客户端用另一个 HOME:输出里出现 daemon 配置的图标,说明走了 daemon
+    let render = || {
+        let output = Command::cargo_bin("claude-code-statusline-pro")
+            .expect("binary available")
+            .env("HOME", temp.path())
+            .env("STATUSLINE_STORAGE_PATH", temp.path().join("storage"))
+            .env("STATUSLINE_DAEMON_SOCKET", &socket)
+            .write_stdin(r#"{"cwd":"/work/demo","workspace":{"current_dir":"/work/demo","project_dir":"/work/demo"}}"#)
+            .output()
+            .expect("run client");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+
+    assert_eq!(render(), "D1 demo");
+    write_config("D22");
+    assert_eq!(render(), "D22 demo");
+}
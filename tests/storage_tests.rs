@@ -187,3 +187,60 @@ async fn test_snapshot_updates_tokens_from_transcript() -> anyhow::Result<()> {
     drop(temp_dir);
     Ok(())
 }
+
+#[tokio::test]
+async fn test_snapshot_folds_transcript_aggregates_incrementally() -> anyhow::Result<()> {
+    let _guard = storage_test_mutex().lock().await;
+    let project_id = "status-project";
+    let temp_dir = init_with_temp_storage(project_id).await?;
+
+    let session_id = "status-session";
+    let transcript_path = temp_dir.path().join("status-session.jsonl");
+    let path_str = transcript_path.to_string_lossy().to_string();
+
+    let mut file = fs::File::create(&transcript_path)?;
+    writeln!(
+        file,
+        r#"{{"type":"assistant","uuid":"msg-1","message":{{"stop_reason":"tool_use","content":[{{"type":"tool_use","name":"Bash"}}],"usage":{{"input_tokens":10,"output_tokens":5}}}}}}"#
+    )?;
+    file.flush()?;
+
+    let input = serde_json::json!({
+        "session_id": session_id,
+        "transcript_path": transcript_path,
+    });
+    storage::update_session_snapshot(&input).await?;
+
+    let aggregates = storage::get_transcript_aggregates(session_id, &path_str)
+        .await?
+        .expect("aggregates should be folded into the snapshot");
+    assert_eq!(aggregates.status.stop_reason.as_deref(), Some("tool_use"));
+    assert_eq!(aggregates.status.recent_tool(), Some("Bash"));
+    assert_eq!(aggregates.tool_uses, 1);
+
+    // 追加的行尚未被处理时快照不可用
+    let mut file = fs::OpenOptions::new().append(true).open(&transcript_path)?;
+    writeln!(
+        file,
+        r#"{{"type":"user","uuid":"user-1","message":{{"content":"done"}}}}"#
+    )?;
+    file.flush()?;
+    assert!(storage::get_transcript_aggregates(session_id, &path_str)
+        .await?
+        .is_none());
+
+    storage::update_session_snapshot(&input).await?;
+    let aggregates = storage::get_transcript_aggregates(session_id, &path_str)
+        .await?
+        .expect("aggregates should follow appended lines");
+    assert_eq!(aggregates.status.last_entry_type.as_deref(), Some("user"));
+    assert_eq!(aggregates.status.stop_reason.as_deref(), Some("tool_use"));
+    assert_eq!(aggregates.assistant_turns, 1);
+    assert_eq!(aggregates.user_prompts, 1);
+    assert_eq!(aggregates.usage.output, 5);
+
+    std::env::remove_var("STATUSLINE_STORAGE_PATH");
+    reset_project_resolver();
+    drop(temp_dir);
+    Ok(())
+}